@@ -0,0 +1,52 @@
+//! Device emulator: runs the serial, UDP and TCP devices described by a script file,
+//! see `app_lib::emulator` for the format.
+//!
+//! Usage: emulator <script.toml|script.json>
+
+use app_lib::emulator::script::EmulatorScript;
+use app_lib::emulator::Emulator;
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt().init();
+
+    let Some(script_path) = env::args().nth(1).map(PathBuf::from) else {
+        println!("Usage: emulator <script.toml|script.json>");
+        return;
+    };
+    let script = match EmulatorScript::load(&script_path) {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let duration_secs = script.duration_secs;
+    let emulator = match Emulator::start(script).await {
+        Ok(emulator) => emulator,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    match duration_secs {
+        Some(secs) => {
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(secs)) => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        }
+        None => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+
+    println!("Shutting down");
+    emulator.stop();
+    // Give delayed replies already on their way a moment to go out
+    tokio::time::sleep(Duration::from_millis(200)).await;
+}
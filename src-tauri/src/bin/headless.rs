@@ -0,0 +1,307 @@
+//! Headless runner: opens connections, bridges them, streams scenarios and records
+//! captures from a config file, without starting the webview.
+//!
+//! Usage: headless <config.toml|config.json>
+
+use app_lib::general::scenario::Scenario;
+use app_lib::general::simulation_commands::decode_simulation_output;
+use app_lib::packet::Packet;
+use app_lib::storage::file_logger::{set_connection_log_settings, LOG_DIR};
+use app_lib::transport::connection_manager::Manager;
+use app_lib::transport::instrumented::Instrumented;
+use app_lib::transport::schemas::{SchemaSource, SCHEMAS};
+use app_lib::transport::serial::SerialTransport;
+use app_lib::transport::udp::UdpTransport;
+use app_lib::transport::zmq::ZmqTransport;
+use app_lib::transport::{ConnectionInfo, ConnectionType, ShareInfo, Transport};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct HeadlessConfig {
+    /// Stop after this many seconds, run until Ctrl+C when unset
+    duration_secs: Option<u64>,
+    /// Print packet statistics every N seconds, 0 disables
+    #[serde(default)]
+    stats_interval_secs: u64,
+    log_dir: Option<String>,
+    /// Simulator executable used to run `scenario`
+    sim_binary: Option<PathBuf>,
+    /// Scenario file to simulate and stream once all connections are open
+    scenario: Option<PathBuf>,
+    /// Protobuf schemas for connections decoding runtime-loaded message types
+    #[serde(default)]
+    schemas: Vec<SchemaSource>,
+    #[serde(default)]
+    connections: Vec<ConnectionInfo>,
+    #[serde(default)]
+    bridges: Vec<ShareInfo>,
+    #[serde(default)]
+    captures: Vec<CaptureConfig>,
+}
+
+/// Record every packet received on `connection_id` as JSON lines in `path`
+#[derive(Serialize, Deserialize, Debug)]
+struct CaptureConfig {
+    connection_id: String,
+    path: PathBuf,
+}
+
+fn load_config(path: &Path) -> Result<HeadlessConfig, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config {:?}: {}", path, e))?;
+    let is_toml = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("toml"))
+        .unwrap_or(false);
+    if is_toml {
+        toml::from_str(&content).map_err(|e| format!("TOML parse error: {e}"))
+    } else {
+        serde_json::from_str(&content).map_err(|e| format!("Deserialization error: {e}"))
+    }
+}
+
+/// Spawn a writer that appends timestamped capture lines to `path`
+fn spawn_capture(path: PathBuf) -> Result<mpsc::UnboundedSender<String>, String> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open capture file {:?}: {}", path, e))?;
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    tokio::spawn(async move {
+        while let Some(line) = rx.recv().await {
+            if let Err(e) = writeln!(file, "{}", line) {
+                tracing::error!("Failed to write capture {:?}: {}", path, e);
+                break;
+            }
+        }
+    });
+    Ok(tx)
+}
+
+fn packet_handler(
+    manager: &Manager,
+    capture: Option<mpsc::UnboundedSender<String>>,
+) -> impl FnMut(String, Packet, Option<SocketAddr>) + Send + 'static {
+    let picture = manager.picture.clone();
+    let responders = manager.responders.clone();
+    move |conn_id: String, packet: Packet, source: Option<SocketAddr>| {
+        picture.offer_packet(&conn_id, source, &packet);
+        responders.offer_packet(&conn_id, source, &packet);
+        if let Some(capture) = &capture {
+            if let Ok(json) = serde_json::to_string(&packet) {
+                let timestamp = chrono::Utc::now()
+                    .format("%Y-%m-%d %H:%M:%S%.3f")
+                    .to_string();
+                let line = match source {
+                    Some(source) => format!("[{}] [{}] {}", timestamp, source, json),
+                    None => format!("[{}] {}", timestamp, json),
+                };
+                let _ = capture.send(line);
+            }
+        }
+    }
+}
+
+async fn open_connection(
+    manager: &Manager,
+    conn: &ConnectionInfo,
+    capture: Option<mpsc::UnboundedSender<String>>,
+) -> Result<(), String> {
+    set_connection_log_settings(&conn.id, conn.logging.clone());
+    let transport: Arc<dyn Transport + Send + Sync> = match conn.connection_type {
+        Some(ConnectionType::Serial) => {
+            let port = conn.port.clone().ok_or("Serial connection without a port")?;
+            let baud_rate = conn.baud_rate.ok_or("Serial connection without a baud rate")?;
+            let mut transport =
+                Instrumented::new(conn.id.clone(), SerialTransport::new(port, baud_rate));
+            transport
+                .start::<Packet>(packet_handler(manager, capture))
+                .await?;
+            Arc::new(transport)
+        }
+        Some(ConnectionType::Udp) => {
+            let local_addr: SocketAddr = conn
+                .local_addr
+                .as_ref()
+                .ok_or("UDP connection without a local address")?
+                .parse()
+                .map_err(|e| format!("Invalid address: {}", e))?;
+            if manager.is_socket_address_in_use(local_addr).await {
+                return Err(format!("Socket address {} is already in use", local_addr));
+            }
+            let udp = UdpTransport::with_options(local_addr, conn.udp_options.clone()).await?;
+            for peer in conn.remote_addr.iter().chain(&conn.peers) {
+                udp.peers.add(
+                    peer.parse()
+                        .map_err(|e| format!("Invalid remote address: {}", e))?,
+                );
+            }
+            udp.peers.set_reply_to_sender(conn.reply_to_sender);
+            udp.tracks.set_config(conn.tracks.clone());
+            let mut transport = Instrumented::new(conn.id.clone(), udp);
+            transport
+                .start::<Packet>(packet_handler(manager, capture))
+                .await?;
+            Arc::new(transport)
+        }
+        Some(ConnectionType::Zmq) => {
+            let options = conn
+                .zmq
+                .clone()
+                .ok_or("ZeroMQ connection without socket options")?;
+            let mut transport = Instrumented::new(conn.id.clone(), ZmqTransport::new(options)?);
+            transport
+                .start::<Packet>(packet_handler(manager, capture))
+                .await?;
+            Arc::new(transport)
+        }
+        None => return Err("Connection has no type".to_string()),
+    };
+    if let Some(decoders) = transport.decoders() {
+        decoders.set_config(conn.decoders.clone())?;
+    }
+    manager.add_connection(conn.id.clone(), transport).await?;
+    manager
+        .responders
+        .set_rules(&conn.id, conn.responders.clone())
+}
+
+async fn run_scenario(
+    manager: &Manager,
+    scenario_path: &Path,
+    sim_binary: &Path,
+) -> Result<(), String> {
+    let scenario = Scenario::from_file(scenario_path)?;
+    let output = tokio::process::Command::new(sim_binary)
+        .arg("--json")
+        .arg(serde_json::to_string(&scenario.simulation).map_err(|e| e.to_string())?)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run simulator {:?}: {}", sim_binary, e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    let sim_results = decode_simulation_output(&output.stdout)?;
+    let run = manager
+        .run_scenario_streams(&scenario.name, scenario.stream_packets(&sim_results))
+        .await?;
+    println!(
+        "Scenario '{}' running: {} connection streams, {} UDP streams",
+        run.name,
+        run.shares.len(),
+        run.simulation_streams.len()
+    );
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt().init();
+
+    let Some(config_path) = env::args().nth(1).map(PathBuf::from) else {
+        println!("Usage: headless <config.toml|config.json>");
+        return;
+    };
+    let config = match load_config(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    if let Some(log_dir) = &config.log_dir {
+        *LOG_DIR.lock().unwrap() = Some(log_dir.clone());
+    }
+
+    let manager = Manager::new();
+
+    let mut captures = HashMap::new();
+    for capture in &config.captures {
+        match spawn_capture(capture.path.clone()) {
+            Ok(tx) => {
+                captures.insert(capture.connection_id.clone(), tx);
+            }
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+
+    for schema in &config.schemas {
+        match SCHEMAS.load(schema.clone()) {
+            Ok(messages) => println!("Loaded {} ({} messages)", schema.path, messages.len()),
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+
+    for conn in &config.connections {
+        match open_connection(&manager, conn, captures.get(&conn.id).cloned()).await {
+            Ok(()) => println!("Opened connection {}", conn.id),
+            Err(e) => eprintln!("Failed to open connection {}: {}", conn.id, e),
+        }
+    }
+
+    for bridge in &config.bridges {
+        match manager
+            .share_data_between_ids(&bridge.from_id, &bridge.to_id, bridge.interval_ms)
+            .await
+        {
+            Ok(_) => println!("Bridging {} -> {}", bridge.from_id, bridge.to_id),
+            Err(e) => eprintln!(
+                "Failed to bridge {} -> {}: {}",
+                bridge.from_id, bridge.to_id, e
+            ),
+        }
+    }
+
+    if let Some(scenario_path) = &config.scenario {
+        let sim_binary = config
+            .sim_binary
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("sim"));
+        if let Err(e) = run_scenario(&manager, scenario_path, &sim_binary).await {
+            eprintln!("Failed to run scenario {:?}: {}", scenario_path, e);
+        }
+    }
+
+    let stats_manager = manager.clone();
+    let stats_interval = config.stats_interval_secs;
+    let stats_task = tokio::spawn(async move {
+        if stats_interval == 0 {
+            return;
+        }
+        let mut ticker = tokio::time::interval(Duration::from_secs(stats_interval));
+        loop {
+            ticker.tick().await;
+            for (id, (received, sent)) in stats_manager.get_connection_packet_counts().await {
+                println!("[stats] {}: received={} sent={}", id, received, sent);
+            }
+        }
+    });
+
+    match config.duration_secs {
+        Some(secs) => {
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(secs)) => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        }
+        None => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+
+    println!("Shutting down");
+    stats_task.abort();
+    let _ = manager.stop_scenario().await;
+    manager.stop_all().await;
+    // Give the logging and capture writers a moment to drain
+    tokio::time::sleep(Duration::from_millis(200)).await;
+}
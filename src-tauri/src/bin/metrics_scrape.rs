@@ -0,0 +1,81 @@
+//! Scraper stand-in: fetches `/metrics` from the OpenMetrics exporter and checks the
+//! exposition the way Prometheus would. Exits non-zero when the output is invalid.
+//!
+//! Usage: metrics_scrape [addr] (default 127.0.0.1:9464)
+
+use app_lib::control::metrics::{validate_openmetrics, CONTENT_TYPE};
+use std::env;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+async fn scrape(addr: &str) -> Result<(String, String), String> {
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+    let request = format!(
+        "GET /metrics HTTP/1.1\r\nHost: {}\r\nAccept: {}\r\nConnection: close\r\n\r\n",
+        addr, CONTENT_TYPE
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or("Malformed HTTP response")?;
+    let status = head.lines().next().unwrap_or_default();
+    if !status.contains(" 200 ") {
+        return Err(format!("Unexpected status: {}", status));
+    }
+    let content_type = head
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-type")
+                .then(|| value.trim().to_string())
+        })
+        .ok_or("Response has no Content-Type")?;
+    Ok((content_type, body.to_string()))
+}
+
+#[tokio::main]
+async fn main() {
+    let addr = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:9464".to_string());
+
+    let (content_type, body) = match scrape(&addr).await {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    if !content_type.starts_with("application/openmetrics-text") {
+        eprintln!("Unexpected Content-Type: {}", content_type);
+        std::process::exit(1);
+    }
+    match validate_openmetrics(&body) {
+        Ok(samples) => {
+            for sample in &samples {
+                let labels: Vec<String> = sample
+                    .labels
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect();
+                println!("{} [{}] {}", sample.name, labels.join(","), sample.value);
+            }
+            println!("OK: {} samples", samples.len());
+        }
+        Err(e) => {
+            eprintln!("Invalid exposition: {e}");
+            std::process::exit(1);
+        }
+    }
+}
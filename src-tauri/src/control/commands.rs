@@ -0,0 +1,101 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tauri::{AppHandle, State};
+use tokio::sync::Mutex;
+
+use crate::control::metrics::{MetricsExporter, MetricsExporterStatus};
+use crate::control::server::{ControlServer, ControlServerStatus};
+use crate::transport::connection_manager::Manager;
+
+const DEFAULT_CONTROL_ADDR: &str = "127.0.0.1:7878";
+const DEFAULT_METRICS_ADDR: &str = "127.0.0.1:9464";
+
+pub type ControlServerState = Arc<Mutex<Option<ControlServer>>>;
+pub type MetricsExporterState = Arc<Mutex<Option<MetricsExporter>>>;
+
+/// Start the localhost JSON-RPC control server. When `token` is set, clients must pass it
+/// as `?token=` in the WebSocket URL or call the `auth` method before anything else.
+/// Browser pages are refused unless their origin is in `allowed_origins`.
+#[tauri::command]
+pub async fn start_control_server(
+    state: State<'_, ControlServerState>,
+    app: AppHandle,
+    addr: Option<String>,
+    token: Option<String>,
+    allowed_origins: Option<Vec<String>>,
+) -> Result<ControlServerStatus, String> {
+    let addr: SocketAddr = addr
+        .as_deref()
+        .unwrap_or(DEFAULT_CONTROL_ADDR)
+        .parse()
+        .map_err(|e| format!("Invalid address: {}", e))?;
+    let token = token.filter(|t| !t.is_empty());
+
+    let mut guard = state.lock().await;
+    if let Some(server) = guard.take() {
+        server.stop();
+    }
+    let server =
+        ControlServer::start(app, addr, token, allowed_origins.unwrap_or_default()).await?;
+    let status = server.status();
+    *guard = Some(server);
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn stop_control_server(state: State<'_, ControlServerState>) -> Result<(), String> {
+    if let Some(server) = state.lock().await.take() {
+        server.stop();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_control_server_status(
+    state: State<'_, ControlServerState>,
+) -> Result<Option<ControlServerStatus>, String> {
+    Ok(state.lock().await.as_ref().map(|server| server.status()))
+}
+
+/// Serve transport statistics in OpenMetrics format on `http://<addr>/metrics`
+#[tauri::command]
+pub async fn start_metrics_exporter(
+    state: State<'_, MetricsExporterState>,
+    manager: State<'_, Manager>,
+    addr: Option<String>,
+) -> Result<MetricsExporterStatus, String> {
+    let addr: SocketAddr = addr
+        .as_deref()
+        .unwrap_or(DEFAULT_METRICS_ADDR)
+        .parse()
+        .map_err(|e| format!("Invalid address: {}", e))?;
+
+    let mut guard = state.lock().await;
+    if let Some(exporter) = guard.take() {
+        exporter.stop();
+    }
+    let exporter = MetricsExporter::start(manager.inner().clone(), addr).await?;
+    let status = exporter.status();
+    *guard = Some(exporter);
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn stop_metrics_exporter(state: State<'_, MetricsExporterState>) -> Result<(), String> {
+    if let Some(exporter) = state.lock().await.take() {
+        exporter.stop();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_metrics_exporter_status(
+    state: State<'_, MetricsExporterState>,
+) -> Result<Option<MetricsExporterStatus>, String> {
+    Ok(state
+        .lock()
+        .await
+        .as_ref()
+        .map(|exporter| exporter.status()))
+}
@@ -0,0 +1,525 @@
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tracing::{error, info, warn};
+
+use crate::storage::file_logger::log_queue_stats;
+use crate::transport::connection_manager::Manager;
+
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+}
+
+impl MetricType {
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+        }
+    }
+}
+
+/// One metric family and its samples, rendered in OpenMetrics text format
+pub struct MetricFamily {
+    pub name: &'static str,
+    pub metric_type: MetricType,
+    pub help: &'static str,
+    pub samples: Vec<(Vec<(&'static str, String)>, f64)>,
+}
+
+impl MetricFamily {
+    pub fn new(name: &'static str, metric_type: MetricType, help: &'static str) -> Self {
+        Self {
+            name,
+            metric_type,
+            help,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn sample(mut self, labels: Vec<(&'static str, String)>, value: f64) -> Self {
+        self.samples.push((labels, value));
+        self
+    }
+
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(out, "# TYPE {} {}", self.name, self.metric_type.as_str());
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let suffix = match self.metric_type {
+            MetricType::Counter => "_total",
+            MetricType::Gauge => "",
+        };
+        for (labels, value) in &self.samples {
+            let _ = write!(out, "{}{}", self.name, suffix);
+            if !labels.is_empty() {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                    .collect();
+                let _ = write!(out, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(out, " {}", value);
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub fn render_families(families: &[MetricFamily]) -> String {
+    let mut out = String::new();
+    for family in families {
+        family.render(&mut out);
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Render the `Manager` counters in OpenMetrics text format
+pub async fn render_openmetrics(manager: &Manager) -> String {
+    let counts = manager.get_connection_packet_counts().await;
+    let stats = manager.get_connection_statistics().await;
+    let log_queue = log_queue_stats();
+
+    let mut rx_packets = MetricFamily::new(
+        "transport_rx_packets",
+        MetricType::Counter,
+        "Packets received per connection.",
+    );
+    let mut tx_packets = MetricFamily::new(
+        "transport_tx_packets",
+        MetricType::Counter,
+        "Packets sent per connection.",
+    );
+    let mut rx_bytes = MetricFamily::new(
+        "transport_rx_bytes",
+        MetricType::Counter,
+        "Bytes received per connection.",
+    );
+    let mut tx_bytes = MetricFamily::new(
+        "transport_tx_bytes",
+        MetricType::Counter,
+        "Bytes sent per connection.",
+    );
+    let mut decode_errors = MetricFamily::new(
+        "transport_decode_errors",
+        MetricType::Counter,
+        "Payloads that could not be decoded per connection.",
+    );
+    let mut buffer_overflows = MetricFamily::new(
+        "transport_buffer_overflows",
+        MetricType::Counter,
+        "Receive buffer overflows per connection.",
+    );
+    let mut rx_rate = MetricFamily::new(
+        "transport_rx_packets_per_second",
+        MetricType::Gauge,
+        "Rolling receive rate per connection.",
+    );
+    let mut tx_rate = MetricFamily::new(
+        "transport_tx_packets_per_second",
+        MetricType::Gauge,
+        "Rolling send rate per connection.",
+    );
+
+    let mut ids: Vec<&String> = counts.keys().collect();
+    ids.sort();
+    for id in ids {
+        let labels = || vec![("connection", id.clone())];
+        let (received, sent) = counts[id];
+        rx_packets = rx_packets.sample(labels(), received as f64);
+        tx_packets = tx_packets.sample(labels(), sent as f64);
+        if let Some(s) = stats.get(id) {
+            rx_bytes = rx_bytes.sample(labels(), s.rx_bytes as f64);
+            tx_bytes = tx_bytes.sample(labels(), s.tx_bytes as f64);
+            decode_errors = decode_errors.sample(labels(), s.decode_errors as f64);
+            buffer_overflows = buffer_overflows.sample(labels(), s.buffer_overflows as f64);
+            rx_rate = rx_rate.sample(labels(), s.rx_packets_per_sec);
+            tx_rate = tx_rate.sample(labels(), s.tx_packets_per_sec);
+        }
+    }
+
+    let families = vec![
+        MetricFamily::new(
+            "transport_connections",
+            MetricType::Gauge,
+            "Open connections.",
+        )
+        .sample(vec![], counts.len() as f64),
+        MetricFamily::new(
+            "transport_active_shares",
+            MetricType::Gauge,
+            "Running share tasks.",
+        )
+        .sample(vec![], manager.share_tasks.lock().await.len() as f64),
+        MetricFamily::new(
+            "transport_active_simulation_streams",
+            MetricType::Gauge,
+            "Running simulation streams.",
+        )
+        .sample(
+            vec![],
+            manager.simulation_stream_tasks.lock().await.len() as f64,
+        ),
+        MetricFamily::new(
+            "transport_log_queue_depth",
+            MetricType::Gauge,
+            "Log records waiting to be written.",
+        )
+        .sample(vec![], log_queue.depth as f64),
+        MetricFamily::new(
+            "transport_log_dropped",
+            MetricType::Counter,
+            "Log records discarded because the queue was full.",
+        )
+        .sample(
+            vec![("reason", "oldest".to_string())],
+            log_queue.dropped_oldest as f64,
+        )
+        .sample(
+            vec![("reason", "newest".to_string())],
+            log_queue.dropped_newest as f64,
+        ),
+        rx_packets,
+        tx_packets,
+        rx_bytes,
+        tx_bytes,
+        decode_errors,
+        buffer_overflows,
+        rx_rate,
+        tx_rate,
+    ];
+    render_families(&families)
+}
+
+/// A sample parsed back out of an exposition by `validate_openmetrics`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScrapedSample {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
+fn parse_labels(raw: &str) -> Result<Vec<(String, String)>, String> {
+    let mut labels = Vec::new();
+    let mut chars = raw.chars().peekable();
+    while chars.peek().is_some() {
+        let name: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("Invalid label name '{}'", name));
+        }
+        if chars.next() != Some('"') {
+            return Err(format!("Label '{}' value is not quoted", name));
+        }
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some('\\') => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some(c @ ('\\' | '"')) => value.push(c),
+                    other => return Err(format!("Invalid escape {:?} in label '{}'", other, name)),
+                },
+                Some('"') => break,
+                Some(c) => value.push(c),
+                None => return Err(format!("Unterminated value for label '{}'", name)),
+            }
+        }
+        labels.push((name, value));
+        match chars.next() {
+            Some(',') | None => {}
+            Some(c) => return Err(format!("Unexpected '{}' after label value", c)),
+        }
+    }
+    Ok(labels)
+}
+
+/// Check an OpenMetrics text exposition the way a scraper would and return its samples
+pub fn validate_openmetrics(text: &str) -> Result<Vec<ScrapedSample>, String> {
+    let mut families: Vec<(String, String)> = Vec::new();
+    let mut samples = Vec::new();
+    let mut saw_eof = false;
+    for (index, line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        if saw_eof {
+            return Err(format!("line {}: content after # EOF", line_no));
+        }
+        if line == "# EOF" {
+            saw_eof = true;
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("# TYPE ") {
+            let (name, metric_type) = rest
+                .split_once(' ')
+                .ok_or_else(|| format!("line {}: malformed TYPE", line_no))?;
+            if families.iter().any(|(n, _)| n == name) {
+                return Err(format!("line {}: duplicate family {}", line_no, name));
+            }
+            families.push((name.to_string(), metric_type.to_string()));
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("# HELP ") {
+            let name = rest.split(' ').next().unwrap_or_default();
+            if !families.iter().any(|(n, _)| n == name) {
+                return Err(format!(
+                    "line {}: HELP for undeclared family {}",
+                    line_no, name
+                ));
+            }
+            continue;
+        }
+        if line.starts_with('#') || line.is_empty() {
+            return Err(format!("line {}: unexpected line '{}'", line_no, line));
+        }
+
+        let (series, value) = line
+            .rsplit_once(' ')
+            .ok_or_else(|| format!("line {}: sample without value", line_no))?;
+        let value: f64 = match value {
+            "+Inf" => f64::INFINITY,
+            "-Inf" => f64::NEG_INFINITY,
+            v => v
+                .parse()
+                .map_err(|_| format!("line {}: invalid value '{}'", line_no, v))?,
+        };
+        let (name, labels) = match series.split_once('{') {
+            Some((name, rest)) => {
+                let raw = rest
+                    .strip_suffix('}')
+                    .ok_or_else(|| format!("line {}: unterminated labels", line_no))?;
+                (
+                    name,
+                    parse_labels(raw).map_err(|e| format!("line {}: {}", line_no, e))?,
+                )
+            }
+            None => (series, Vec::new()),
+        };
+        let (_, family_type) = families
+            .iter()
+            .rev()
+            .find(|(family, _)| {
+                name == family
+                    || name
+                        .strip_prefix(family.as_str())
+                        .is_some_and(|suffix| matches!(suffix, "_total" | "_created"))
+            })
+            .ok_or_else(|| format!("line {}: sample {} has no TYPE", line_no, name))?;
+        if family_type == "counter" {
+            if !name.ends_with("_total") && !name.ends_with("_created") {
+                return Err(format!(
+                    "line {}: counter sample {} must end in _total",
+                    line_no, name
+                ));
+            }
+            if value < 0.0 {
+                return Err(format!("line {}: counter {} is negative", line_no, name));
+            }
+        }
+        samples.push(ScrapedSample {
+            name: name.to_string(),
+            labels,
+            value,
+        });
+    }
+    if !saw_eof {
+        return Err("missing # EOF".to_string());
+    }
+    Ok(samples)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MetricsExporterStatus {
+    pub addr: String,
+}
+
+/// Local HTTP endpoint serving `GET /metrics`
+pub struct MetricsExporter {
+    pub addr: SocketAddr,
+    shutdown_tx: Option<oneshot::Sender<()>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl MetricsExporter {
+    pub async fn start(manager: Manager, addr: SocketAddr) -> Result<Self, String> {
+        if !addr.ip().is_loopback() {
+            return Err(format!(
+                "Metrics exporter only listens on loopback addresses, got {}",
+                addr
+            ));
+        }
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| format!("Failed to bind metrics exporter on {}: {}", addr, e))?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            info!("[metrics] Serving http://{}/metrics", addr);
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => break,
+                    res = listener.accept() => match res {
+                        Ok((stream, peer)) => {
+                            let manager = manager.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = serve_scrape(stream, &manager).await {
+                                    warn!("[metrics] Scrape from {} failed: {}", peer, e);
+                                }
+                            });
+                        }
+                        Err(e) => error!("[metrics] Accept error: {}", e),
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            addr,
+            shutdown_tx: Some(shutdown_tx),
+            task,
+        })
+    }
+
+    pub fn status(&self) -> MetricsExporterStatus {
+        MetricsExporterStatus {
+            addr: self.addr.to_string(),
+        }
+    }
+
+    pub fn stop(mut self) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(());
+        }
+        self.task.abort();
+    }
+}
+
+async fn serve_scrape(mut stream: TcpStream, manager: &Manager) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || request.len() > 16 * 1024 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let request_line = String::from_utf8_lossy(&request);
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let (status, content_type, body) = if method == "GET" && path == "/metrics" {
+        ("200 OK", CONTENT_TYPE, render_openmetrics(manager).await)
+    } else {
+        ("404 Not Found", "text/plain", "Not Found\n".to_string())
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::Transport;
+    use async_trait::async_trait;
+    use std::any::Any;
+    use std::sync::Arc;
+
+    /// Connection that only reports fixed packet counts
+    struct FixedCounts {
+        received: usize,
+        sent: usize,
+    }
+
+    #[async_trait]
+    impl Transport for FixedCounts {
+        async fn send(&self, _data: Vec<u8>) -> Result<(), String> {
+            Ok(())
+        }
+        async fn stop(&self) {}
+        fn name(&self) -> String {
+            "fixed".to_string()
+        }
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+        fn get_packet_received_count(&self) -> usize {
+            self.received
+        }
+        fn get_packet_sent_count(&self) -> usize {
+            self.sent
+        }
+    }
+
+    fn sample<'a>(samples: &'a [ScrapedSample], name: &str) -> Option<&'a ScrapedSample> {
+        samples.iter().find(|s| s.name == name)
+    }
+
+    #[tokio::test]
+    async fn scrape_is_valid_openmetrics() {
+        let manager = Manager::new();
+        manager
+            .add_connection(
+                "udp_test".to_string(),
+                Arc::new(FixedCounts {
+                    received: 42,
+                    sent: 7,
+                }),
+            )
+            .await
+            .unwrap();
+        let exporter = MetricsExporter::start(manager, "127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+
+        let mut stream = TcpStream::connect(exporter.addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        exporter.stop();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(head.contains(CONTENT_TYPE));
+        assert!(body.ends_with("# EOF\n"));
+
+        let samples = validate_openmetrics(body).unwrap();
+        let connection = vec![("connection".to_string(), "udp_test".to_string())];
+        let rx = sample(&samples, "transport_rx_packets_total").unwrap();
+        assert_eq!((&rx.labels, rx.value), (&connection, 42.0));
+        let tx = sample(&samples, "transport_tx_packets_total").unwrap();
+        assert_eq!((&tx.labels, tx.value), (&connection, 7.0));
+        assert_eq!(
+            sample(&samples, "transport_connections").map(|s| s.value),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn rejects_missing_eof() {
+        let text = "# TYPE up gauge\nup 1\n";
+        assert_eq!(validate_openmetrics(text), Err("missing # EOF".to_string()));
+    }
+}
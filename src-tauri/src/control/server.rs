@@ -0,0 +1,809 @@
+use futures::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tauri::{AppHandle, EventId, Listener, Manager as _};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, oneshot};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{header, StatusCode};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{error, info, warn};
+
+use crate::geo;
+use crate::storage;
+use crate::transport;
+
+/// Backend events that clients can subscribe to
+pub const FORWARDED_EVENTS: &[&str] = &[
+    "serial_packet",
+    "packet_sent",
+    "statistics_tick",
+    "track_lost",
+    "targets_updated",
+    "alert",
+    "decoded_payload",
+];
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+const UNAUTHORIZED: i64 = -32001;
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ControlServerStatus {
+    pub addr: String,
+    pub auth_required: bool,
+    pub allowed_origins: Vec<String>,
+}
+
+/// A running control server; dropping it without `stop` leaves the listener running
+pub struct ControlServer {
+    pub addr: SocketAddr,
+    token: Option<String>,
+    allowed_origins: Arc<Vec<String>>,
+    shutdown_tx: Option<oneshot::Sender<()>>,
+    task: tokio::task::JoinHandle<()>,
+    listeners: Vec<EventId>,
+    app: AppHandle,
+}
+
+impl ControlServer {
+    /// Bind `addr` (loopback only) and start accepting WebSocket clients. Handshakes with
+    /// an `Origin` header come from browser pages and are refused unless it is listed in
+    /// `allowed_origins`.
+    pub async fn start(
+        app: AppHandle,
+        addr: SocketAddr,
+        token: Option<String>,
+        allowed_origins: Vec<String>,
+    ) -> Result<Self, String> {
+        if !addr.ip().is_loopback() {
+            return Err(format!(
+                "Control server only listens on loopback addresses, got {}",
+                addr
+            ));
+        }
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| format!("Failed to bind control server on {}: {}", addr, e))?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+
+        // Forward backend events to every subscribed client
+        let (events_tx, _) = broadcast::channel::<(String, String)>(1024);
+        let listeners = FORWARDED_EVENTS
+            .iter()
+            .map(|name| {
+                let events_tx = events_tx.clone();
+                let event_name = name.to_string();
+                app.listen_any(*name, move |event| {
+                    let _ = events_tx.send((event_name.clone(), event.payload().to_string()));
+                })
+            })
+            .collect();
+
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+        let accept_app = app.clone();
+        let accept_token = token.clone();
+        let allowed_origins = Arc::new(allowed_origins);
+        let accept_origins = allowed_origins.clone();
+        let task = tokio::spawn(async move {
+            info!("[control] Listening on ws://{}", addr);
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => break,
+                    res = listener.accept() => match res {
+                        Ok((stream, peer)) => {
+                            tokio::spawn(handle_client(
+                                stream,
+                                peer,
+                                accept_app.clone(),
+                                accept_token.clone(),
+                                accept_origins.clone(),
+                                events_tx.subscribe(),
+                            ));
+                        }
+                        Err(e) => error!("[control] Accept error: {}", e),
+                    }
+                }
+            }
+            info!("[control] Stopped listening on {}", addr);
+        });
+
+        Ok(Self {
+            addr,
+            token,
+            allowed_origins,
+            shutdown_tx: Some(shutdown_tx),
+            task,
+            listeners,
+            app,
+        })
+    }
+
+    pub fn status(&self) -> ControlServerStatus {
+        ControlServerStatus {
+            addr: self.addr.to_string(),
+            auth_required: self.token.is_some(),
+            allowed_origins: self.allowed_origins.to_vec(),
+        }
+    }
+
+    pub fn stop(mut self) {
+        for id in self.listeners.drain(..) {
+            self.app.unlisten(id);
+        }
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(());
+        }
+        self.task.abort();
+    }
+}
+
+fn query_token(req: &Request) -> Option<String> {
+    req.uri()
+        .query()
+        .and_then(|query| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("token="))
+        })
+        .and_then(percent_decode)
+}
+
+/// Decode `%XX` escapes and `+` of a query value; `None` if an escape is malformed or the
+/// result is not UTF-8
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3)?;
+                if !hex.iter().all(u8::is_ascii_hexdigit) {
+                    return None;
+                }
+                let hex = std::str::from_utf8(hex).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// True when no token is required or `presented` equals it. Every byte is compared so the
+/// time taken does not reveal how much of the token matched.
+fn token_matches(expected: Option<&str>, presented: Option<&str>) -> bool {
+    let Some(expected) = expected else {
+        return true;
+    };
+    let Some(presented) = presented else {
+        return false;
+    };
+    let (expected, presented) = (expected.as_bytes(), presented.as_bytes());
+    expected.len() == presented.len()
+        && expected
+            .iter()
+            .zip(presented)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn handle_client(
+    stream: TcpStream,
+    peer: SocketAddr,
+    app: AppHandle,
+    token: Option<String>,
+    allowed_origins: Arc<Vec<String>>,
+    mut events: broadcast::Receiver<(String, String)>,
+) {
+    let mut presented_token = None;
+    let callback = |req: &Request, resp: Response| -> Result<Response, ErrorResponse> {
+        if let Some(origin) = req.headers().get(header::ORIGIN) {
+            let origin = origin.to_str().unwrap_or_default();
+            if !allowed_origins.iter().any(|allowed| allowed == origin) {
+                warn!("[control] Refused {} from origin '{}'", peer, origin);
+                let mut forbidden = ErrorResponse::new(Some("Origin not allowed".to_string()));
+                *forbidden.status_mut() = StatusCode::FORBIDDEN;
+                return Err(forbidden);
+            }
+        }
+        presented_token = query_token(req);
+        Ok(resp)
+    };
+    let ws = match tokio_tungstenite::accept_hdr_async(stream, callback).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            warn!("[control] Handshake with {} failed: {}", peer, e);
+            return;
+        }
+    };
+    info!("[control] Client connected: {}", peer);
+
+    let mut authenticated = token_matches(token.as_deref(), presented_token.as_deref());
+    let mut subscriptions: HashSet<String> = HashSet::new();
+    let (mut sink, mut incoming) = ws.split();
+
+    loop {
+        tokio::select! {
+            msg = incoming.next() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Ping(data))) => {
+                        let _ = sink.send(Message::Pong(data)).await;
+                        continue;
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Err(e) => {
+                        warn!("[control] Read error from {}: {}", peer, e);
+                        break;
+                    }
+                };
+                let response = handle_request(
+                    &app,
+                    &text,
+                    token.as_deref(),
+                    &mut authenticated,
+                    &mut subscriptions,
+                )
+                .await;
+                if let Some(response) = response {
+                    if sink.send(Message::Text(response.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            event = events.recv() => match event {
+                Ok((name, payload)) => {
+                    if !authenticated || !subscriptions.contains(&name) {
+                        continue;
+                    }
+                    let params = serde_json::from_str(&payload).unwrap_or(Value::String(payload));
+                    let notification = json!({ "jsonrpc": "2.0", "method": name, "params": params });
+                    if sink.send(Message::Text(notification.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("[control] Client {} lagged, dropped {} events", peer, skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+    info!("[control] Client disconnected: {}", peer);
+}
+
+fn rpc_error(id: Option<Value>, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id.unwrap_or(Value::Null),
+        "error": { "code": code, "message": message.into() },
+    })
+}
+
+/// Handle one JSON-RPC message, returning the response (none for notifications)
+async fn handle_request(
+    app: &AppHandle,
+    text: &str,
+    token: Option<&str>,
+    authenticated: &mut bool,
+    subscriptions: &mut HashSet<String>,
+) -> Option<Value> {
+    let request: RpcRequest = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => return Some(rpc_error(None, PARSE_ERROR, e.to_string())),
+    };
+    if request.jsonrpc != "2.0" {
+        return Some(rpc_error(
+            request.id,
+            INVALID_REQUEST,
+            "jsonrpc must be \"2.0\"",
+        ));
+    }
+    let id = request.id.clone();
+
+    let result = match request.method.as_str() {
+        "auth" => match param::<String>(&request.params, "token") {
+            Ok(presented) if token_matches(token, Some(&presented)) => {
+                *authenticated = true;
+                Ok(Value::Bool(true))
+            }
+            Ok(_) => Err((UNAUTHORIZED, "Invalid token".to_string())),
+            Err(e) => Err((INVALID_PARAMS, e)),
+        },
+        _ if !*authenticated => Err((UNAUTHORIZED, "Not authenticated".to_string())),
+        "subscribe" | "unsubscribe" => match param::<Vec<String>>(&request.params, "events") {
+            Ok(events) => {
+                for event in events {
+                    if !FORWARDED_EVENTS.contains(&event.as_str()) {
+                        return Some(rpc_error(
+                            id,
+                            INVALID_PARAMS,
+                            format!("Unknown event '{}'", event),
+                        ));
+                    }
+                    if request.method == "subscribe" {
+                        subscriptions.insert(event);
+                    } else {
+                        subscriptions.remove(&event);
+                    }
+                }
+                Ok(json!(subscriptions.iter().collect::<Vec<_>>()))
+            }
+            Err(e) => Err((INVALID_PARAMS, e)),
+        },
+        method => dispatch(app, method, &request.params).await,
+    };
+
+    // Requests without an id are notifications and get no response
+    id.as_ref()?;
+    Some(match result {
+        Ok(value) => json!({ "jsonrpc": "2.0", "id": id, "result": value }),
+        Err((code, message)) => rpc_error(id, code, message),
+    })
+}
+
+fn param<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T, String> {
+    let value = params.get(name).cloned().unwrap_or(Value::Null);
+    serde_json::from_value(value).map_err(|e| format!("Invalid param '{}': {}", name, e))
+}
+
+fn reply<T: Serialize>(result: Result<T, String>) -> Result<Value, (i64, String)> {
+    result
+        .and_then(|value| serde_json::to_value(value).map_err(|e| e.to_string()))
+        .map_err(|e| (SERVER_ERROR, e))
+}
+
+/// Call the Tauri command named `method`; params use the Rust argument names
+async fn dispatch(app: &AppHandle, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+    macro_rules! p {
+        ($name:literal) => {
+            param(params, $name).map_err(|e| (INVALID_PARAMS, e))?
+        };
+    }
+    match method {
+        // transport::commands
+        "start_connection" => reply(
+            transport::commands::start_connection(
+                app.state(),
+                p!("id"),
+                p!("port"),
+                p!("baud"),
+                app.clone(),
+            )
+            .await,
+        ),
+        "start_udp_connection" => reply(
+            transport::commands::start_udp_connection(
+                app.state(),
+                p!("id"),
+                p!("local_addr"),
+                p!("options"),
+                app.clone(),
+            )
+            .await,
+        ),
+        "add_udp_peer" => reply(
+            transport::commands::add_udp_peer(app.state(), p!("id"), p!("addr")).await,
+        ),
+        "remove_udp_peer" => reply(
+            transport::commands::remove_udp_peer(app.state(), p!("id"), p!("addr")).await,
+        ),
+        "list_udp_peers" => {
+            reply(transport::commands::list_udp_peers(app.state(), p!("id")).await)
+        }
+        "set_udp_reply_to_sender" => reply(
+            transport::commands::set_udp_reply_to_sender(app.state(), p!("id"), p!("enabled"))
+                .await,
+        ),
+        "list_udp_senders" => {
+            reply(transport::commands::list_udp_senders(app.state(), p!("id")).await)
+        }
+        "send_packet_to_sender" => reply(
+            transport::commands::send_packet_to_sender(
+                app.state(),
+                p!("id"),
+                p!("addr"),
+                p!("packet"),
+            )
+            .await,
+        ),
+        "get_udp_sensor_clients" => reply(
+            transport::commands::get_udp_sensor_clients(app.state(), p!("connection_id")).await,
+        ),
+        "map_udp_sensor_target" => reply(
+            transport::commands::map_udp_sensor_target(
+                app.state(),
+                p!("sensor_id"),
+                p!("target_id"),
+                p!("connection_id"),
+            )
+            .await,
+        ),
+        "unmap_udp_sensor_target" => reply(
+            transport::commands::unmap_udp_sensor_target(
+                app.state(),
+                p!("sensor_id"),
+                p!("connection_id"),
+            )
+            .await,
+        ),
+        "send_sensor_command" => reply(
+            transport::commands::send_sensor_command(
+                app.state(),
+                p!("sensor_id"),
+                p!("command"),
+                p!("timeout_ms"),
+                p!("connection_id"),
+            )
+            .await,
+        ),
+        "start_sensor_streaming" => reply(
+            transport::commands::start_sensor_streaming(app.state(), app.state(), p!("request"))
+                .await,
+        ),
+        "stop_sensor_streaming" => {
+            reply(transport::commands::stop_sensor_streaming(app.state()).await)
+        }
+        "stop_sensor_target_stream" => reply(
+            transport::commands::stop_sensor_target_stream(
+                app.state(),
+                p!("sensor_id"),
+                p!("connection_id"),
+            )
+            .await,
+        ),
+        "get_active_sensor_streams" => {
+            reply(transport::commands::get_active_sensor_streams(app.state()).await)
+        }
+        "list_sensor_streams" => reply(transport::commands::list_sensor_streams(app.state()).await),
+        "join_udp_multicast" => reply(
+            transport::commands::join_udp_multicast(app.state(), p!("id"), p!("group")).await,
+        ),
+        "leave_udp_multicast" => reply(
+            transport::commands::leave_udp_multicast(app.state(), p!("id"), p!("group")).await,
+        ),
+        "start_zmq_connection" => reply(
+            transport::commands::start_zmq_connection(
+                app.state(),
+                p!("id"),
+                p!("options"),
+                app.clone(),
+            )
+            .await,
+        ),
+        "zmq_subscribe" => {
+            reply(transport::commands::zmq_subscribe(app.state(), p!("id"), p!("topic")).await)
+        }
+        "zmq_unsubscribe" => {
+            reply(transport::commands::zmq_unsubscribe(app.state(), p!("id"), p!("topic")).await)
+        }
+        "init_zmq" => reply(transport::commands::init_zmq(p!("endpoint")).await),
+        "add_sub" => reply(
+            transport::commands::add_sub(
+                app.state(),
+                p!("id"),
+                p!("topic"),
+                p!("endpoint"),
+                app.clone(),
+            )
+            .await,
+        ),
+        "remove_sub" => {
+            reply(transport::commands::remove_sub(app.state(), p!("id"), p!("topic")).await)
+        }
+        "list_subs" => reply(transport::commands::list_subs(app.state()).await),
+        "list_subs_with_status" => {
+            reply(transport::commands::list_subs_with_status(app.state()).await)
+        }
+        "stop_connection" => {
+            reply(transport::commands::stop_connection(app.state(), p!("id")).await)
+        }
+        "send_packet" => {
+            reply(transport::commands::send_packet(app.state(), p!("id"), p!("packet")).await)
+        }
+        "send_raw" => reply(
+            transport::commands::send_raw(app.state(), p!("id"), p!("data"), p!("framed")).await,
+        ),
+        "send_hex" => reply(
+            transport::commands::send_hex(app.state(), p!("id"), p!("hex"), p!("framed")).await,
+        ),
+        "send_text" => reply(
+            transport::commands::send_text(
+                app.state(),
+                p!("id"),
+                p!("text"),
+                p!("terminator"),
+                p!("framed"),
+            )
+            .await,
+        ),
+        "send_file" => reply(
+            transport::commands::send_file(app.state(), p!("id"), p!("path"), p!("framed")).await,
+        ),
+        "list_serial_ports" => reply(transport::commands::list_serial_ports()),
+        "list_connections" => reply(transport::commands::list_connections(app.state()).await),
+        "disconnect_all_connections" => {
+            reply(transport::commands::disconnect_all_connections(app.state()).await)
+        }
+        "start_serial_share" => reply(
+            transport::commands::start_serial_share(
+                app.state(),
+                p!("from_id"),
+                p!("to_id"),
+                p!("interval_ms"),
+            )
+            .await,
+        ),
+        "stop_share" => {
+            reply(transport::commands::stop_share(app.state(), p!("from_id"), p!("to_id")).await)
+        }
+        "stop_share_by_connection_id" => reply(
+            transport::commands::stop_share_by_connection_id(app.state(), p!("connection_id"))
+                .await,
+        ),
+        "set_udp_remote_addr" => reply(
+            transport::commands::set_udp_remote_addr(app.state(), p!("id"), p!("remote_addr"))
+                .await,
+        ),
+        "get_connection_logging" => {
+            reply(transport::commands::get_connection_logging(app.state(), p!("id")).await)
+        }
+        "set_connection_logging" => reply(
+            transport::commands::set_connection_logging(app.state(), p!("id"), p!("settings"))
+                .await,
+        ),
+        "get_connection_decoders" => {
+            reply(transport::commands::get_connection_decoders(app.state(), p!("id")).await)
+        }
+        "set_connection_decoders" => reply(
+            transport::commands::set_connection_decoders(app.state(), p!("id"), p!("config")).await,
+        ),
+        "get_responder_rules" => {
+            reply(transport::commands::get_responder_rules(app.state(), p!("id")).await)
+        }
+        "set_responder_rules" => reply(
+            transport::commands::set_responder_rules(app.state(), p!("id"), p!("rules")).await,
+        ),
+        "load_proto_schema" => {
+            reply(transport::commands::load_proto_schema(p!("path"), p!("include_dirs")).await)
+        }
+        "unload_proto_schema" => reply(transport::commands::unload_proto_schema(p!("path")).await),
+        "list_proto_schemas" => reply(transport::commands::list_proto_schemas().await),
+        "list_schema_messages" => reply(transport::commands::list_schema_messages().await),
+        "send_dynamic_packet" => reply(
+            transport::commands::send_dynamic_packet(
+                app.state(),
+                p!("id"),
+                p!("message"),
+                p!("value"),
+            )
+            .await,
+        ),
+        "start_simulation_udp_streaming" => reply(
+            transport::commands::start_simulation_udp_streaming(
+                app.state(),
+                app.state(),
+                p!("local_addr"),
+                p!("remote_addr"),
+                p!("interval_ms"),
+            )
+            .await,
+        ),
+        "stop_simulation_udp_streaming" => reply(
+            transport::commands::stop_simulation_udp_streaming(app.state(), p!("connection_id"))
+                .await,
+        ),
+        "share_target_to_udp_server" => reply(
+            transport::commands::share_target_to_udp_server(
+                app.state(),
+                app.state(),
+                p!("local_addr"),
+                p!("remote_addr"),
+                p!("interval_ms"),
+                p!("target_id"),
+            )
+            .await,
+        ),
+        "share_target_to_connection" => reply(
+            transport::commands::share_target_to_connection(
+                app.state(),
+                app.state(),
+                p!("target_id"),
+                p!("connection_id"),
+                p!("interval_ms"),
+            )
+            .await,
+        ),
+        "stop_share_to_connection" => reply(
+            transport::commands::stop_share_to_connection(
+                app.state(),
+                p!("share_id"),
+                p!("connection_id"),
+            )
+            .await,
+        ),
+        "share_udp_target_to_connection" => reply(
+            transport::commands::share_udp_target_to_connection(
+                app.state(),
+                p!("udp_connection_id"),
+                p!("target_id"),
+                p!("dest_connection_id"),
+                p!("interval_ms"),
+                p!("sources"),
+            )
+            .await,
+        ),
+        "list_active_shares" => reply(transport::commands::list_active_shares(app.state()).await),
+        "start_schedule" => {
+            reply(transport::commands::start_schedule(app.state(), p!("config")).await)
+        }
+        "stop_schedule" => reply(transport::commands::stop_schedule(app.state(), p!("id")).await),
+        "list_schedules" => reply(transport::commands::list_schedules(app.state()).await),
+        "list_active_simulation_streams" => {
+            reply(transport::commands::list_active_simulation_streams(app.state()).await)
+        }
+        "list_udp_targets" => reply(
+            transport::commands::list_udp_targets(
+                app.state(),
+                p!("connection_id"),
+                p!("history_limit"),
+                p!("since_ms"),
+            )
+            .await,
+        ),
+        "get_udp_target_history" => reply(
+            transport::commands::get_udp_target_history(
+                app.state(),
+                p!("connection_id"),
+                p!("target_id"),
+                p!("since_ms"),
+                p!("limit"),
+            )
+            .await,
+        ),
+        "get_fused_targets" => reply(transport::commands::get_fused_targets(app.state()).await),
+        "get_target_picture_config" => {
+            reply(transport::commands::get_target_picture_config(app.state()).await)
+        }
+        "set_target_picture_config" => reply(
+            transport::commands::set_target_picture_config(app.state(), p!("config")).await,
+        ),
+        "get_udp_track_config" => {
+            reply(transport::commands::get_udp_track_config(app.state(), p!("id")).await)
+        }
+        "set_udp_track_config" => reply(
+            transport::commands::set_udp_track_config(app.state(), p!("id"), p!("config")).await,
+        ),
+        "get_total_udp_targets" => {
+            reply(transport::commands::get_total_udp_targets(app.state()).await)
+        }
+        "get_packet_statistics" => {
+            reply(transport::commands::get_packet_statistics(app.state()).await)
+        }
+        "get_total_packets_received" => {
+            reply(transport::commands::get_total_packets_received(app.state()).await)
+        }
+        "get_total_packets_sent" => {
+            reply(transport::commands::get_total_packets_sent(app.state()).await)
+        }
+        "get_connection_packet_counts" => {
+            reply(transport::commands::get_connection_packet_counts(app.state()).await)
+        }
+        "get_connection_count" => {
+            reply(transport::commands::get_connection_count(app.state()).await)
+        }
+        "reset_packet_counters" => {
+            reply(transport::commands::reset_packet_counters(app.state()).await)
+        }
+        "get_connection_statistics" => {
+            reply(transport::commands::get_connection_statistics(app.state()).await)
+        }
+        // storage::commands
+        "read_log_file" => reply(storage::commands::read_log_file(p!("connection_id")).await),
+        "list_log_files" => reply(storage::commands::list_log_files().await),
+        "get_logs_directory" => reply(storage::commands::get_logs_directory().await),
+        "get_app_root_directory" => reply(storage::commands::get_app_root_directory().await),
+        "set_log_directory" => {
+            storage::commands::set_log_directory(p!("path"));
+            Ok(Value::Null)
+        }
+        "get_log_queue_stats" => reply(storage::commands::get_log_queue_stats().await),
+        "set_log_queue_config" => {
+            reply(storage::commands::set_log_queue_config(p!("config")).await)
+        }
+        // geo::commands
+        "get_geo_settings" => reply(geo::commands::get_geo_settings(app.state()).await),
+        "set_geo_settings" => {
+            reply(geo::commands::set_geo_settings(app.state(), p!("settings")).await)
+        }
+        "geo_range_bearing" => reply(
+            geo::commands::geo_range_bearing(app.state(), app.state(), p!("from"), p!("to")).await,
+        ),
+        "geo_from_geodetic" => {
+            reply(geo::commands::geo_from_geodetic(app.state(), p!("point")).await)
+        }
+        "geo_from_ecef" => reply(geo::commands::geo_from_ecef(app.state(), p!("ecef")).await),
+        "geo_from_enu" => {
+            reply(geo::commands::geo_from_enu(app.state(), p!("enu"), p!("origin")).await)
+        }
+        "get_target_kinematics" => reply(
+            geo::commands::get_target_kinematics(
+                app.state(),
+                app.state(),
+                p!("target_id"),
+                p!("connection_id"),
+            )
+            .await,
+        ),
+        "list_alert_rules" => reply(geo::commands::list_alert_rules(app.state()).await),
+        "save_geofence" => {
+            reply(geo::commands::save_geofence(app.state(), app.clone(), p!("fence")).await)
+        }
+        "delete_geofence" => {
+            reply(geo::commands::delete_geofence(app.state(), app.clone(), p!("id")).await)
+        }
+        "save_separation_rule" => {
+            reply(geo::commands::save_separation_rule(app.state(), app.clone(), p!("rule")).await)
+        }
+        "delete_separation_rule" => {
+            reply(geo::commands::delete_separation_rule(app.state(), app.clone(), p!("id")).await)
+        }
+        "get_active_alerts" => reply(geo::commands::get_active_alerts(app.state()).await),
+        _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_values_are_percent_decoded() {
+        assert_eq!(percent_decode("abc").as_deref(), Some("abc"));
+        assert_eq!(percent_decode("a%2Bb%20c+d").as_deref(), Some("a+b c d"));
+        assert_eq!(percent_decode("%C3%A9").as_deref(), Some("é"));
+        for malformed in ["%+1", "%4", "%zz", "%FF"] {
+            assert_eq!(percent_decode(malformed), None, "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn tokens_match_exactly() {
+        assert!(token_matches(None, None));
+        assert!(token_matches(None, Some("anything")));
+        assert!(token_matches(Some("s3cret"), Some("s3cret")));
+        assert!(!token_matches(Some("s3cret"), Some("s3creT")));
+        assert!(!token_matches(Some("s3cret"), Some("s3cre")));
+        assert!(!token_matches(Some("s3cret"), None));
+    }
+}
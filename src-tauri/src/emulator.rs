@@ -0,0 +1,88 @@
+//! Scripted device emulator: plays serial, UDP and TCP devices that send periodic
+//! messages and answer received frames, with optional latency, jitter and packet loss.
+//!
+//! A UDP sensor that streams readings once the app maps it to a target:
+//!
+//! ```toml
+//! [[endpoints]]
+//! name = "sensor-1"
+//! transport = { kind = "udp", remote = "127.0.0.1:5001" }
+//! latency_ms = 20
+//! jitter_ms = 10
+//! loss = 0.05
+//! vars = { sensor_id = 1, target_id = 0 }
+//!
+//! [[endpoints.periodic]]
+//! interval_ms = 500
+//! when = [{ field = "target_id", op = "ne", value = 0 }]
+//! message = { text = "{{target_id}},{{sensor_id}},{{rand -90.0 90.0}},{{rand -180.0 180.0}},{{rand 0.0 1000.0}},{{rand 20.0 30.0}}" }
+//!
+//! [[endpoints.responses]]
+//! on = { format = "text", prefix = "map:" }
+//! set = { target_id = "{{rest}}" }
+//!
+//! [[endpoints.responses]]
+//! on = { format = "text", prefix = "unmap" }
+//! set = { target_id = 0 }
+//!
+//! [[endpoints.responses]]
+//! on = { format = "text", prefix = "health-check" }
+//! reply = { text = "health:OK,temp:{{rand 20.0 30.0}}" }
+//! ```
+//!
+//! A serial device echoing headers back five seconds later:
+//!
+//! ```toml
+//! [[endpoints]]
+//! name = "serial-device"
+//! transport = { kind = "serial", port = "COM3" }
+//!
+//! [[endpoints.periodic]]
+//! interval_ms = 1000
+//! message = { packet = { kind = { Header = { id = "{{rand 1 999}}", length = 42, checksum = 1234, version = 1, flags = 0 } } } }
+//!
+//! [[endpoints.responses]]
+//! on = { format = "packet", kind = "Header" }
+//! reply = { packet = { kind = { Header = { id = "{{id+1000}}", checksum = "{{checksum+1}}" } } } }
+//! delay_ms = 5000
+//! ```
+
+pub mod endpoint;
+pub mod script;
+
+use tokio::task::JoinHandle;
+
+use crate::emulator::endpoint::Endpoint;
+use crate::emulator::script::EmulatorScript;
+use crate::transport::schemas::SCHEMAS;
+
+/// Every endpoint of a running script
+pub struct Emulator {
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Emulator {
+    /// Load the script's schemas and start all its endpoints
+    pub async fn start(script: EmulatorScript) -> Result<Self, String> {
+        for schema in &script.schemas {
+            SCHEMAS.load(schema.clone())?;
+        }
+        script.validate()?;
+        let mut tasks = Vec::new();
+        for endpoint in script.endpoints {
+            let name = endpoint.name.clone();
+            match Endpoint::start(endpoint).await {
+                Ok(endpoint_tasks) => tasks.extend(endpoint_tasks),
+                Err(e) => {
+                    tasks.iter().for_each(JoinHandle::abort);
+                    return Err(format!("Endpoint '{}': {}", name, e));
+                }
+            }
+        }
+        Ok(Self { tasks })
+    }
+
+    pub fn stop(self) {
+        self.tasks.iter().for_each(JoinHandle::abort);
+    }
+}
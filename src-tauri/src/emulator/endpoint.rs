@@ -0,0 +1,388 @@
+use serde_json::{Map, Value};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_serial::SerialPortBuilderExt;
+
+use crate::emulator::script::{EndpointScript, EndpointTransport, PeriodicMessage};
+use crate::transport::responder::render;
+
+type Writer = Arc<tokio::sync::Mutex<Box<dyn AsyncWrite + Send + Unpin>>>;
+
+/// Where a frame came from, and where its replies go
+#[derive(Clone)]
+enum Peer {
+    Udp(SocketAddr),
+    Stream { label: String, writer: Writer },
+}
+
+impl Peer {
+    fn stream<W: AsyncWrite + Send + Unpin + 'static>(label: String, writer: W) -> Self {
+        Peer::Stream {
+            label,
+            writer: Arc::new(tokio::sync::Mutex::new(Box::new(writer))),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Peer::Udp(addr) => addr.to_string(),
+            Peer::Stream { label, .. } => label.clone(),
+        }
+    }
+
+    fn is(&self, other: &Peer) -> bool {
+        match (self, other) {
+            (Peer::Udp(a), Peer::Udp(b)) => a == b,
+            (Peer::Stream { writer: a, .. }, Peer::Stream { writer: b, .. }) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// A running emulated device
+pub struct Endpoint {
+    script: EndpointScript,
+    vars: Mutex<Map<String, Value>>,
+    udp: Option<Arc<UdpSocket>>,
+    /// Where periodic messages go: the UDP remote or last sender, or every open stream
+    peers: Mutex<Vec<Peer>>,
+    /// Reply counts of each response, for `{{seq}}`
+    replies: Vec<AtomicU64>,
+}
+
+impl Endpoint {
+    /// Open the endpoint and start its periodic messages and receive loop
+    pub async fn start(script: EndpointScript) -> Result<Vec<JoinHandle<()>>, String> {
+        let mut udp = None;
+        let mut peers = Vec::new();
+        let mut serial = None;
+        let mut listener = None;
+        match &script.transport {
+            EndpointTransport::Serial { port, baud_rate } => {
+                let stream = tokio_serial::new(port, *baud_rate)
+                    .open_native_async()
+                    .map_err(|e| format!("Failed to open {}: {}", port, e))?;
+                let (reader, writer) = tokio::io::split(stream);
+                let peer = Peer::stream(port.clone(), writer);
+                peers.push(peer.clone());
+                serial = Some((reader, peer));
+            }
+            EndpointTransport::Udp { bind, remote } => {
+                let socket = UdpSocket::bind(bind)
+                    .await
+                    .map_err(|e| format!("Failed to bind {}: {}", bind, e))?;
+                if let Some(remote) = remote {
+                    let remote = tokio::net::lookup_host(remote)
+                        .await
+                        .map_err(|e| format!("Invalid remote address {}: {}", remote, e))?
+                        .next()
+                        .ok_or_else(|| format!("Invalid remote address {}", remote))?;
+                    peers.push(Peer::Udp(remote));
+                }
+                udp = Some(Arc::new(socket));
+            }
+            EndpointTransport::TcpClient { .. } => {}
+            EndpointTransport::TcpServer { bind } => {
+                listener = Some(
+                    TcpListener::bind(bind)
+                        .await
+                        .map_err(|e| format!("Failed to listen on {}: {}", bind, e))?,
+                );
+            }
+        }
+
+        let endpoint = Arc::new(Endpoint {
+            vars: Mutex::new(script.vars.clone()),
+            replies: script.responses.iter().map(|_| AtomicU64::new(0)).collect(),
+            script,
+            udp,
+            peers: Mutex::new(peers),
+        });
+        if let Some(socket) = &endpoint.udp {
+            println!(
+                "[{}] UDP on {}",
+                endpoint.script.name,
+                socket.local_addr().map_err(|e| e.to_string())?
+            );
+        }
+
+        let mut tasks: Vec<JoinHandle<()>> = endpoint
+            .script
+            .periodic
+            .iter()
+            .cloned()
+            .map(|periodic| tokio::spawn(endpoint.clone().run_periodic(periodic)))
+            .collect();
+        let receiver = endpoint.clone();
+        tasks.push(match (&endpoint.script.transport, serial, listener) {
+            (_, Some((reader, peer)), _) => tokio::spawn(receiver.read_stream(reader, peer)),
+            (EndpointTransport::TcpClient { addr }, _, _) => {
+                tokio::spawn(receiver.run_tcp_client(addr.clone()))
+            }
+            (_, _, Some(listener)) => tokio::spawn(receiver.run_tcp_server(listener)),
+            _ => tokio::spawn(receiver.run_udp()),
+        });
+        Ok(tasks)
+    }
+
+    fn name(&self) -> &str {
+        &self.script.name
+    }
+
+    fn add_peer(&self, peer: Peer) {
+        self.peers.lock().unwrap().push(peer);
+    }
+
+    fn remove_peer(&self, peer: &Peer) {
+        self.peers.lock().unwrap().retain(|p| !p.is(peer));
+    }
+
+    /// Frame `data` and send it to `peer` with the endpoint's latency, jitter and loss
+    fn send(self: &Arc<Self>, peer: Peer, data: Vec<u8>, what: &str) {
+        let frame = match self.script.framing.encode(&data) {
+            Ok(frame) => frame,
+            Err(e) => {
+                eprintln!("[{}] {}: {}", self.name(), what, e);
+                return;
+            }
+        };
+        if self.script.impairments.drop_sent() {
+            println!("[{}] dropped {} to {}", self.name(), what, peer.label());
+            return;
+        }
+        let delay = self.script.impairments.delay();
+        let endpoint = self.clone();
+        let what = what.to_string();
+        tokio::spawn(async move {
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            let result = match &peer {
+                Peer::Udp(addr) => match &endpoint.udp {
+                    Some(socket) => socket.send_to(&frame, addr).await.map(|_| ()),
+                    None => return,
+                },
+                Peer::Stream { writer, .. } => {
+                    let mut writer = writer.lock().await;
+                    match writer.write_all(&frame).await {
+                        Ok(()) => writer.flush().await,
+                        Err(e) => Err(e),
+                    }
+                }
+            };
+            match result {
+                Ok(()) => println!(
+                    "[{}] sent {} ({} bytes) to {}",
+                    endpoint.name(),
+                    what,
+                    frame.len(),
+                    peer.label()
+                ),
+                Err(e) => eprintln!(
+                    "[{}] failed to send {} to {}: {}",
+                    endpoint.name(),
+                    what,
+                    peer.label(),
+                    e
+                ),
+            }
+        });
+    }
+
+    async fn run_periodic(self: Arc<Self>, periodic: PeriodicMessage) {
+        let what = if periodic.name.is_empty() {
+            "periodic message".to_string()
+        } else {
+            format!("'{}'", periodic.name)
+        };
+        tokio::time::sleep(Duration::from_millis(periodic.offset_ms)).await;
+        let mut ticker = tokio::time::interval(Duration::from_millis(periodic.interval_ms));
+        let mut seq = 0;
+        while periodic.count.map_or(true, |count| seq < count) {
+            ticker.tick().await;
+            let vars = Value::Object(self.vars.lock().unwrap().clone());
+            if !periodic.when.iter().all(|p| p.matches(&vars)) {
+                continue;
+            }
+            let peers = self.peers.lock().unwrap().clone();
+            if peers.is_empty() {
+                continue;
+            }
+            match periodic.message.render(&vars, seq, None) {
+                Ok(data) => {
+                    for peer in peers {
+                        self.send(peer, data.clone(), &what);
+                    }
+                }
+                Err(e) => eprintln!("[{}] {}: {}", self.name(), what, e),
+            }
+            seq += 1;
+        }
+    }
+
+    /// Run the first response matching a received frame
+    fn handle_frame(self: &Arc<Self>, frame: &[u8], from: &Peer) {
+        if self.script.impairments.drop_received() {
+            println!(
+                "[{}] ignored {} bytes from {}",
+                self.name(),
+                frame.len(),
+                from.label()
+            );
+            return;
+        }
+        println!(
+            "[{}] received {} bytes from {}",
+            self.name(),
+            frame.len(),
+            from.label()
+        );
+        for (index, response) in self.script.responses.iter().enumerate() {
+            let Some(received) = response.on.decode(frame) else {
+                continue;
+            };
+            let mut body = self.vars.lock().unwrap().clone();
+            if let Value::Object(fields) = &received.fields {
+                body.extend(fields.clone());
+            }
+            let body = Value::Object(body);
+            if !response.when.iter().all(|p| p.matches(&body)) {
+                continue;
+            }
+
+            let seq = self.replies[index].fetch_add(1, Ordering::Relaxed);
+            let what = if response.name.is_empty() {
+                format!("response {}", index + 1)
+            } else {
+                format!("'{}'", response.name)
+            };
+            println!(
+                "[{}] {} matched a {} frame",
+                self.name(),
+                what,
+                received.kind
+            );
+            if !response.set.is_empty() {
+                let mut vars = self.vars.lock().unwrap();
+                for (name, template) in &response.set {
+                    let value = render(template, &body, seq);
+                    vars.insert(name.clone(), value);
+                }
+            }
+            let Some(reply) = &response.reply else {
+                return;
+            };
+            match reply.render(&body, seq, Some(&received)) {
+                Ok(data) => {
+                    let endpoint = self.clone();
+                    let delay = Duration::from_millis(response.delay_ms);
+                    let from = from.clone();
+                    tokio::spawn(async move {
+                        tokio::time::sleep(delay).await;
+                        endpoint.send(from, data, &what);
+                    });
+                }
+                Err(e) => eprintln!("[{}] {}: {}", self.name(), what, e),
+            }
+            return;
+        }
+    }
+
+    async fn read_stream<R: AsyncRead + Unpin>(self: Arc<Self>, mut reader: R, peer: Peer) {
+        let mut pending = Vec::new();
+        let mut buf = vec![0u8; 4096];
+        loop {
+            match reader.read(&mut buf).await {
+                Ok(0) => break,
+                Ok(n) => {
+                    for frame in self.script.framing.split(&mut pending, &buf[..n]) {
+                        self.handle_frame(&frame, &peer);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("[{}] read error on {}: {}", self.name(), peer.label(), e);
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn run_udp(self: Arc<Self>) {
+        let Some(socket) = self.udp.clone() else {
+            return;
+        };
+        let follow_sender = matches!(
+            self.script.transport,
+            EndpointTransport::Udp { remote: None, .. }
+        );
+        let mut pending = Vec::new();
+        let mut buf = vec![0u8; 65536];
+        loop {
+            let (n, source) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    eprintln!("[{}] receive error: {}", self.name(), e);
+                    continue;
+                }
+            };
+            let peer = Peer::Udp(source);
+            if follow_sender {
+                *self.peers.lock().unwrap() = vec![peer.clone()];
+            }
+            for frame in self.script.framing.split(&mut pending, &buf[..n]) {
+                self.handle_frame(&frame, &peer);
+            }
+        }
+    }
+
+    async fn run_tcp_client(self: Arc<Self>, addr: String) {
+        loop {
+            match TcpStream::connect(&addr).await {
+                Ok(stream) => {
+                    println!("[{}] connected to {}", self.name(), addr);
+                    let (reader, writer) = stream.into_split();
+                    let peer = Peer::stream(addr.clone(), writer);
+                    self.add_peer(peer.clone());
+                    self.clone().read_stream(reader, peer.clone()).await;
+                    self.remove_peer(&peer);
+                    println!("[{}] disconnected from {}", self.name(), addr);
+                }
+                Err(e) => eprintln!("[{}] failed to connect to {}: {}", self.name(), addr, e),
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    async fn run_tcp_server(self: Arc<Self>, listener: TcpListener) {
+        if let Ok(addr) = listener.local_addr() {
+            println!("[{}] listening on {}", self.name(), addr);
+        }
+        // Dropped with the task, which stops every client
+        let mut clients = JoinSet::new();
+        loop {
+            while clients.try_join_next().is_some() {}
+            let (stream, addr) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    eprintln!("[{}] accept error: {}", self.name(), e);
+                    continue;
+                }
+            };
+            println!("[{}] client {} connected", self.name(), addr);
+            let (reader, writer) = stream.into_split();
+            let peer = Peer::stream(addr.to_string(), writer);
+            self.add_peer(peer.clone());
+            let endpoint = self.clone();
+            clients.spawn(async move {
+                endpoint.clone().read_stream(reader, peer.clone()).await;
+                endpoint.remove_peer(&peer);
+                println!("[{}] client {} disconnected", endpoint.name(), addr);
+            });
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::packet::Packet;
+use crate::transport::decoders::DecoderSpec;
+use crate::transport::framing::{parse_hex, Framing};
+use crate::transport::responder::{render, render_packet, FieldPredicate};
+use crate::transport::schemas::{SchemaSource, SCHEMAS};
+
+fn default_baud_rate() -> u32 {
+    115200
+}
+
+fn any_addr() -> String {
+    "0.0.0.0:0".to_string()
+}
+
+/// Devices to emulate, loaded from a TOML or JSON file
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct EmulatorScript {
+    /// Stop after this many seconds, run until Ctrl+C when unset
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+    /// Protobuf schemas for `protobuf` messages and matches
+    #[serde(default)]
+    pub schemas: Vec<SchemaSource>,
+    pub endpoints: Vec<EndpointScript>,
+}
+
+impl EmulatorScript {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read script {:?}: {}", path, e))?;
+        let is_toml = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("toml"))
+            .unwrap_or(false);
+        if is_toml {
+            toml::from_str(&content).map_err(|e| format!("TOML parse error: {e}"))
+        } else {
+            serde_json::from_str(&content).map_err(|e| format!("Deserialization error: {e}"))
+        }
+    }
+
+    /// Check the script; protobuf message types must already be loaded
+    pub fn validate(&self) -> Result<(), String> {
+        if self.endpoints.is_empty() {
+            return Err("Script has no endpoints".to_string());
+        }
+        let mut names = HashSet::new();
+        for endpoint in &self.endpoints {
+            if !names.insert(endpoint.name.as_str()) {
+                return Err(format!("Duplicate endpoint name '{}'", endpoint.name));
+            }
+            endpoint
+                .validate()
+                .map_err(|e| format!("Endpoint '{}': {}", endpoint.name, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// How an emulated device is reached
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EndpointTransport {
+    Serial {
+        port: String,
+        #[serde(default = "default_baud_rate")]
+        baud_rate: u32,
+    },
+    /// Periodic messages go to `remote`, or to the last sender when unset
+    Udp {
+        #[serde(default = "any_addr")]
+        bind: String,
+        #[serde(default)]
+        remote: Option<String>,
+    },
+    /// Connects to `addr`, reconnecting every second while it is unreachable
+    TcpClient { addr: String },
+    /// Accepts clients on `bind`; periodic messages go to every connected client
+    TcpServer { bind: String },
+}
+
+/// Network conditions applied to an endpoint's traffic
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Impairments {
+    /// Delay added to every sent frame
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Up to this much is randomly added to or taken off the latency
+    #[serde(default)]
+    pub jitter_ms: u64,
+    /// Probability of dropping a sent frame, 0 to 1
+    #[serde(default)]
+    pub loss: f64,
+    /// Probability of ignoring a received frame, 0 to 1
+    #[serde(default)]
+    pub receive_loss: f64,
+}
+
+impl Impairments {
+    fn validate(&self) -> Result<(), String> {
+        for (name, p) in [("loss", self.loss), ("receive_loss", self.receive_loss)] {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(format!("{} must be between 0 and 1, not {}", name, p));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn drop_sent(&self) -> bool {
+        self.loss > 0.0 && rand::random::<f64>() < self.loss
+    }
+
+    pub fn drop_received(&self) -> bool {
+        self.receive_loss > 0.0 && rand::random::<f64>() < self.receive_loss
+    }
+
+    /// Latency of one sent frame, with jitter
+    pub fn delay(&self) -> Duration {
+        let jitter = self.jitter_ms as i64;
+        let offset = if jitter > 0 {
+            rand::random::<i64>().rem_euclid(2 * jitter + 1) - jitter
+        } else {
+            0
+        };
+        Duration::from_millis((self.latency_ms as i64 + offset).max(0) as u64)
+    }
+}
+
+/// Bytes to send, with `{{...}}` placeholders filled as in responder replies. Endpoint
+/// variables can be used by name; in responses, so can the received frame's fields.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageTemplate {
+    /// `Packet` JSON, e.g. `{"kind": {"Header": {"id": "{{seq}}"}}}`. A reply to a packet
+    /// of the same kind copies the fields it leaves out.
+    Packet(Value),
+    /// Protobuf JSON of a message type from `schemas`
+    Protobuf {
+        message: String,
+        value: Value,
+    },
+    Text(String),
+    Hex(String),
+}
+
+impl MessageTemplate {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            MessageTemplate::Protobuf { message, .. } if SCHEMAS.message(message).is_none() => {
+                Err(format!("Message type {} is not loaded", message))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Encode the message as number `seq`, in reply to `received` when set
+    pub fn render(
+        &self,
+        body: &Value,
+        seq: u64,
+        received: Option<&ReceivedFrame>,
+    ) -> Result<Vec<u8>, String> {
+        match self {
+            MessageTemplate::Packet(template) => {
+                let received = received
+                    .filter(|frame| frame.is_packet)
+                    .map(|frame| (frame.kind.as_str(), &frame.fields));
+                Ok(render_packet(template, body, seq, received)?.encode_to_vec())
+            }
+            MessageTemplate::Protobuf { message, value } => {
+                SCHEMAS.encode_json(message, &render(value, body, seq))
+            }
+            MessageTemplate::Text(text) => Ok(render_text(text, body, seq).into_bytes()),
+            MessageTemplate::Hex(hex) => parse_hex(&render_text(hex, body, seq)),
+        }
+    }
+}
+
+fn render_text(text: &str, body: &Value, seq: u64) -> String {
+    match render(&Value::String(text.to_string()), body, seq) {
+        Value::String(text) => text,
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// A number when `text` is one, otherwise the text
+fn scalar(text: &str) -> Value {
+    if let Ok(number) = text.parse::<i64>() {
+        return Value::from(number);
+    }
+    match text.parse::<f64>() {
+        Ok(number) => Value::from(number),
+        Err(_) => Value::from(text),
+    }
+}
+
+/// What a response reacts to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum FrameMatch {
+    /// `Packet` frames, of `kind` when set; fields are the packet kind's
+    Packet {
+        #[serde(default)]
+        kind: Option<String>,
+    },
+    /// Frames decoding as a message type from `schemas`
+    Protobuf { message: String },
+    /// UTF-8 frames starting with `prefix`; fields are `text`, trimmed, and `rest`, what
+    /// follows the prefix, as a number when it is one
+    Text {
+        #[serde(default)]
+        prefix: String,
+    },
+    /// Every frame; fields are `len`, `hex` and `text`
+    Any,
+}
+
+/// A received frame as matched by `FrameMatch`
+#[derive(Clone, Debug)]
+pub struct ReceivedFrame {
+    /// Packet kind, message type, `text` or `raw`
+    pub kind: String,
+    pub fields: Value,
+    is_packet: bool,
+}
+
+impl FrameMatch {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            FrameMatch::Protobuf { message } if SCHEMAS.message(message).is_none() => {
+                Err(format!("Message type {} is not loaded", message))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn decode(&self, frame: &[u8]) -> Option<ReceivedFrame> {
+        match self {
+            FrameMatch::Packet { kind } => {
+                let packet = Packet::decode(frame).ok()?;
+                packet.kind.as_ref()?;
+                let Value::Object(mut json) = serde_json::to_value(&packet).ok()? else {
+                    return None;
+                };
+                let Some(Value::Object(packet_kind)) = json.remove("kind") else {
+                    return None;
+                };
+                let (name, fields) = packet_kind.into_iter().next()?;
+                if let Some(wanted) = kind {
+                    if !wanted.eq_ignore_ascii_case(&name) {
+                        return None;
+                    }
+                }
+                Some(ReceivedFrame {
+                    kind: name,
+                    fields,
+                    is_packet: true,
+                })
+            }
+            FrameMatch::Protobuf { message } => Some(ReceivedFrame {
+                kind: message.clone(),
+                fields: SCHEMAS.decode_json(message, frame).ok()?,
+                is_packet: false,
+            }),
+            FrameMatch::Text { prefix } => {
+                let text = std::str::from_utf8(frame).ok()?.trim();
+                let rest = text.strip_prefix(prefix.as_str())?.trim();
+                let mut fields = Map::new();
+                fields.insert("text".to_string(), Value::from(text));
+                fields.insert("rest".to_string(), scalar(rest));
+                Some(ReceivedFrame {
+                    kind: "text".to_string(),
+                    fields: Value::Object(fields),
+                    is_packet: false,
+                })
+            }
+            FrameMatch::Any => Some(ReceivedFrame {
+                kind: "raw".to_string(),
+                fields: DecoderSpec::Raw.decode(frame)?,
+                is_packet: false,
+            }),
+        }
+    }
+}
+
+/// A message sent every `interval_ms`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PeriodicMessage {
+    #[serde(default)]
+    pub name: String,
+    pub interval_ms: u64,
+    /// Wait before the first message, to stagger messages with the same interval
+    #[serde(default)]
+    pub offset_ms: u64,
+    /// Stop after this many messages when set
+    #[serde(default)]
+    pub count: Option<u64>,
+    /// Only sent while all hold for the endpoint's variables
+    #[serde(default)]
+    pub when: Vec<FieldPredicate>,
+    pub message: MessageTemplate,
+}
+
+/// Reaction to a received frame; the first matching response of an endpoint wins
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Response {
+    #[serde(default)]
+    pub name: String,
+    pub on: FrameMatch,
+    /// All must hold for the frame's fields together with the endpoint's variables
+    #[serde(default)]
+    pub when: Vec<FieldPredicate>,
+    /// Sent back to where the frame came from
+    #[serde(default)]
+    pub reply: Option<MessageTemplate>,
+    /// Endpoint variables to update, templated like replies
+    #[serde(default)]
+    pub set: Map<String, Value>,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// One emulated device
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EndpointScript {
+    pub name: String,
+    pub transport: EndpointTransport,
+    /// How sent messages are wrapped and received bytes split
+    #[serde(default)]
+    pub framing: Framing,
+    #[serde(default, flatten)]
+    pub impairments: Impairments,
+    /// Initial variables, readable in templates and changed by responses
+    #[serde(default)]
+    pub vars: Map<String, Value>,
+    #[serde(default)]
+    pub periodic: Vec<PeriodicMessage>,
+    #[serde(default)]
+    pub responses: Vec<Response>,
+}
+
+impl EndpointScript {
+    fn validate(&self) -> Result<(), String> {
+        self.framing.validate()?;
+        self.impairments.validate()?;
+        for periodic in &self.periodic {
+            if periodic.interval_ms == 0 {
+                return Err(format!(
+                    "Periodic message '{}' needs an interval greater than 0",
+                    periodic.name
+                ));
+            }
+            periodic.message.validate()?;
+        }
+        for response in &self.responses {
+            response.on.validate()?;
+            if let Some(reply) = &response.reply {
+                reply.validate()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 // pub mod commands;
 
+pub mod scenario;
 pub mod simulation_commands;
 pub mod timer_res;
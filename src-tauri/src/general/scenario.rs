@@ -0,0 +1,137 @@
+use crate::general::simulation_commands::{
+    collect_target_packets, run_simulation, SimulationDataState,
+};
+use crate::simulation::{Simulation, SimulationResultList};
+use crate::transport::connection_manager::Manager;
+use crate::transport::{ScenarioRun, StreamPlan};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::Mutex;
+
+/// A simulation input together with the plan for streaming its targets
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Scenario {
+    pub name: String,
+    pub simulation: Simulation,
+    #[serde(default)]
+    pub streams: Vec<StreamPlan>,
+}
+
+pub type ScenarioState = Arc<Mutex<Option<Scenario>>>;
+
+impl Scenario {
+    /// Read a scenario from a `.toml` or `.json` file
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read scenario file {:?}: {}", path, e))?;
+        let is_toml = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("toml"))
+            .unwrap_or(false);
+        let scenario: Scenario = if is_toml {
+            toml::from_str(&content).map_err(|e| format!("TOML parse error: {e}"))?
+        } else {
+            serde_json::from_str(&content).map_err(|e| format!("Deserialization error: {e}"))?
+        };
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.simulation.targets.is_empty() {
+            return Err(format!("Scenario '{}' has no targets", self.name));
+        }
+        if self.simulation.time_step <= 0.0 || self.simulation.max_time <= 0.0 {
+            return Err(format!(
+                "Scenario '{}' needs a positive time_step and max_time",
+                self.name
+            ));
+        }
+        for plan in &self.streams {
+            if plan.connection_id.is_none()
+                && (plan.local_addr.is_none() || plan.remote_addr.is_none())
+            {
+                return Err(format!(
+                    "Scenario '{}': stream plan for targets {:?} needs a connection_id or both local_addr and remote_addr",
+                    self.name, plan.target_ids
+                ));
+            }
+            for target_id in &plan.target_ids {
+                if !self.simulation.targets.iter().any(|t| t.id == *target_id) {
+                    return Err(format!(
+                        "Scenario '{}': stream plan references unknown target {}",
+                        self.name, target_id
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Pair every stream plan with the simulated packets it should send
+    pub fn stream_packets(
+        &self,
+        sim_results: &SimulationResultList,
+    ) -> Vec<(StreamPlan, Vec<crate::packet::TargetPacket>)> {
+        self.streams
+            .iter()
+            .map(|plan| {
+                (
+                    plan.clone(),
+                    collect_target_packets(sim_results, &plan.target_ids),
+                )
+            })
+            .collect()
+    }
+}
+
+#[tauri::command]
+pub async fn load_scenario(
+    scenario_state: State<'_, ScenarioState>,
+    path: String,
+) -> Result<Scenario, String> {
+    let scenario = Scenario::from_file(Path::new(&path))?;
+    *scenario_state.lock().await = Some(scenario.clone());
+    Ok(scenario)
+}
+
+#[tauri::command]
+pub async fn get_scenario(
+    scenario_state: State<'_, ScenarioState>,
+) -> Result<Option<Scenario>, String> {
+    Ok(scenario_state.lock().await.clone())
+}
+
+/// Run the loaded scenario's simulation and start all of its streams
+#[tauri::command]
+pub async fn run_scenario(
+    app: tauri::AppHandle,
+    manager: State<'_, Manager>,
+    sim_state: State<'_, SimulationDataState>,
+    scenario_state: State<'_, ScenarioState>,
+) -> Result<ScenarioRun, String> {
+    let scenario = scenario_state
+        .lock()
+        .await
+        .clone()
+        .ok_or("No scenario loaded. Load a scenario first.")?;
+    let sim_results = run_simulation(&app, &scenario.simulation).await?;
+    *sim_state.lock().await = Some(sim_results.clone());
+    manager
+        .run_scenario_streams(&scenario.name, scenario.stream_packets(&sim_results))
+        .await
+}
+
+#[tauri::command]
+pub async fn stop_scenario(manager: State<'_, Manager>) -> Result<(), String> {
+    manager.stop_scenario().await
+}
+
+#[tauri::command]
+pub async fn get_active_scenario(
+    manager: State<'_, Manager>,
+) -> Result<Option<ScenarioRun>, String> {
+    Ok(manager.active_scenario.lock().await.clone())
+}
@@ -1,3 +1,4 @@
+use crate::packet::TargetPacket;
 use crate::simulation::{Simulation, SimulationResultList};
 use base64::{engine::general_purpose, Engine as _};
 
@@ -15,29 +16,64 @@ pub async fn simulation(
     sim: Simulation,
     sim_state: State<'_, SimulationDataState>,
 ) -> Result<String, String> {
+    let sim_results = run_simulation(&app, &sim).await?;
+    // Save to state
+    let mut state = sim_state.lock().await;
+    *state = Some(sim_results.clone());
+    Ok(serde_json::to_string(&sim_results)
+        .unwrap_or_else(|e| format!("Failed to serialize simulation results: {}", e)))
+}
+
+/// Run the `sim` sidecar for `sim` and decode its output
+pub async fn run_simulation(
+    app: &tauri::AppHandle,
+    sim: &Simulation,
+) -> Result<SimulationResultList, String> {
     let sidecar_command = app
         .shell()
         .sidecar("sim")
-        .unwrap()
+        .map_err(|e| e.to_string())?
         .arg("--json")
-        .arg(serde_json::to_string(&sim).map_err(|e| e.to_string())?);
-    let output = sidecar_command.output().await.unwrap();
-    let b64 = String::from_utf8(output.stdout).unwrap();
-    let buffer = general_purpose::STANDARD.decode(b64.trim()).unwrap();
-    eprintln!("[DEBUG] Decoded bytes: {}", buffer.len());
+        .arg(serde_json::to_string(sim).map_err(|e| e.to_string())?);
+    let output = sidecar_command.output().await.map_err(|e| e.to_string())?;
     if !output.status.success() {
         return Err(String::from_utf8_lossy(&output.stderr).to_string());
     }
-    match SimulationResultList::decode(&*buffer) {
-        Ok(sim_results) => {
-            // Save to state
-            let mut state = sim_state.lock().await;
-            *state = Some(sim_results.clone());
-            Ok(serde_json::to_string(&sim_results)
-                .unwrap_or_else(|e| format!("Failed to serialize simulation results: {}", e)))
+    decode_simulation_output(&output.stdout)
+}
+
+/// Decode the base64-encoded `SimulationResultList` printed by the simulator
+pub fn decode_simulation_output(stdout: &[u8]) -> Result<SimulationResultList, String> {
+    let b64 = String::from_utf8_lossy(stdout);
+    let buffer = general_purpose::STANDARD
+        .decode(b64.trim())
+        .map_err(|e| format!("Failed to decode simulation output: {}", e))?;
+    tracing::debug!("Decoded {} bytes of simulation output", buffer.len());
+    SimulationResultList::decode(&*buffer)
+        .map_err(|e| format!("Failed to decode simulation output: {}", e))
+}
+
+/// Flatten simulation results into `TargetPacket`s, keeping only `target_ids` when not empty
+pub fn collect_target_packets(
+    sim_results: &SimulationResultList,
+    target_ids: &[u32],
+) -> Vec<TargetPacket> {
+    let mut packets = Vec::new();
+    for result in &sim_results.results {
+        if !target_ids.is_empty() && !target_ids.contains(&result.target_id) {
+            continue;
+        }
+        for state in &result.final_state {
+            packets.push(TargetPacket {
+                target_id: result.target_id,
+                lat: state.lat,
+                lon: state.lon,
+                alt: state.alt,
+                time: state.time.unwrap_or(0.0),
+            });
         }
-        Err(e) => Err(format!("Failed to decode simulation output: {}", e)),
     }
+    packets
 }
 
 #[tauri::command]
@@ -0,0 +1,561 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, RwLock};
+
+use crate::geo::geodesy::GeoPoint;
+use crate::packet::Packet;
+use crate::transport::tracks::now_ms;
+
+/// Horizontal extent of a geofence
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FenceShape {
+    /// Closed polygon; edges are straight in lat/lon, fine for fences up to a few tens of km
+    Polygon { vertices: Vec<GeoPoint> },
+    /// Circle of `radius` metres around `center`
+    Circle { center: GeoPoint, radius: f64 },
+    /// No horizontal limit, only the fence's altitude band applies
+    AltitudeBand,
+}
+
+/// Which transitions of a geofence raise alerts
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FenceTrigger {
+    Enter,
+    Exit,
+    #[default]
+    Both,
+}
+
+/// Packet sent to a connection whenever a rule raises an alert
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AlertAction {
+    pub connection_id: String,
+    pub packet: Packet,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Geofence {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub shape: FenceShape,
+    /// Altitude band in metres, open-ended when unset
+    #[serde(default)]
+    pub min_alt: Option<f64>,
+    #[serde(default)]
+    pub max_alt: Option<f64>,
+    /// Targets the fence applies to, every target when empty
+    #[serde(default)]
+    pub target_ids: Vec<u32>,
+    #[serde(default)]
+    pub trigger: FenceTrigger,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub action: Option<AlertAction>,
+}
+
+/// Raise a violation when two targets come closer than `min_distance` metres
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SeparationRule {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    /// Targets whose pairs are checked, every pair when empty
+    #[serde(default)]
+    pub target_ids: Vec<u32>,
+    /// Minimum straight-line distance in metres
+    pub min_distance: f64,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub action: Option<AlertAction>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// Every fence and separation rule, as persisted in the store
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AlertRules {
+    #[serde(default)]
+    pub fences: Vec<Geofence>,
+    #[serde(default)]
+    pub separations: Vec<SeparationRule>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertKind {
+    Enter,
+    Exit,
+    Violation,
+    /// A separation violation ended
+    Cleared,
+}
+
+/// Emitted as `alert`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AlertEvent {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub kind: AlertKind,
+    pub target_id: u32,
+    /// The other target of a separation rule
+    pub other_target_id: Option<u32>,
+    pub position: GeoPoint,
+    /// Distance between the two targets of a separation rule, in metres
+    pub distance: Option<f64>,
+    pub time_ms: u64,
+    #[serde(skip)]
+    pub action: Option<AlertAction>,
+}
+
+/// Current fence occupancy and separation violations
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ActiveAlerts {
+    /// Fence id to the targets inside it
+    pub inside: HashMap<String, Vec<u32>>,
+    /// Separation rule id to the target pairs violating it
+    pub violations: HashMap<String, Vec<(u32, u32)>>,
+}
+
+impl Geofence {
+    fn applies_to(&self, target_id: u32) -> bool {
+        self.target_ids.is_empty() || self.target_ids.contains(&target_id)
+    }
+
+    pub fn contains(&self, p: &GeoPoint) -> bool {
+        if self.min_alt.is_some_and(|min| p.alt < min)
+            || self.max_alt.is_some_and(|max| p.alt > max)
+        {
+            return false;
+        }
+        match &self.shape {
+            FenceShape::Polygon { vertices } => polygon_contains(vertices, p),
+            FenceShape::Circle { center, radius } => {
+                center.range_bearing(p).ground_range <= *radius
+            }
+            FenceShape::AltitudeBand => true,
+        }
+    }
+}
+
+impl SeparationRule {
+    fn applies_to(&self, target_id: u32) -> bool {
+        self.target_ids.is_empty() || self.target_ids.contains(&target_id)
+    }
+}
+
+/// Even-odd ray casting on lat/lon
+fn polygon_contains(vertices: &[GeoPoint], p: &GeoPoint) -> bool {
+    if vertices.len() < 3 {
+        return false;
+    }
+    let mut inside = false;
+    let mut j = vertices.len() - 1;
+    for i in 0..vertices.len() {
+        let (a, b) = (&vertices[i], &vertices[j]);
+        if (a.lat > p.lat) != (b.lat > p.lat)
+            && p.lon < (b.lon - a.lon) * (p.lat - a.lat) / (b.lat - a.lat) + a.lon
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Checks target positions against the fences and separation rules, remembering which
+/// targets are inside which fence so only transitions raise alerts
+#[derive(Debug, Default)]
+pub struct AlertEngine {
+    rules: RwLock<AlertRules>,
+    inside: Mutex<HashSet<(String, u32)>>,
+    violations: Mutex<HashSet<(String, u32, u32)>>,
+}
+
+impl AlertEngine {
+    pub fn rules(&self) -> AlertRules {
+        self.rules.read().unwrap().clone()
+    }
+
+    pub fn set_rules(&self, rules: AlertRules) {
+        *self.rules.write().unwrap() = rules;
+        self.inside.lock().unwrap().clear();
+        self.violations.lock().unwrap().clear();
+    }
+
+    /// Add or replace a fence by id; an empty id gets a new one, which is returned
+    pub fn save_fence(&self, mut fence: Geofence) -> String {
+        if fence.id.is_empty() {
+            fence.id = uuid::Uuid::new_v4().to_string();
+        }
+        let id = fence.id.clone();
+        let mut rules = self.rules.write().unwrap();
+        rules.fences.retain(|f| f.id != id);
+        rules.fences.push(fence);
+        self.inside
+            .lock()
+            .unwrap()
+            .retain(|(fence_id, _)| *fence_id != id);
+        id
+    }
+
+    pub fn delete_fence(&self, id: &str) -> bool {
+        let mut rules = self.rules.write().unwrap();
+        let before = rules.fences.len();
+        rules.fences.retain(|f| f.id != id);
+        self.inside
+            .lock()
+            .unwrap()
+            .retain(|(fence_id, _)| fence_id != id);
+        rules.fences.len() != before
+    }
+
+    /// Add or replace a separation rule by id; an empty id gets a new one, which is returned
+    pub fn save_separation(&self, mut rule: SeparationRule) -> String {
+        if rule.id.is_empty() {
+            rule.id = uuid::Uuid::new_v4().to_string();
+        }
+        let id = rule.id.clone();
+        let mut rules = self.rules.write().unwrap();
+        rules.separations.retain(|r| r.id != id);
+        rules.separations.push(rule);
+        self.violations
+            .lock()
+            .unwrap()
+            .retain(|(rule_id, _, _)| *rule_id != id);
+        id
+    }
+
+    pub fn delete_separation(&self, id: &str) -> bool {
+        let mut rules = self.rules.write().unwrap();
+        let before = rules.separations.len();
+        rules.separations.retain(|r| r.id != id);
+        self.violations
+            .lock()
+            .unwrap()
+            .retain(|(rule_id, _, _)| rule_id != id);
+        rules.separations.len() != before
+    }
+
+    pub fn active(&self) -> ActiveAlerts {
+        let mut active = ActiveAlerts::default();
+        for (fence_id, target_id) in self.inside.lock().unwrap().iter() {
+            active
+                .inside
+                .entry(fence_id.clone())
+                .or_default()
+                .push(*target_id);
+        }
+        for (rule_id, a, b) in self.violations.lock().unwrap().iter() {
+            active
+                .violations
+                .entry(rule_id.clone())
+                .or_default()
+                .push((*a, *b));
+        }
+        active
+    }
+
+    /// Check the current position of every target and return the alerts raised since the
+    /// previous call. Targets missing from `targets` are forgotten without an alert.
+    pub fn evaluate(&self, targets: &[(u32, GeoPoint)]) -> Vec<AlertEvent> {
+        let rules = self.rules.read().unwrap();
+        let now = now_ms();
+        let mut events = Vec::new();
+        let present: HashSet<u32> = targets.iter().map(|(id, _)| *id).collect();
+
+        let mut inside = self.inside.lock().unwrap();
+        inside.retain(|(_, target_id)| present.contains(target_id));
+        for fence in rules.fences.iter().filter(|f| f.enabled) {
+            for (target_id, position) in targets.iter().filter(|(id, _)| fence.applies_to(*id)) {
+                let key = (fence.id.clone(), *target_id);
+                let kind = match (fence.contains(position), inside.contains(&key)) {
+                    (true, false) => {
+                        inside.insert(key);
+                        AlertKind::Enter
+                    }
+                    (false, true) => {
+                        inside.remove(&key);
+                        AlertKind::Exit
+                    }
+                    _ => continue,
+                };
+                let wanted = match fence.trigger {
+                    FenceTrigger::Enter => kind == AlertKind::Enter,
+                    FenceTrigger::Exit => kind == AlertKind::Exit,
+                    FenceTrigger::Both => true,
+                };
+                if wanted {
+                    events.push(AlertEvent {
+                        rule_id: fence.id.clone(),
+                        rule_name: fence.name.clone(),
+                        kind,
+                        target_id: *target_id,
+                        other_target_id: None,
+                        position: *position,
+                        distance: None,
+                        time_ms: now,
+                        action: fence.action.clone(),
+                    });
+                }
+            }
+        }
+
+        let mut violations = self.violations.lock().unwrap();
+        violations.retain(|(_, a, b)| present.contains(a) && present.contains(b));
+        for rule in rules.separations.iter().filter(|r| r.enabled) {
+            let mut checked: Vec<&(u32, GeoPoint)> = targets
+                .iter()
+                .filter(|(id, _)| rule.applies_to(*id))
+                .collect();
+            // Pairs are keyed lowest id first
+            checked.sort_by_key(|(id, _)| *id);
+            for (i, &&(a, pa)) in checked.iter().enumerate() {
+                for &&(b, pb) in &checked[i + 1..] {
+                    let distance = pa.range_bearing(&pb).slant_range;
+                    let key = (rule.id.clone(), a, b);
+                    let kind = match (distance < rule.min_distance, violations.contains(&key)) {
+                        (true, false) => {
+                            violations.insert(key);
+                            AlertKind::Violation
+                        }
+                        (false, true) => {
+                            violations.remove(&key);
+                            AlertKind::Cleared
+                        }
+                        _ => continue,
+                    };
+                    events.push(AlertEvent {
+                        rule_id: rule.id.clone(),
+                        rule_name: rule.name.clone(),
+                        kind,
+                        target_id: a,
+                        other_target_id: Some(b),
+                        position: pa,
+                        distance: Some(distance),
+                        time_ms: now,
+                        // Only the violation itself triggers the outgoing packet
+                        action: rule.action.clone().filter(|_| kind == AlertKind::Violation),
+                    });
+                }
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fence(shape: FenceShape, trigger: FenceTrigger) -> Geofence {
+        Geofence {
+            id: "fence".to_string(),
+            name: "Fence".to_string(),
+            shape,
+            min_alt: None,
+            max_alt: None,
+            target_ids: Vec::new(),
+            trigger,
+            enabled: true,
+            action: None,
+        }
+    }
+
+    fn separation(min_distance: f64) -> SeparationRule {
+        SeparationRule {
+            id: "sep".to_string(),
+            name: "Separation".to_string(),
+            target_ids: Vec::new(),
+            min_distance,
+            enabled: true,
+            action: Some(AlertAction {
+                connection_id: "udp_1".to_string(),
+                packet: Packet::default(),
+            }),
+        }
+    }
+
+    fn engine(rules: AlertRules) -> AlertEngine {
+        let engine = AlertEngine::default();
+        engine.set_rules(rules);
+        engine
+    }
+
+    fn square(size: f64) -> FenceShape {
+        FenceShape::Polygon {
+            vertices: vec![
+                GeoPoint::new(0.0, 0.0, 0.0),
+                GeoPoint::new(0.0, size, 0.0),
+                GeoPoint::new(size, size, 0.0),
+                GeoPoint::new(size, 0.0, 0.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn polygon_contains_points() {
+        let FenceShape::Polygon { vertices } = square(1.0) else {
+            unreachable!()
+        };
+        assert!(polygon_contains(&vertices, &GeoPoint::new(0.5, 0.5, 0.0)));
+        assert!(!polygon_contains(&vertices, &GeoPoint::new(1.5, 0.5, 0.0)));
+        assert!(!polygon_contains(&vertices, &GeoPoint::new(0.5, -0.5, 0.0)));
+        assert!(!polygon_contains(
+            &vertices[..2],
+            &GeoPoint::new(0.0, 0.5, 0.0)
+        ));
+    }
+
+    #[test]
+    fn fences_alert_on_their_triggers() {
+        // Each shape with a point inside and one outside it
+        let shapes = [
+            (
+                square(0.05),
+                GeoPoint::new(0.01, 0.01, 0.0),
+                GeoPoint::new(0.1, 0.1, 0.0),
+            ),
+            (
+                FenceShape::Circle {
+                    center: GeoPoint::new(0.0, 0.0, 0.0),
+                    radius: 1_000.0,
+                },
+                GeoPoint::new(0.001, 0.0, 0.0),
+                GeoPoint::new(0.1, 0.0, 0.0),
+            ),
+            (
+                FenceShape::AltitudeBand,
+                GeoPoint::new(0.0, 0.0, 1_500.0),
+                GeoPoint::new(0.0, 0.0, 500.0),
+            ),
+        ];
+        for (shape, inside, outside) in shapes {
+            for (trigger, expected) in [
+                (FenceTrigger::Both, vec![AlertKind::Enter, AlertKind::Exit]),
+                (FenceTrigger::Enter, vec![AlertKind::Enter]),
+                (FenceTrigger::Exit, vec![AlertKind::Exit]),
+            ] {
+                let mut fence = fence(shape.clone(), trigger);
+                if matches!(shape, FenceShape::AltitudeBand) {
+                    fence.min_alt = Some(1_000.0);
+                    fence.max_alt = Some(2_000.0);
+                }
+                let engine = engine(AlertRules {
+                    fences: vec![fence],
+                    separations: Vec::new(),
+                });
+                let mut kinds = Vec::new();
+                for position in [outside, inside, inside, outside] {
+                    for event in engine.evaluate(&[(7, position)]) {
+                        assert_eq!((event.rule_id.as_str(), event.target_id), ("fence", 7));
+                        assert_eq!(event.position, position);
+                        kinds.push(event.kind);
+                    }
+                }
+                assert_eq!(kinds, expected, "{:?} {:?}", shape, trigger);
+            }
+        }
+    }
+
+    #[test]
+    fn fences_skip_other_targets_and_altitudes() {
+        let mut fence = fence(
+            FenceShape::Circle {
+                center: GeoPoint::new(0.0, 0.0, 0.0),
+                radius: 1_000.0,
+            },
+            FenceTrigger::Both,
+        );
+        fence.max_alt = Some(1_000.0);
+        fence.target_ids = vec![1, 2];
+        let engine = engine(AlertRules {
+            fences: vec![fence],
+            separations: Vec::new(),
+        });
+        let events = engine.evaluate(&[
+            (1, GeoPoint::new(0.0, 0.0, 2_000.0)),
+            (2, GeoPoint::new(0.0, 0.0, 500.0)),
+            (3, GeoPoint::new(0.0, 0.0, 500.0)),
+        ]);
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].target_id, events[0].kind), (2, AlertKind::Enter));
+    }
+
+    #[test]
+    fn separation_violation_then_cleared() {
+        let engine = engine(AlertRules {
+            fences: Vec::new(),
+            separations: vec![separation(1_000.0)],
+        });
+        let origin = GeoPoint::new(0.0, 0.0, 0.0);
+        let far = GeoPoint::new(0.1, 0.0, 0.0);
+        let near = GeoPoint::new(0.001, 0.0, 0.0);
+
+        assert!(engine.evaluate(&[(2, far), (1, origin)]).is_empty());
+        let events = engine.evaluate(&[(2, near), (1, origin)]);
+        assert_eq!(events.len(), 1);
+        let violation = &events[0];
+        assert_eq!(violation.kind, AlertKind::Violation);
+        assert_eq!(
+            (violation.target_id, violation.other_target_id),
+            (1, Some(2))
+        );
+        assert!(violation.distance.unwrap() < 1_000.0);
+        assert!(violation.action.is_some());
+        assert_eq!(engine.active().violations["sep"], vec![(1, 2)]);
+
+        assert!(engine.evaluate(&[(2, near), (1, origin)]).is_empty());
+        let events = engine.evaluate(&[(2, far), (1, origin)]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, AlertKind::Cleared);
+        assert!(events[0].action.is_none());
+        assert!(engine.active().violations.is_empty());
+    }
+
+    #[test]
+    fn vanished_targets_raise_no_alert() {
+        let engine = engine(AlertRules {
+            fences: vec![fence(
+                FenceShape::Circle {
+                    center: GeoPoint::new(0.0, 0.0, 0.0),
+                    radius: 1_000.0,
+                },
+                FenceTrigger::Both,
+            )],
+            separations: vec![separation(1_000.0)],
+        });
+        let a = GeoPoint::new(0.0, 0.0, 0.0);
+        let b = GeoPoint::new(0.001, 0.0, 0.0);
+        let kinds: Vec<AlertKind> = engine
+            .evaluate(&[(1, a), (2, b)])
+            .iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![AlertKind::Enter, AlertKind::Enter, AlertKind::Violation]
+        );
+
+        assert!(engine.evaluate(&[(1, a)]).is_empty());
+        let active = engine.active();
+        assert_eq!(active.inside["fence"], vec![1]);
+        assert!(active.violations.is_empty());
+
+        // Coming back counts as a new entry
+        let kinds: Vec<AlertKind> = engine
+            .evaluate(&[(1, a), (2, b)])
+            .iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(kinds, vec![AlertKind::Enter, AlertKind::Violation]);
+    }
+}
@@ -0,0 +1,299 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+use tokio::time;
+
+use crate::geo::alerts::{ActiveAlerts, AlertEngine, AlertRules, Geofence, SeparationRule};
+use crate::geo::geodesy::{
+    derive_kinematics, AngleUnit, Ecef, Enu, GeoPoint, Kinematics, RangeBearing,
+};
+use crate::storage::store::{load_alert_rules, save_alert_rules};
+use crate::transport::connection_manager::Manager;
+use crate::transport::tracks::TrackPoint;
+
+/// Reference site and unit conventions used by the geo commands
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct GeoSettings {
+    /// Observer position for `reference` endpoints and ENU conversions
+    pub reference: Option<GeoPoint>,
+    /// Unit of the lat/lon in received `TargetPacket`s
+    pub target_units: AngleUnit,
+}
+
+pub type GeoSettingsState = Arc<RwLock<GeoSettings>>;
+pub type AlertEngineState = Arc<AlertEngine>;
+
+/// One end of a range/bearing query
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GeoEndpoint {
+    Point(GeoPoint),
+    /// Latest fix of a target, from one UDP connection's tracks or the fused picture
+    Target {
+        target_id: u32,
+        connection_id: Option<String>,
+    },
+    /// The configured reference site
+    Reference,
+}
+
+/// A position in every supported frame
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GeoFrames {
+    pub geodetic: GeoPoint,
+    pub ecef: Ecef,
+    /// Relative to the reference site, when one is set
+    pub enu: Option<Enu>,
+}
+
+fn resolve(
+    manager: &Manager,
+    settings: &GeoSettings,
+    endpoint: &GeoEndpoint,
+) -> Result<GeoPoint, String> {
+    match endpoint {
+        GeoEndpoint::Point(point) => Ok(*point),
+        GeoEndpoint::Reference => settings
+            .reference
+            .ok_or_else(|| "No reference site configured".to_string()),
+        GeoEndpoint::Target {
+            target_id,
+            connection_id: Some(connection_id),
+        } => {
+            let latest =
+                manager.with_udp(connection_id, |udp| Ok(udp.tracks.latest(*target_id)))?;
+            latest
+                .map(|tp| GeoPoint::from_target(&tp, settings.target_units))
+                .ok_or_else(|| format!("No track for target {} on {}", target_id, connection_id))
+        }
+        GeoEndpoint::Target {
+            target_id,
+            connection_id: None,
+        } => manager
+            .picture
+            .get(*target_id)
+            .map(|t| GeoPoint::from_target(&t.packet, settings.target_units))
+            .ok_or_else(|| format!("Target {} is not in the fused picture", target_id)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_geo_settings(state: State<'_, GeoSettingsState>) -> Result<GeoSettings, String> {
+    Ok(state.read().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn set_geo_settings(
+    state: State<'_, GeoSettingsState>,
+    settings: GeoSettings,
+) -> Result<(), String> {
+    *state.write().unwrap() = settings;
+    Ok(())
+}
+
+/// Range, bearing and elevation from `from` to `to`; `from` defaults to the reference site
+#[tauri::command]
+pub async fn geo_range_bearing(
+    manager: State<'_, Manager>,
+    state: State<'_, GeoSettingsState>,
+    from: Option<GeoEndpoint>,
+    to: GeoEndpoint,
+) -> Result<RangeBearing, String> {
+    let settings = state.read().unwrap().clone();
+    let from = resolve(&manager, &settings, &from.unwrap_or(GeoEndpoint::Reference))?;
+    let to = resolve(&manager, &settings, &to)?;
+    Ok(from.range_bearing(&to))
+}
+
+/// Express a geodetic position in ECEF and, if a reference site is set, local ENU
+#[tauri::command]
+pub async fn geo_from_geodetic(
+    state: State<'_, GeoSettingsState>,
+    point: GeoPoint,
+) -> Result<GeoFrames, String> {
+    let reference = state.read().unwrap().reference;
+    Ok(GeoFrames {
+        geodetic: point,
+        ecef: point.to_ecef(),
+        enu: reference.map(|r| r.enu_of(&point)),
+    })
+}
+
+#[tauri::command]
+pub async fn geo_from_ecef(
+    state: State<'_, GeoSettingsState>,
+    ecef: Ecef,
+) -> Result<GeoFrames, String> {
+    let reference = state.read().unwrap().reference;
+    let point = GeoPoint::from_ecef(ecef);
+    Ok(GeoFrames {
+        geodetic: point,
+        ecef,
+        enu: reference.map(|r| r.enu_of(&point)),
+    })
+}
+
+/// Convert ENU offsets from the reference site, or from `origin` when given
+#[tauri::command]
+pub async fn geo_from_enu(
+    state: State<'_, GeoSettingsState>,
+    enu: Enu,
+    origin: Option<GeoPoint>,
+) -> Result<GeoFrames, String> {
+    let origin = origin
+        .or(state.read().unwrap().reference)
+        .ok_or_else(|| "No origin given and no reference site configured".to_string())?;
+    let point = origin.at_enu(enu);
+    Ok(GeoFrames {
+        geodetic: point,
+        ecef: point.to_ecef(),
+        enu: Some(enu),
+    })
+}
+
+/// Seconds between two fixes: target time when it advances, receive time otherwise
+fn fix_interval(from: &TrackPoint, to: &TrackPoint) -> f64 {
+    let dt = to.time - from.time;
+    if dt > 0.0 {
+        dt
+    } else {
+        to.received_ms.saturating_sub(from.received_ms) as f64 / 1000.0
+    }
+}
+
+/// Velocity and heading of a target from its last two fixes on one connection. Without
+/// `connection_id`, the connection that owns the target in the fused picture is used.
+#[tauri::command]
+pub async fn get_target_kinematics(
+    manager: State<'_, Manager>,
+    state: State<'_, GeoSettingsState>,
+    target_id: u32,
+    connection_id: Option<String>,
+) -> Result<Kinematics, String> {
+    let connection_id = match connection_id {
+        Some(id) => id,
+        None => manager
+            .picture
+            .get(target_id)
+            .map(|t| t.connection_id)
+            .ok_or_else(|| format!("Target {} is not in the fused picture", target_id))?,
+    };
+    let (from, to) = manager
+        .picture
+        .last_fixes(&connection_id, target_id)
+        .ok_or_else(|| {
+            format!(
+                "Target {} needs two fixes from {}",
+                target_id, connection_id
+            )
+        })?;
+    let units = state.read().unwrap().target_units;
+    let point = |p: &TrackPoint| GeoPoint::in_unit(p.lat, p.lon, p.alt, units);
+    derive_kinematics(&point(&from), &point(&to), fix_interval(&from, &to))
+        .ok_or_else(|| format!("Last two fixes of target {} have the same time", target_id))
+}
+
+#[tauri::command]
+pub async fn list_alert_rules(engine: State<'_, AlertEngineState>) -> Result<AlertRules, String> {
+    Ok(engine.rules())
+}
+
+/// Add or replace a geofence; returns its id
+#[tauri::command]
+pub async fn save_geofence(
+    engine: State<'_, AlertEngineState>,
+    app: AppHandle,
+    fence: Geofence,
+) -> Result<String, String> {
+    let id = engine.save_fence(fence);
+    save_alert_rules(&app, &engine.rules())?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn delete_geofence(
+    engine: State<'_, AlertEngineState>,
+    app: AppHandle,
+    id: String,
+) -> Result<(), String> {
+    if !engine.delete_fence(&id) {
+        return Err(format!("No geofence with id {}", id));
+    }
+    save_alert_rules(&app, &engine.rules())
+}
+
+/// Add or replace a min-separation rule; returns its id
+#[tauri::command]
+pub async fn save_separation_rule(
+    engine: State<'_, AlertEngineState>,
+    app: AppHandle,
+    rule: SeparationRule,
+) -> Result<String, String> {
+    let id = engine.save_separation(rule);
+    save_alert_rules(&app, &engine.rules())?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn delete_separation_rule(
+    engine: State<'_, AlertEngineState>,
+    app: AppHandle,
+    id: String,
+) -> Result<(), String> {
+    if !engine.delete_separation(&id) {
+        return Err(format!("No separation rule with id {}", id));
+    }
+    save_alert_rules(&app, &engine.rules())
+}
+
+/// Targets currently inside each fence and pairs currently violating each separation rule
+#[tauri::command]
+pub async fn get_active_alerts(
+    engine: State<'_, AlertEngineState>,
+) -> Result<ActiveAlerts, String> {
+    Ok(engine.active())
+}
+
+/// Load the persisted rules, then check the fused picture against them four times a
+/// second, emitting `alert` and sending each rule's packet on a transition
+pub fn spawn_alert_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let engine = tauri::Manager::state::<AlertEngineState>(&app)
+            .inner()
+            .clone();
+        match load_alert_rules(&app) {
+            Ok(rules) => engine.set_rules(rules),
+            Err(e) => println!("Failed to load alert rules: {e}"),
+        }
+        let mut ticker = time::interval(Duration::from_millis(250));
+        loop {
+            ticker.tick().await;
+            let manager = tauri::Manager::state::<Manager>(&app);
+            let units = tauri::Manager::state::<GeoSettingsState>(&app)
+                .read()
+                .unwrap()
+                .target_units;
+            let targets: Vec<(u32, GeoPoint)> = manager
+                .picture
+                .list()
+                .iter()
+                .map(|t| (t.packet.target_id, GeoPoint::from_target(&t.packet, units)))
+                .collect();
+            for event in engine.evaluate(&targets) {
+                if let Some(action) = &event.action {
+                    let mut buf = Vec::new();
+                    if action.packet.encode(&mut buf).is_ok() {
+                        if let Err(e) = manager.send_to(&action.connection_id, buf).await {
+                            tracing::warn!("Failed to send alert packet: {}", e);
+                        }
+                    }
+                }
+                let _ = app.emit("alert", event);
+            }
+        }
+    });
+}
@@ -0,0 +1,198 @@
+use jord::ellipsoidal::Ellipsoid;
+use jord::{GeocentricPos, GeodeticPos, LatLong, Length, LocalFrame, LocalPositionVector};
+use serde::{Deserialize, Serialize};
+
+use crate::packet::TargetPacket;
+use crate::simulation::F16State;
+
+/// Unit of the lat/lon carried in `TargetPacket`s
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AngleUnit {
+    Degrees,
+    /// What the simulator produces, as `TargetPacket`s are filled from `F16State`
+    #[default]
+    Radians,
+}
+
+/// Geodetic position on WGS84: latitude and longitude in degrees, altitude in metres
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+    #[serde(default)]
+    pub alt: f64,
+}
+
+/// Earth-centred, Earth-fixed coordinates in metres
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Ecef {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// Local east/north/up offsets in metres from an origin
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Enu {
+    pub east: f64,
+    pub north: f64,
+    pub up: f64,
+}
+
+/// Look angles from one position to another
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct RangeBearing {
+    /// Straight-line distance in metres
+    pub slant_range: f64,
+    /// Distance in the observer's horizontal plane in metres
+    pub ground_range: f64,
+    /// Degrees clockwise from true north, in [0, 360)
+    pub bearing: f64,
+    /// Degrees above the observer's horizontal plane
+    pub elevation: f64,
+}
+
+/// Motion derived from two consecutive fixes
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Kinematics {
+    /// Horizontal speed in m/s
+    pub ground_speed: f64,
+    /// Climb rate in m/s, negative when descending
+    pub vertical_rate: f64,
+    /// Course over ground in degrees clockwise from true north, in [0, 360)
+    pub heading: f64,
+    /// Velocity in the ENU frame of the earlier fix, in m/s
+    pub velocity: Enu,
+    /// Seconds between the two fixes
+    pub dt: f64,
+}
+
+impl GeoPoint {
+    pub fn new(lat: f64, lon: f64, alt: f64) -> Self {
+        Self { lat, lon, alt }
+    }
+
+    /// Position from a lat/lon pair given in `unit`
+    pub fn in_unit(lat: f64, lon: f64, alt: f64, unit: AngleUnit) -> Self {
+        match unit {
+            AngleUnit::Degrees => Self::new(lat, lon, alt),
+            AngleUnit::Radians => Self::new(lat.to_degrees(), lon.to_degrees(), alt),
+        }
+    }
+
+    pub fn from_target(tp: &TargetPacket, unit: AngleUnit) -> Self {
+        Self::in_unit(tp.lat, tp.lon, tp.alt, unit)
+    }
+
+    /// `TargetPacket` at this position with lat/lon in `unit`
+    pub fn to_target(self, target_id: u32, time: f64, unit: AngleUnit) -> TargetPacket {
+        let (lat, lon) = match unit {
+            AngleUnit::Degrees => (self.lat, self.lon),
+            AngleUnit::Radians => (self.lat.to_radians(), self.lon.to_radians()),
+        };
+        TargetPacket {
+            target_id,
+            lat,
+            lon,
+            alt: self.alt,
+            time,
+        }
+    }
+
+    pub fn from_f16_state(state: &F16State) -> Self {
+        Self::new(state.lat.to_degrees(), state.lon.to_degrees(), state.alt)
+    }
+
+    fn geodetic(&self) -> GeodeticPos {
+        GeodeticPos::new(
+            LatLong::from_degrees(self.lat, self.lon).to_nvector(),
+            Length::from_metres(self.alt),
+        )
+    }
+
+    fn from_geodetic(pos: GeodeticPos) -> Self {
+        let ll = LatLong::from_nvector(pos.horizontal_position());
+        Self::new(
+            ll.latitude().as_degrees(),
+            ll.longitude().as_degrees(),
+            pos.height().as_metres(),
+        )
+    }
+
+    pub fn to_ecef(self) -> Ecef {
+        let v = Ellipsoid::WGS84
+            .geodetic_to_geocentric_position(self.geodetic())
+            .as_metres();
+        Ecef {
+            x: v.x(),
+            y: v.y(),
+            z: v.z(),
+        }
+    }
+
+    pub fn from_ecef(ecef: Ecef) -> Self {
+        Self::from_geodetic(
+            Ellipsoid::WGS84.geocentric_to_geodetic_position(GeocentricPos::from_metres(
+                ecef.x, ecef.y, ecef.z,
+            )),
+        )
+    }
+
+    /// Position of `other` in the ENU frame centred on `self`
+    pub fn enu_of(&self, other: &GeoPoint) -> Enu {
+        let v = LocalFrame::enu(self.geodetic(), Ellipsoid::WGS84)
+            .geodetic_to_local_pos(other.geodetic());
+        Enu {
+            east: v.x().as_metres(),
+            north: v.y().as_metres(),
+            up: v.z().as_metres(),
+        }
+    }
+
+    /// Position at `enu` metres from `self`
+    pub fn at_enu(&self, enu: Enu) -> GeoPoint {
+        Self::from_geodetic(
+            LocalFrame::enu(self.geodetic(), Ellipsoid::WGS84).local_to_geodetic_pos(
+                LocalPositionVector::from_metres(enu.east, enu.north, enu.up),
+            ),
+        )
+    }
+
+    /// Range, bearing and elevation from `self` to `other`
+    pub fn range_bearing(&self, other: &GeoPoint) -> RangeBearing {
+        let enu = self.enu_of(other);
+        let ground_range = enu.east.hypot(enu.north);
+        RangeBearing {
+            slant_range: ground_range.hypot(enu.up),
+            ground_range,
+            bearing: compass_degrees(enu.east, enu.north),
+            elevation: enu.up.atan2(ground_range).to_degrees(),
+        }
+    }
+}
+
+/// Degrees clockwise from north of a horizontal vector, in [0, 360)
+fn compass_degrees(east: f64, north: f64) -> f64 {
+    east.atan2(north).to_degrees().rem_euclid(360.0)
+}
+
+/// Velocity and heading between two fixes taken `dt` seconds apart; `None` if `dt` is not positive
+pub fn derive_kinematics(from: &GeoPoint, to: &GeoPoint, dt: f64) -> Option<Kinematics> {
+    if dt.is_nan() || dt <= 0.0 {
+        return None;
+    }
+    let delta = from.enu_of(to);
+    let velocity = Enu {
+        east: delta.east / dt,
+        north: delta.north / dt,
+        up: delta.up / dt,
+    };
+    Some(Kinematics {
+        ground_speed: velocity.east.hypot(velocity.north),
+        vertical_rate: velocity.up,
+        heading: compass_degrees(velocity.east, velocity.north),
+        velocity,
+        dt,
+    })
+}
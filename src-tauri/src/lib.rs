@@ -1,16 +1,298 @@
+// mod commands;
+pub mod control;
+pub mod emulator;
+pub mod general;
+pub mod geo;
+pub mod logger;
+pub mod packet;
+pub mod simulation;
+pub mod simulation_state;
+pub mod storage;
+pub mod transport;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use once_cell::sync::Lazy;
+
+static STORE_LOADED: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
+
+use general::{
+    // commands::{
+    //     list_connections, list_serial_ports, send_packet, start_connection, stop_connection,
+    // },
+    // serial::SerialManager,
+    simulation_commands::{
+        clear_simulation_data, get_simulation_data, simulation, SimulationDataState,
+    },
+    // simulation_streaming::{
+    //     check_simulation_data_available, get_active_sensor_streams, get_active_simulation_streams,
+    //     get_available_simulation_connections, get_available_simulation_targets,
+    //     get_udp_sensor_clients, start_sensor_streaming, start_simulation_streaming,
+    //     stop_sensor_streaming, stop_sensor_target_stream, stop_simulation_streaming,
+    //     stop_target_stream,
+    // },
+};
+use tauri::{Emitter, Manager as _};
+
+use crate::{
+    storage::store::{
+        save_manager_state, load_manager_state, save_simulation_state, load_simulation_state, reset_store, restore_all_connections
+    },
+    transport::connection_manager::Manager
+};
+// use crate::general::simulation_streaming::{
+//     map_udp_sensor_target, send_sensor_command, set_target_udp_addr, unmap_udp_sensor_target,
+// };
+use crate::simulation_state::command::{
+    reset_simulation_timer, start_simulation_timer, stop_simulation_timer, SimTimerState,
+};
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logger::init_logging(); // initialize file logging
+                            // console_subscriber::init(); // starts the Tokio console layer
+
+    // Create serial manager and simulation streamer
+    // Assume you have a `Manager` initialized already:
+    let serial_manager = Manager::new(); // Or however it's created
+                                         // let simulation_streamer = Arc::new(SimulationStreamer::new(Arc::new(serial_manager.clone())));
+                                         // Add sensor streamer
+                                         // let sensor_streamer = Arc::new(UdpSensorStreamer::new(Arc::new(serial_manager.clone())));
+
+    // Initialize and start UDP server before Tauri runs
+    // let udp_socket = Arc::new(
+    //     tokio::net::UdpSocket::bind("0.0.0.0:5001")
+    //         .await
+    //         .expect("could not bind UDP server"),
+    // );
+    // let sensor_map = SharedSensorMap::default();
+    // let client_addr_map = SharedClientAddrMap::default();
+
+    // // Clone for the closure
+    // let udp_socket_for_task = udp_socket.clone();
+    // let client_addr_map_for_task = client_addr_map.clone();
+    // let sensor_map_for_task = sensor_map.clone();
+    // SENSOR_MAP.set(sensor_map.clone()).unwrap();
+
     tauri::Builder::default()
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_persisted_scope::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_store::Builder::default().build())
         .setup(|app| {
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
+            transport::commands::spawn_statistics_ticker(app.handle().clone());
+            transport::commands::spawn_track_monitor(app.handle().clone());
+            transport::commands::spawn_picture_publisher(app.handle().clone());
+            geo::commands::spawn_alert_monitor(app.handle().clone());
             Ok(())
         })
+        .manage(Arc::new(std::sync::Mutex::new(SimTimerState {
+            handle: None,
+            current_step: 0,
+            running: false,
+            total_steps: 0,
+        })))
+        .manage(serial_manager)
+        // .manage(simulation_streamer)
+        // .manage(sensor_streamer)
+        // .manage(AppState::default())
+        .manage(SimulationDataState::default())
+        .manage(general::scenario::ScenarioState::default())
+        .manage(control::commands::ControlServerState::default())
+        .manage(control::commands::MetricsExporterState::default())
+        .manage(geo::commands::GeoSettingsState::default())
+        .manage(geo::commands::AlertEngineState::default())
+        // .manage(transport::commands::SimulationDataStateManager::default())
+        // .manage(client_addr_map)
+        // .manage(udp_socket)
+        // .manage(transport::connection_manager::Manager::new())
+        .invoke_handler(tauri::generate_handler![
+            // list_ports,
+            // start_serial,
+            transport::commands::start_connection,
+            transport::commands::start_udp_connection,
+            transport::commands::stop_connection,
+            transport::commands::send_packet,
+            transport::commands::send_raw,
+            transport::commands::send_hex,
+            transport::commands::send_text,
+            transport::commands::send_file,
+            transport::commands::list_serial_ports,
+            transport::commands::list_connections,
+            transport::commands::disconnect_all_connections,
+            transport::commands::start_serial_share,
+            transport::commands::stop_share,
+            transport::commands::stop_share_by_connection_id,
+            transport::commands::set_udp_remote_addr,
+            transport::commands::add_udp_peer,
+            transport::commands::remove_udp_peer,
+            transport::commands::list_udp_peers,
+            transport::commands::set_udp_reply_to_sender,
+            transport::commands::list_udp_senders,
+            transport::commands::send_packet_to_sender,
+            transport::commands::get_udp_sensor_clients,
+            transport::commands::map_udp_sensor_target,
+            transport::commands::unmap_udp_sensor_target,
+            transport::commands::send_sensor_command,
+            transport::commands::start_sensor_streaming,
+            transport::commands::stop_sensor_streaming,
+            transport::commands::stop_sensor_target_stream,
+            transport::commands::get_active_sensor_streams,
+            transport::commands::list_sensor_streams,
+            transport::commands::join_udp_multicast,
+            transport::commands::leave_udp_multicast,
+            transport::commands::start_zmq_connection,
+            transport::commands::zmq_subscribe,
+            transport::commands::zmq_unsubscribe,
+            transport::commands::init_zmq,
+            transport::commands::add_sub,
+            transport::commands::remove_sub,
+            transport::commands::list_subs,
+            transport::commands::list_subs_with_status,
+            transport::commands::get_connection_logging,
+            transport::commands::set_connection_logging,
+            transport::commands::get_connection_decoders,
+            transport::commands::set_connection_decoders,
+            transport::commands::get_responder_rules,
+            transport::commands::set_responder_rules,
+            transport::commands::load_proto_schema,
+            transport::commands::unload_proto_schema,
+            transport::commands::list_proto_schemas,
+            transport::commands::list_schema_messages,
+            transport::commands::send_dynamic_packet,
+            transport::commands::start_simulation_udp_streaming,
+            transport::commands::stop_simulation_udp_streaming,
+            transport::commands::share_target_to_udp_server,
+            transport::commands::share_target_to_connection,
+            transport::commands::stop_share_to_connection,
+            transport::commands::list_active_shares,
+            transport::commands::start_schedule,
+            transport::commands::stop_schedule,
+            transport::commands::list_schedules,
+            transport::commands::list_active_simulation_streams,
+            transport::commands::list_udp_targets,
+            transport::commands::get_udp_target_history,
+            transport::commands::get_udp_track_config,
+            transport::commands::set_udp_track_config,
+            transport::commands::share_udp_target_to_connection,
+            transport::commands::get_total_udp_targets,
+            transport::commands::get_fused_targets,
+            transport::commands::get_target_picture_config,
+            transport::commands::set_target_picture_config,
+            transport::commands::get_packet_statistics,
+            transport::commands::get_total_packets_received,
+            transport::commands::get_total_packets_sent,
+            transport::commands::get_connection_packet_counts,
+            transport::commands::get_connection_count,
+            transport::commands::reset_packet_counters,
+            transport::commands::get_connection_statistics,
+            // general::commands::start_connection,
+            // general::commands::stop_connection,
+            // general::commands::send_packet,
+            // general::commands::list_serial_ports,
+            // general::commands::list_connections,
+            // general::commands::disconnect_all_connections,
+            // Add the new share commands
+            // general::commands::start_share,
+            // general::commands::stop_share,
+
+            // Add data persistence commands
+            storage::commands::read_log_file,
+            storage::commands::list_log_files,
+            storage::commands::get_logs_directory,
+            storage::commands::get_app_root_directory,
+            storage::commands::set_log_directory,
+            storage::commands::get_log_queue_stats,
+            storage::commands::set_log_queue_config,
+            simulation,
+            get_simulation_data,
+            clear_simulation_data,
+            start_simulation_timer,
+            stop_simulation_timer,
+            reset_simulation_timer,
+            general::scenario::load_scenario,
+            general::scenario::get_scenario,
+            general::scenario::run_scenario,
+            general::scenario::stop_scenario,
+            general::scenario::get_active_scenario,
+            // Add simulation streaming commands
+            // start_simulation_streaming,F
+            // stop_simulation_streaming,
+            // stop_target_stream,
+            // get_active_simulation_streams,
+            // get_available_simulation_connections,
+            // get_available_simulation_targets,
+            // check_simulation_data_available,
+            // set_target_udp_addr,
+            save_manager_state,
+            load_manager_state,
+            save_simulation_state,
+            load_simulation_state,
+            reset_store,
+            storage::workspace::save_workspace,
+            storage::workspace::list_workspaces,
+            storage::workspace::get_workspace,
+            storage::workspace::load_workspace,
+            storage::workspace::delete_workspace,
+            storage::workspace::export_workspace,
+            storage::workspace::import_workspace,
+            control::commands::start_control_server,
+            control::commands::stop_control_server,
+            control::commands::get_control_server_status,
+            control::commands::start_metrics_exporter,
+            control::commands::stop_metrics_exporter,
+            control::commands::get_metrics_exporter_status,
+            geo::commands::get_geo_settings,
+            geo::commands::set_geo_settings,
+            geo::commands::geo_range_bearing,
+            geo::commands::geo_from_geodetic,
+            geo::commands::geo_from_ecef,
+            geo::commands::geo_from_enu,
+            geo::commands::get_target_kinematics,
+            geo::commands::list_alert_rules,
+            geo::commands::save_geofence,
+            geo::commands::delete_geofence,
+            geo::commands::save_separation_rule,
+            geo::commands::delete_separation_rule,
+            geo::commands::get_active_alerts,
+        ])
+        .on_page_load(|window, _payload| {
+            let app = window.app_handle().clone();
+            if !STORE_LOADED.load(Ordering::SeqCst) {
+                let window_ = window.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Ok(_) = load_manager_state(app.clone()).await {
+                        if let Err(e) = restore_all_connections(app.clone()).await {
+                            println!("Failed to restore connections: {e}");
+                        }
+                    }
+                    if let Err(e) = load_simulation_state(app.state::<SimulationDataState>(), app.clone()).await {
+                        println!("Failed to load simulation state: {e}");
+                    }
+                    STORE_LOADED.store(true, Ordering::SeqCst);
+                    window_.emit("store_loaded", true).unwrap_or_else(|e| {
+                        println!("Failed to emit store_loaded event: {e}");
+                    });
+                });
+            }
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { .. } = event {
+                let app = tauri::Manager::app_handle(window).clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = save_manager_state(app.state::<Manager>(), app.clone()).await {
+                        println!("Failed to save manager state: {e}");
+                    }
+                    if let Err(e) = save_simulation_state(app.state::<SimulationDataState>(), app.clone()).await {
+                        println!("Failed to save simulation state: {e}");
+                    }
+                });
+                // Do NOT call window.close() here!
+                // Let Tauri handle the close event naturally
+            }
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -229,6 +229,9 @@ pub struct SerialPacketEvent {
     pub id: ::prost::alloc::string::String,
     #[prost(message, optional, tag = "2")]
     pub packet: ::core::option::Option<Packet>,
+    /// Sender address for UDP connections, empty otherwise
+    #[prost(string, tag = "3")]
+    pub source: ::prost::alloc::string::String,
 }
 #[derive(::serde::Serialize, ::serde::Deserialize)]
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
@@ -1 +1 @@
-pub mod command;
\ No newline at end of file
+pub mod command;
@@ -1,3 +1,4 @@
 pub mod file_logger;
 pub mod commands;
 pub mod store;
+pub mod workspace;
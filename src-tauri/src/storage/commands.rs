@@ -1,6 +1,6 @@
 use std::{env, path::Path};
 
-use crate::storage::file_logger::LOG_DIR;
+use crate::storage::file_logger::{self, LogQueueConfig, LogQueueStats, LOG_DIR};
 
 fn get_app_root() -> std::path::PathBuf {
     if let Ok(exe_path) = env::current_exe() {
@@ -89,3 +89,14 @@ pub fn set_log_directory(path: String) {
     let mut log_dir = LOG_DIR.lock().unwrap();
     *log_dir = Some(path);
 }
+
+#[tauri::command]
+pub async fn get_log_queue_stats() -> Result<LogQueueStats, String> {
+    Ok(file_logger::log_queue_stats())
+}
+
+#[tauri::command]
+pub async fn set_log_queue_config(config: LogQueueConfig) -> Result<LogQueueStats, String> {
+    file_logger::set_log_queue_config(config)?;
+    Ok(file_logger::log_queue_stats())
+}
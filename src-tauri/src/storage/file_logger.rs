@@ -1,54 +1,375 @@
 use once_cell::sync::Lazy;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::env;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::Path;
-use std::sync::Mutex;
-use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock};
+use tokio::runtime::{Handle, RuntimeFlavor};
 use tracing::{debug, error};
 
+use crate::packet::{packet::Kind, Packet};
+
 // Holds the user-selected log directory, if set
 pub static LOG_DIR: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
 
-static LOGGING_CHANNEL: Lazy<mpsc::UnboundedSender<(String, String, String)>> = Lazy::new(|| {
-    let (tx, mut rx) = mpsc::unbounded_channel::<(String, String, String)>();
-
-    // Spawn logging worker
-    tokio::spawn(async move {
-        // Get Tauri app root directory
-        let app_root = if let Ok(exe_path) = env::current_exe() {
-            if let Some(parent) = exe_path.parent() {
-                parent.to_path_buf()
-            } else {
-                Path::new(".").to_path_buf()
+const DEFAULT_LOG_QUEUE_CAPACITY: usize = 10_000;
+
+/// What to do with a new log record when the queue is full
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Discard the oldest queued record to make room
+    #[default]
+    DropOldest,
+    /// Discard the new record
+    DropNewest,
+    /// Wait for the writer to make room. This stalls the caller, including receive loops;
+    /// on a multi-thread runtime the worker's other tasks move to another thread meanwhile.
+    Block,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct LogQueueConfig {
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl Default for LogQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_LOG_QUEUE_CAPACITY,
+            policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogQueueStats {
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+    pub depth: usize,
+    pub enqueued: u64,
+    pub written: u64,
+    pub dropped_oldest: u64,
+    pub dropped_newest: u64,
+}
+
+/// How much of a connection's traffic goes to its log file
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogDirection {
+    None,
+    Rx,
+    Tx,
+    #[default]
+    Both,
+}
+
+impl LogDirection {
+    fn rx(self) -> bool {
+        matches!(self, LogDirection::Rx | LogDirection::Both)
+    }
+
+    fn tx(self) -> bool {
+        matches!(self, LogDirection::Tx | LogDirection::Both)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Json,
+    Hex,
+    Both,
+}
+
+fn default_sample_every() -> u32 {
+    1
+}
+
+/// Per-connection logging controls, persisted with the connection in `ConnectionInfo`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionLogSettings {
+    #[serde(default)]
+    pub direction: LogDirection,
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Log one of every N matching packets, per direction
+    #[serde(default = "default_sample_every")]
+    pub sample_every: u32,
+    /// `Packet` kinds to log, e.g. `TargetPacket`. Empty logs every kind.
+    #[serde(default)]
+    pub kinds: Vec<String>,
+}
+
+impl Default for ConnectionLogSettings {
+    fn default() -> Self {
+        Self {
+            direction: LogDirection::default(),
+            format: LogFormat::default(),
+            sample_every: default_sample_every(),
+            kinds: Vec::new(),
+        }
+    }
+}
+
+impl ConnectionLogSettings {
+    fn accepts_kind(&self, kind: Option<&str>) -> bool {
+        self.kinds.is_empty() || kind.is_some_and(|kind| self.kinds.iter().any(|k| k == kind))
+    }
+}
+
+/// Name of the `kind` variant of a logged message, used by `ConnectionLogSettings::kinds`
+pub trait LogKind {
+    fn log_kind(&self) -> Option<&'static str>;
+}
+
+impl LogKind for Packet {
+    fn log_kind(&self) -> Option<&'static str> {
+        Some(match self.kind.as_ref()? {
+            Kind::Header(_) => "Header",
+            Kind::Payload(_) => "Payload",
+            Kind::Checksum(_) => "Checksum",
+            Kind::Timestamp(_) => "Timestamp",
+            Kind::Source(_) => "Source",
+            Kind::Destination(_) => "Destination",
+            Kind::Protocol(_) => "Protocol",
+            Kind::Flags(_) => "Flags",
+            Kind::Version(_) => "Version",
+            Kind::TargetPacket(_) => "TargetPacket",
+            Kind::TargetPacketList(_) => "TargetPacketList",
+        })
+    }
+}
+
+struct ConnectionLogState {
+    settings: ConnectionLogSettings,
+    rx_seen: AtomicU64,
+    tx_seen: AtomicU64,
+}
+
+impl ConnectionLogState {
+    fn new(settings: ConnectionLogSettings) -> Self {
+        Self {
+            settings,
+            rx_seen: AtomicU64::new(0),
+            tx_seen: AtomicU64::new(0),
+        }
+    }
+
+    /// Apply the sample rate to one more matching packet
+    fn sample(&self, seen: &AtomicU64) -> bool {
+        let every = self.settings.sample_every.max(1) as u64;
+        seen.fetch_add(1, Ordering::Relaxed) % every == 0
+    }
+}
+
+// Logging settings per connection id; connections without an entry log everything as JSON
+static CONNECTION_LOG_SETTINGS: Lazy<RwLock<HashMap<String, Arc<ConnectionLogState>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn connection_log_state(connection_id: &str) -> Arc<ConnectionLogState> {
+    if let Some(state) = CONNECTION_LOG_SETTINGS.read().unwrap().get(connection_id) {
+        return state.clone();
+    }
+    CONNECTION_LOG_SETTINGS
+        .write()
+        .unwrap()
+        .entry(connection_id.to_string())
+        .or_insert_with(|| Arc::new(ConnectionLogState::new(ConnectionLogSettings::default())))
+        .clone()
+}
+
+pub fn connection_log_settings(connection_id: &str) -> ConnectionLogSettings {
+    CONNECTION_LOG_SETTINGS
+        .read()
+        .unwrap()
+        .get(connection_id)
+        .map(|state| state.settings.clone())
+        .unwrap_or_default()
+}
+
+/// Replace the logging settings of a connection. Takes effect for the next packet.
+pub fn set_connection_log_settings(connection_id: &str, settings: ConnectionLogSettings) {
+    CONNECTION_LOG_SETTINGS.write().unwrap().insert(
+        connection_id.to_string(),
+        Arc::new(ConnectionLogState::new(settings)),
+    );
+}
+
+pub fn remove_connection_log_settings(connection_id: &str) {
+    CONNECTION_LOG_SETTINGS
+        .write()
+        .unwrap()
+        .remove(connection_id);
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Record body, rendered by the writer thread rather than by the caller
+type RenderFn = Box<dyn FnOnce() -> Result<String, String> + Send>;
+
+struct LogRecord {
+    connection_id: String,
+    timestamp: String,
+    render: RenderFn,
+}
+
+impl LogRecord {
+    fn render(self) -> Option<String> {
+        match (self.render)() {
+            Ok(line) => Some(line),
+            Err(e) => {
+                error!(
+                    "Failed to serialize packet to JSON for {}: {}",
+                    self.connection_id, e
+                );
+                None
             }
-        } else {
-            Path::new(".").to_path_buf()
-        };
+        }
+    }
+}
+
+struct LogQueue {
+    state: Mutex<LogQueueState>,
+    /// Signalled when a record is pushed
+    not_empty: Condvar,
+    /// Signalled when the writer takes records off the queue
+    not_full: Condvar,
+    enqueued: AtomicU64,
+    written: AtomicU64,
+    dropped_oldest: AtomicU64,
+    dropped_newest: AtomicU64,
+}
+
+struct LogQueueState {
+    records: VecDeque<LogRecord>,
+    config: LogQueueConfig,
+}
+
+impl LogQueue {
+    fn new(config: LogQueueConfig) -> Self {
+        LogQueue {
+            state: Mutex::new(LogQueueState {
+                records: VecDeque::new(),
+                config,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            enqueued: AtomicU64::new(0),
+            written: AtomicU64::new(0),
+            dropped_oldest: AtomicU64::new(0),
+            dropped_newest: AtomicU64::new(0),
+        }
+    }
 
-        // Use user-selected log directory if set, otherwise default to AppData/logs
-        let log_dir = {
-            let log_dir_guard = LOG_DIR.lock().unwrap();
-            if let Some(ref user_path) = *log_dir_guard {
-                std::path::PathBuf::from(user_path)
-            } else {
-                app_root.join("logs")
+    fn push(&self, record: LogRecord) {
+        let mut state = self.state.lock().unwrap();
+        while state.records.len() >= state.config.capacity.max(1) {
+            match state.config.policy {
+                OverflowPolicy::DropOldest => {
+                    state.records.pop_front();
+                    self.dropped_oldest.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::DropNewest => {
+                    self.dropped_newest.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                OverflowPolicy::Block => {
+                    state = self.wait_not_full(state);
+                }
             }
-        };
-        if !log_dir.exists() {
-            if let Err(e) = std::fs::create_dir_all(&log_dir) {
-                error!("Failed to create logs directory at {:?}: {}", log_dir, e);
-                return;
+        }
+        state.records.push_back(record);
+        self.enqueued.fetch_add(1, Ordering::Relaxed);
+        drop(state);
+        self.not_empty.notify_one();
+    }
+
+    /// Park until the writer takes records. Receive loops run on tokio workers, so the wait
+    /// goes through `block_in_place` there; a current-thread runtime can only block.
+    fn wait_not_full<'a>(
+        &self,
+        state: MutexGuard<'a, LogQueueState>,
+    ) -> MutexGuard<'a, LogQueueState> {
+        let wait = || self.not_full.wait(state).unwrap();
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(wait)
             }
+            _ => wait(),
+        }
+    }
+
+    fn drain(&self) -> Vec<LogRecord> {
+        let mut state = self.state.lock().unwrap();
+        while state.records.is_empty() {
+            state = self.not_empty.wait(state).unwrap();
+        }
+        let batch = state.records.drain(..).collect();
+        drop(state);
+        self.not_full.notify_all();
+        batch
+    }
+}
+
+static LOG_QUEUE: Lazy<LogQueue> = Lazy::new(|| {
+    // Spawn logging worker on its own thread so file IO and JSON encoding stay off
+    // the async reader tasks
+    if let Err(e) = std::thread::Builder::new()
+        .name("packet-logger".to_string())
+        .spawn(run_log_writer)
+    {
+        error!("Failed to spawn logging thread: {}", e);
+    }
+    LogQueue::new(LogQueueConfig::default())
+});
+
+fn run_log_writer() {
+    // Get Tauri app root directory
+    let app_root = if let Ok(exe_path) = env::current_exe() {
+        if let Some(parent) = exe_path.parent() {
+            parent.to_path_buf()
+        } else {
+            Path::new(".").to_path_buf()
         }
-        debug!("Logs directory created at: {:?}", log_dir);
+    } else {
+        Path::new(".").to_path_buf()
+    };
 
-        while let Some((connection_id, json_data, timestamp)) = rx.recv().await {
-            // Validate connection_id is not empty
-            if connection_id.is_empty() {
-                error!("Empty connection_id received for logging");
+    // Use user-selected log directory if set, otherwise default to AppData/logs
+    let log_dir = {
+        let log_dir_guard = LOG_DIR.lock().unwrap();
+        if let Some(ref user_path) = *log_dir_guard {
+            std::path::PathBuf::from(user_path)
+        } else {
+            app_root.join("logs")
+        }
+    };
+    // On failure keep draining anyway: every record retries creating the directory and is
+    // discarded if it still fails, so producers waiting on a full queue are never stranded
+    match std::fs::create_dir_all(&log_dir) {
+        Ok(()) => debug!("Logs directory created at: {:?}", log_dir),
+        Err(e) => error!("Failed to create logs directory at {:?}: {}", log_dir, e),
+    }
+
+    loop {
+        for record in LOG_QUEUE.drain() {
+            let connection_id = record.connection_id.clone();
+            let timestamp = record.timestamp.clone();
+            let Some(data) = record.render() else {
                 continue;
-            }
+            };
 
             // Write to log file in Tauri app root
             let filename = log_dir.join(format!("connection_{}.log", connection_id));
@@ -61,9 +382,10 @@ static LOGGING_CHANNEL: Lazy<mpsc::UnboundedSender<(String, String, String)>> =
 
             match OpenOptions::new().create(true).append(true).open(&filename) {
                 Ok(mut file) => {
-                    if let Err(e) = writeln!(file, "[{}] {}", timestamp, json_data) {
+                    if let Err(e) = writeln!(file, "[{}] {}", timestamp, data) {
                         error!("Failed to write to log file {:?}: {}", filename, e);
                     } else {
+                        LOG_QUEUE.written.fetch_add(1, Ordering::Relaxed);
                         debug!(
                             "[{}] [{}] Logged packet to file: {:?}",
                             connection_id, timestamp, filename
@@ -75,62 +397,265 @@ static LOGGING_CHANNEL: Lazy<mpsc::UnboundedSender<(String, String, String)>> =
                 }
             }
         }
-    });
+    }
+}
 
-    tx
-});
+fn log_timestamp() -> String {
+    chrono::Utc::now()
+        .format("%Y-%m-%d %H:%M:%S%.3f")
+        .to_string()
+}
 
-pub fn save_packet_fast(connection_id: &str, packet: &impl serde::Serialize) {
+/// Queue a received packet for the log file, subject to the connection's logging settings.
+/// The packet is cloned here and serialized by the logging thread.
+pub fn save_packet_fast<T>(connection_id: &str, packet: &T)
+where
+    T: Serialize + Message + LogKind + Clone + Send + 'static,
+{
     // Validate connection_id
     if connection_id.is_empty() {
         error!("Empty connection_id provided to save_packet_fast");
         return;
     }
 
-    // Convert packet to JSON
-    let json_data = match serde_json::to_string(packet) {
-        Ok(json) => json,
-        Err(e) => {
-            error!(
-                "Failed to serialize packet to JSON for {}: {}",
-                connection_id, e
-            );
+    let state = connection_log_state(connection_id);
+    if !state.settings.direction.rx()
+        || !state.settings.accepts_kind(packet.log_kind())
+        || !state.sample(&state.rx_seen)
+    {
+        return;
+    }
+
+    let packet = packet.clone();
+    let format = state.settings.format;
+    LOG_QUEUE.push(LogRecord {
+        connection_id: connection_id.to_string(),
+        timestamp: log_timestamp(),
+        render: Box::new(move || {
+            let json = || serde_json::to_string(&packet).map_err(|e| e.to_string());
+            Ok(match format {
+                LogFormat::Json => json()?,
+                LogFormat::Hex => format!("RECV: {}", to_hex(&packet.encode_to_vec())),
+                LogFormat::Both => {
+                    format!("{} RECV: {}", json()?, to_hex(&packet.encode_to_vec()))
+                }
+            })
+        }),
+    });
+    debug!("[{}] Packet queued for logging", connection_id);
+}
+
+/// Queue a frame decoded by a connection's decoder pipeline for the log file. The
+/// decoder name stands in for the packet kind when the settings filter by kind.
+pub fn log_decoded(connection_id: &str, decoder: &str, value: &serde_json::Value, data: &[u8]) {
+    if connection_id.is_empty() {
+        error!("Empty connection_id provided to log_decoded");
+        return;
+    }
+    let state = connection_log_state(connection_id);
+    if !state.settings.direction.rx()
+        || !state.settings.accepts_kind(Some(decoder))
+        || !state.sample(&state.rx_seen)
+    {
+        return;
+    }
+
+    let record = serde_json::json!({ "decoder": decoder, "value": value });
+    let data = data.to_vec();
+    let format = state.settings.format;
+    LOG_QUEUE.push(LogRecord {
+        connection_id: connection_id.to_string(),
+        timestamp: log_timestamp(),
+        render: Box::new(move || {
+            Ok(match format {
+                LogFormat::Json => record.to_string(),
+                LogFormat::Hex => format!("RECV: {}", to_hex(&data)),
+                LogFormat::Both => format!("{} RECV: {}", record, to_hex(&data)),
+            })
+        }),
+    });
+}
+
+/// Queue sent bytes for the log file, subject to the connection's logging settings.
+/// JSON output decodes the bytes as a `Packet` and falls back to hex.
+pub fn log_sent_data(connection_id: &str, data: &[u8]) {
+    if connection_id.is_empty() {
+        error!("Empty connection_id provided to log_sent_data");
+        return;
+    }
+    let state = connection_log_state(connection_id);
+    if !state.settings.direction.tx() {
+        return;
+    }
+    if !state.settings.kinds.is_empty() {
+        let kind = Packet::decode(data).ok().and_then(|p| p.log_kind());
+        if !state.settings.accepts_kind(kind) {
             return;
         }
-    };
+    }
+    if !state.sample(&state.tx_seen) {
+        return;
+    }
 
-    // Get current timestamp
-    let timestamp = chrono::Utc::now()
-        .format("%Y-%m-%d %H:%M:%S%.3f")
-        .to_string();
-
-    // Send to logging channel (non-blocking)
-    if let Err(e) = LOGGING_CHANNEL.send((connection_id.to_string(), json_data, timestamp)) {
-        error!(
-            "Failed to send data to logging channel for {}: {}",
-            connection_id, e
-        );
-    } else {
-        debug!("[{}] Packet sent to logging channel", connection_id);
+    let data = data.to_vec();
+    let format = state.settings.format;
+    LOG_QUEUE.push(LogRecord {
+        connection_id: connection_id.to_string(),
+        timestamp: log_timestamp(),
+        render: Box::new(move || {
+            let json = || {
+                Packet::decode(data.as_slice())
+                    .ok()
+                    .and_then(|packet| serde_json::to_string(&packet).ok())
+            };
+            Ok(match (format, json()) {
+                (LogFormat::Json, Some(json)) => format!("SENT: {}", json),
+                (LogFormat::Both, Some(json)) => format!("SENT: {} HEX: {}", json, to_hex(&data)),
+                _ => format!("SENT: {}", to_hex(&data)),
+            })
+        }),
+    });
+}
+
+/// Number of log records waiting to be written
+pub fn log_queue_depth() -> usize {
+    LOG_QUEUE.state.lock().unwrap().records.len()
+}
+
+pub fn log_queue_stats() -> LogQueueStats {
+    let state = LOG_QUEUE.state.lock().unwrap();
+    LogQueueStats {
+        capacity: state.config.capacity,
+        policy: state.config.policy,
+        depth: state.records.len(),
+        enqueued: LOG_QUEUE.enqueued.load(Ordering::Relaxed),
+        written: LOG_QUEUE.written.load(Ordering::Relaxed),
+        dropped_oldest: LOG_QUEUE.dropped_oldest.load(Ordering::Relaxed),
+        dropped_newest: LOG_QUEUE.dropped_newest.load(Ordering::Relaxed),
     }
 }
 
-// Function to log sent data
-pub fn log_sent_data(connection_id: &str, data: &[u8]) {
-    // Convert raw data to hex string for logging
-    let hex_data = data
-        .iter()
-        .map(|b| format!("{:02x}", b))
-        .collect::<Vec<String>>()
-        .join(" ");
+/// Change the queue capacity and overflow policy. Records already queued beyond a smaller
+/// capacity are kept and drained normally.
+pub fn set_log_queue_config(config: LogQueueConfig) -> Result<(), String> {
+    if config.capacity == 0 {
+        return Err("Log queue capacity must be at least 1".to_string());
+    }
+    LOG_QUEUE.state.lock().unwrap().config = config;
+    // Blocked producers may now fit, or need to switch to dropping
+    LOG_QUEUE.not_full.notify_all();
+    Ok(())
+}
 
-    let timestamp = chrono::Utc::now()
-        .format("%Y-%m-%d %H:%M:%S%.3f")
-        .to_string();
-    let log_entry = format!("SENT: {}", hex_data);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
 
-    // Send to logging channel (non-blocking)
-    if let Err(e) = LOGGING_CHANNEL.send((connection_id.to_string(), log_entry, timestamp)) {
-        error!("Failed to send sent data to logging channel: {}", e);
+    fn record(line: &str) -> LogRecord {
+        let line = line.to_string();
+        LogRecord {
+            connection_id: "log_test".to_string(),
+            timestamp: String::new(),
+            render: Box::new(move || Ok(line)),
+        }
+    }
+
+    fn queue(policy: OverflowPolicy) -> LogQueue {
+        LogQueue::new(LogQueueConfig {
+            capacity: 2,
+            policy,
+        })
+    }
+
+    fn lines(records: Vec<LogRecord>) -> Vec<String> {
+        records.into_iter().filter_map(LogRecord::render).collect()
+    }
+
+    #[test]
+    fn drop_oldest_keeps_the_newest() {
+        let queue = queue(OverflowPolicy::DropOldest);
+        for line in ["a", "b", "c", "d"] {
+            queue.push(record(line));
+        }
+        assert_eq!(lines(queue.drain()), ["c", "d"]);
+        assert_eq!(queue.enqueued.load(Ordering::Relaxed), 4);
+        assert_eq!(queue.dropped_oldest.load(Ordering::Relaxed), 2);
+        assert_eq!(queue.dropped_newest.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn drop_newest_keeps_the_oldest() {
+        let queue = queue(OverflowPolicy::DropNewest);
+        for line in ["a", "b", "c", "d"] {
+            queue.push(record(line));
+        }
+        assert_eq!(lines(queue.drain()), ["a", "b"]);
+        assert_eq!(queue.enqueued.load(Ordering::Relaxed), 2);
+        assert_eq!(queue.dropped_oldest.load(Ordering::Relaxed), 0);
+        assert_eq!(queue.dropped_newest.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn block_waits_for_the_writer() {
+        let queue = Arc::new(queue(OverflowPolicy::Block));
+        queue.push(record("a"));
+        queue.push(record("b"));
+        let drainer = {
+            let queue = queue.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                lines(queue.drain())
+            })
+        };
+        queue.push(record("c"));
+        assert_eq!(drainer.join().unwrap(), ["a", "b"]);
+        assert_eq!(lines(queue.drain()), ["c"]);
+        assert_eq!(queue.enqueued.load(Ordering::Relaxed), 3);
+        assert_eq!(queue.dropped_oldest.load(Ordering::Relaxed), 0);
+        assert_eq!(queue.dropped_newest.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn block_keeps_other_tasks_running() {
+        let queue = Arc::new(queue(OverflowPolicy::Block));
+        queue.push(record("a"));
+        queue.push(record("b"));
+        let producer = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.push(record("c")) })
+        };
+        // Only gets the single worker if the blocked producer hands it over
+        let writer = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                lines(queue.drain())
+            })
+        };
+        let drained = tokio::time::timeout(Duration::from_secs(5), writer)
+            .await
+            .expect("writer task starved")
+            .unwrap();
+        assert_eq!(drained, ["a", "b"]);
+        producer.await.unwrap();
+        assert_eq!(lines(queue.drain()), ["c"]);
+    }
+
+    #[test]
+    fn switching_policy_releases_blocked_producers() {
+        let queue = Arc::new(queue(OverflowPolicy::Block));
+        queue.push(record("a"));
+        queue.push(record("b"));
+        let producer = {
+            let queue = queue.clone();
+            std::thread::spawn(move || queue.push(record("c")))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        queue.state.lock().unwrap().config.policy = OverflowPolicy::DropNewest;
+        queue.not_full.notify_all();
+        producer.join().unwrap();
+        assert_eq!(lines(queue.drain()), ["a", "b"]);
+        assert_eq!(queue.dropped_newest.load(Ordering::Relaxed), 1);
     }
 }
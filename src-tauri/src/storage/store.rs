@@ -1,6 +1,14 @@
 use crate::general::simulation_commands::SimulationDataState;
+use crate::geo::alerts::AlertRules;
 use crate::simulation::SimulationResultList;
-use crate::transport::commands::{start_connection, start_udp_connection};
+use crate::storage::file_logger::set_connection_log_settings;
+use crate::transport::commands::{
+    add_udp_peer, set_connection_decoders, set_udp_reply_to_sender, set_udp_track_config,
+    start_connection, start_udp_connection, start_zmq_connection,
+};
+use crate::transport::decoders::DecoderConfig;
+use crate::transport::picture::PictureConfig;
+use crate::transport::schemas::{SchemaSource, SCHEMAS};
 use crate::transport::ConnectionInfo;
 use crate::transport::{commands::set_udp_remote_addr, connection_manager::Manager};
 use serde::{Deserialize, Serialize};
@@ -11,16 +19,25 @@ use tauri_plugin_store::StoreBuilder;
 
 const MANAGER_STORE_FILE: &str = "manager_state.bin";
 const SIM_STORE_FILE: &str = "simulation_state.bin";
+const ALERT_STORE_FILE: &str = "alert_rules.bin";
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct SerializableManager {
     pub connections: Vec<ConnectionInfo>,
+    #[serde(default)]
+    pub picture: PictureConfig,
+    #[serde(default)]
+    pub schemas: Vec<SchemaSource>,
 }
 
 impl SerializableManager {
     pub async fn from_manager(manager: &Manager) -> Self {
         let connections = manager.list_connections().await;
-        SerializableManager { connections }
+        SerializableManager {
+            connections,
+            picture: manager.picture.config(),
+            schemas: SCHEMAS.sources(),
+        }
     }
 }
 
@@ -96,31 +113,63 @@ pub async fn load_manager_state(app: AppHandle) -> Result<SerializableManager, S
 #[tauri::command]
 pub async fn restore_all_connections(app: AppHandle) -> Result<(), String> {
     let manager_state = load_manager_state(app.clone()).await?;
-    let manager = tauri::Manager::state::<Manager>(&app);
-    for conn in manager_state.connections {
+    tauri::Manager::state::<Manager>(&app)
+        .picture
+        .set_config(manager_state.picture);
+    for schema in manager_state.schemas {
+        if let Err(e) = SCHEMAS.load(schema) {
+            println!("Failed to restore schema: {e}");
+        }
+    }
+    for (id, e) in restore_connections(&app, manager_state.connections).await {
+        println!("Failed to restore connection {id}: {e}");
+    }
+    Ok(())
+}
+
+/// Start every connection described in `connections`, returning `(id, error)` for the ones that fail
+pub async fn restore_connections(
+    app: &AppHandle,
+    connections: Vec<ConnectionInfo>,
+) -> Vec<(String, String)> {
+    let manager = tauri::Manager::state::<Manager>(app);
+    let mut failed = Vec::new();
+    for conn in connections {
+        set_connection_log_settings(&conn.id, conn.logging.clone());
+        let id = conn.id.clone();
+        let decoders = conn.decoders.clone();
+        let responders = conn.responders.clone();
         match conn.connection_type {
             Some(crate::transport::ConnectionType::Serial) => {
                 if let (Some(port), Some(baud_rate)) = (conn.port.clone(), conn.baud_rate) {
                     // Use id directly
-                    let _ = start_connection(
+                    if let Err(e) = start_connection(
                         manager.clone(),
                         conn.id.clone(),
                         port,
                         baud_rate,
                         app.clone(),
                     )
-                    .await;
+                    .await
+                    {
+                        failed.push((conn.id.clone(), e));
+                    }
                 }
             }
             Some(crate::transport::ConnectionType::Udp) => {
                 if let Some(local_addr) = conn.local_addr.clone() {
-                    let _ = start_udp_connection(
+                    if let Err(e) = start_udp_connection(
                         manager.clone(),
                         conn.id.clone(),
                         local_addr,
+                        Some(conn.udp_options.clone()),
                         app.clone(),
                     )
-                    .await;
+                    .await
+                    {
+                        failed.push((conn.id.clone(), e));
+                        continue;
+                    }
                 }
                 let _ = set_udp_remote_addr(
                     manager.clone(),
@@ -128,11 +177,40 @@ pub async fn restore_all_connections(app: AppHandle) -> Result<(), String> {
                     conn.remote_addr.unwrap_or_default().clone(),
                 )
                 .await;
+                for peer in &conn.peers {
+                    let _ = add_udp_peer(manager.clone(), conn.id.clone(), peer.clone()).await;
+                }
+                let _ =
+                    set_udp_reply_to_sender(manager.clone(), conn.id.clone(), conn.reply_to_sender)
+                        .await;
+                let _ = set_udp_track_config(manager.clone(), conn.id.clone(), conn.tracks).await;
+            }
+            Some(crate::transport::ConnectionType::Zmq) => {
+                if let Some(options) = conn.zmq.clone() {
+                    if let Err(e) =
+                        start_zmq_connection(manager.clone(), conn.id.clone(), options, app.clone())
+                            .await
+                    {
+                        failed.push((conn.id.clone(), e));
+                    }
+                }
             }
             None => {}
         }
+        if decoders != DecoderConfig::default()
+            && manager.connections.read().unwrap().contains_key(&id)
+        {
+            if let Err(e) = set_connection_decoders(manager.clone(), id.clone(), decoders).await {
+                failed.push((id.clone(), e));
+            }
+        }
+        if !responders.is_empty() && manager.connections.read().unwrap().contains_key(&id) {
+            if let Err(e) = manager.responders.set_rules(&id, responders) {
+                failed.push((id, e));
+            }
+        }
     }
-    Ok(())
+    failed
 }
 
 #[tauri::command]
@@ -209,6 +287,66 @@ pub async fn load_simulation_state(
     }
 }
 
+/// Persist geofences and separation rules
+pub fn save_alert_rules(app: &AppHandle, rules: &AlertRules) -> Result<(), String> {
+    let data_dir = tauri::Manager::path(app)
+        .app_local_data_dir()
+        .map_err(|e| {
+            let msg = format!("Could not resolve app local data dir: {e}");
+            println!("{msg}");
+            msg
+        })?;
+    let store = StoreBuilder::new(app, data_dir.join(ALERT_STORE_FILE))
+        .build()
+        .map_err(|e| {
+            let msg = format!("Store build error: {e}");
+            println!("{msg}");
+            msg
+        })?;
+    let value = serde_json::to_value(rules).map_err(|e| {
+        let msg = format!("Serialization error: {e}");
+        println!("{msg}");
+        msg
+    })?;
+    store.set("alerts", value);
+    store.save().map_err(|e| {
+        let msg = format!("Store save error: {e}");
+        println!("{msg}");
+        msg
+    })?;
+    Ok(())
+}
+
+pub fn load_alert_rules(app: &AppHandle) -> Result<AlertRules, String> {
+    let data_dir = tauri::Manager::path(app)
+        .app_local_data_dir()
+        .map_err(|e| {
+            let msg = format!("Could not resolve app local data dir: {e}");
+            println!("{msg}");
+            msg
+        })?;
+    let store = StoreBuilder::new(app, data_dir.join(ALERT_STORE_FILE))
+        .build()
+        .map_err(|e| {
+            let msg = format!("Store build error: {e}");
+            println!("{msg}");
+            msg
+        })?;
+    store.reload().map_err(|e| {
+        let msg = format!("Store reload error: {e}");
+        println!("{msg}");
+        msg
+    })?;
+    match store.get("alerts") {
+        Some(loaded) => serde_json::from_value(loaded.clone()).map_err(|e| {
+            let msg = format!("Deserialization error: {e}");
+            println!("{msg}");
+            msg
+        }),
+        None => Ok(AlertRules::default()),
+    }
+}
+
 #[tauri::command]
 pub async fn reset_store(app: AppHandle) -> Result<(), String> {
     let data_dir = tauri::Manager::path(&app)
@@ -220,6 +358,7 @@ pub async fn reset_store(app: AppHandle) -> Result<(), String> {
         })?;
     let manager_path = data_dir.join(MANAGER_STORE_FILE);
     let sim_path = data_dir.join(SIM_STORE_FILE);
+    let alert_path = data_dir.join(ALERT_STORE_FILE);
     let mut errors = vec![];
     if manager_path.exists() {
         if let Err(e) = fs::remove_file(&manager_path) {
@@ -235,6 +374,13 @@ pub async fn reset_store(app: AppHandle) -> Result<(), String> {
             errors.push(msg);
         }
     }
+    if alert_path.exists() {
+        if let Err(e) = fs::remove_file(&alert_path) {
+            let msg = format!("Failed to remove alert store: {e}");
+            println!("{msg}");
+            errors.push(msg);
+        }
+    }
     if errors.is_empty() {
         Ok(())
     } else {
@@ -0,0 +1,427 @@
+use crate::general::scenario::{Scenario, ScenarioState};
+use crate::general::simulation_commands::SimulationDataState;
+use crate::simulation::SimulationResultList;
+use crate::storage::file_logger::LOG_DIR;
+use crate::storage::store::restore_connections;
+use crate::transport::connection_manager::Manager;
+use crate::transport::schemas::{SchemaSource, SCHEMAS};
+use crate::transport::serial::SerialTransport;
+use crate::transport::{ConnectionInfo, ConnectionType, ShareInfo};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::{AppHandle, Wry};
+use tauri_plugin_store::{Store, StoreBuilder};
+
+const WORKSPACE_STORE_FILE: &str = "workspaces.bin";
+
+/// Logging settings saved together with a workspace
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct WorkspaceLogSettings {
+    pub log_dir: Option<String>,
+}
+
+/// A named bench setup: connections, shares, log settings, loaded simulation and scenario
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Workspace {
+    pub name: String,
+    #[serde(default)]
+    pub saved_at: Option<String>,
+    /// Protobuf schemas loaded at runtime, restored before the connections using them
+    #[serde(default)]
+    pub schemas: Vec<SchemaSource>,
+    #[serde(default)]
+    pub connections: Vec<ConnectionInfo>,
+    #[serde(default)]
+    pub shares: Vec<ShareInfo>,
+    #[serde(default)]
+    pub log_settings: WorkspaceLogSettings,
+    #[serde(default)]
+    pub simulation: Option<SimulationResultList>,
+    #[serde(default)]
+    pub scenario: Option<Scenario>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorkspaceSummary {
+    pub name: String,
+    pub saved_at: Option<String>,
+    pub connection_count: usize,
+    pub share_count: usize,
+    pub has_simulation: bool,
+    pub scenario: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorkspaceIssue {
+    pub connection_id: Option<String>,
+    pub message: String,
+}
+
+/// Result of loading or importing a workspace
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct WorkspaceReport {
+    pub name: String,
+    pub restored_connections: Vec<String>,
+    pub restored_shares: Vec<ShareInfo>,
+    pub issues: Vec<WorkspaceIssue>,
+}
+
+impl Workspace {
+    pub async fn capture(
+        name: String,
+        manager: &Manager,
+        sim_state: &SimulationDataState,
+        scenario_state: &ScenarioState,
+    ) -> Self {
+        let log_dir = LOG_DIR.lock().unwrap().clone();
+        Workspace {
+            name,
+            saved_at: Some(chrono::Utc::now().to_rfc3339()),
+            schemas: SCHEMAS.sources(),
+            connections: manager.list_connections().await,
+            shares: manager.list_shares().await,
+            log_settings: WorkspaceLogSettings { log_dir },
+            simulation: sim_state.lock().await.clone(),
+            scenario: scenario_state.lock().await.clone(),
+        }
+    }
+
+    pub fn summary(&self) -> WorkspaceSummary {
+        WorkspaceSummary {
+            name: self.name.clone(),
+            saved_at: self.saved_at.clone(),
+            connection_count: self.connections.len(),
+            share_count: self.shares.len(),
+            has_simulation: self.simulation.is_some(),
+            scenario: self.scenario.as_ref().map(|s| s.name.clone()),
+        }
+    }
+
+    /// Check that every port and address referenced by the workspace exists on this machine
+    pub fn validate(&self) -> Vec<WorkspaceIssue> {
+        let mut issues = Vec::new();
+        let ports = SerialTransport::list_ports().unwrap_or_default();
+        for schema in &self.schemas {
+            if !Path::new(&schema.path).is_file() {
+                issues.push(WorkspaceIssue {
+                    connection_id: None,
+                    message: format!("Schema file {} does not exist", schema.path),
+                });
+            }
+        }
+        for conn in &self.connections {
+            if let Err(message) = validate_connection(conn, &ports) {
+                issues.push(WorkspaceIssue {
+                    connection_id: Some(conn.id.clone()),
+                    message,
+                });
+            }
+        }
+        for share in &self.shares {
+            for id in [&share.from_id, &share.to_id] {
+                if !self.connections.iter().any(|c| &c.id == id) {
+                    issues.push(WorkspaceIssue {
+                        connection_id: Some(id.clone()),
+                        message: format!(
+                            "Share {} -> {} references unknown connection '{}'",
+                            share.from_id, share.to_id, id
+                        ),
+                    });
+                }
+            }
+        }
+        if let Some(scenario) = &self.scenario {
+            if let Err(message) = scenario.validate() {
+                issues.push(WorkspaceIssue {
+                    connection_id: None,
+                    message,
+                });
+            }
+        }
+        if let Some(dir) = &self.log_settings.log_dir {
+            if !Path::new(dir).is_dir() {
+                issues.push(WorkspaceIssue {
+                    connection_id: None,
+                    message: format!("Log directory {} does not exist", dir),
+                });
+            }
+        }
+        issues
+    }
+
+    /// Serialize as TOML when the path ends in `.toml`, JSON otherwise
+    pub fn to_file_string(&self, path: &Path) -> Result<String, String> {
+        if is_toml(path) {
+            toml::to_string_pretty(self).map_err(|e| format!("TOML serialization error: {e}"))
+        } else {
+            serde_json::to_string_pretty(self).map_err(|e| format!("Serialization error: {e}"))
+        }
+    }
+
+    pub fn from_file_string(path: &Path, content: &str) -> Result<Self, String> {
+        if is_toml(path) {
+            toml::from_str(content).map_err(|e| format!("TOML parse error: {e}"))
+        } else {
+            serde_json::from_str(content).map_err(|e| format!("Deserialization error: {e}"))
+        }
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("toml"))
+        .unwrap_or(false)
+}
+
+fn validate_connection(conn: &ConnectionInfo, ports: &[String]) -> Result<(), String> {
+    conn.decoders.validate()?;
+    for rule in &conn.responders {
+        rule.validate()?;
+    }
+    match conn.connection_type {
+        Some(ConnectionType::Serial) => {
+            let port = conn.port.as_ref().ok_or("Serial connection without a port")?;
+            if conn.baud_rate.is_none() {
+                return Err(format!("Serial port {} has no baud rate", port));
+            }
+            if !ports.contains(port) {
+                return Err(format!("Serial port {} does not exist on this machine", port));
+            }
+            Ok(())
+        }
+        Some(ConnectionType::Udp) => {
+            let local = conn
+                .local_addr
+                .as_ref()
+                .ok_or("UDP connection without a local address")?;
+            let local: SocketAddr = local
+                .parse()
+                .map_err(|e| format!("Invalid local address {}: {}", local, e))?;
+            if !local.ip().is_unspecified() {
+                // Binding an ephemeral port only succeeds for addresses owned by this machine
+                std::net::UdpSocket::bind(SocketAddr::new(local.ip(), 0)).map_err(|e| {
+                    format!("Local address {} is not available on this machine: {}", local.ip(), e)
+                })?;
+            }
+            for remote in conn.remote_addr.iter().chain(&conn.peers) {
+                remote
+                    .parse::<SocketAddr>()
+                    .map_err(|e| format!("Invalid remote address {}: {}", remote, e))?;
+            }
+            Ok(())
+        }
+        Some(ConnectionType::Zmq) => conn
+            .zmq
+            .as_ref()
+            .ok_or("ZeroMQ connection without socket options")?
+            .validate(),
+        None => Err("Connection has no type".to_string()),
+    }
+}
+
+fn open_workspace_store(app: &AppHandle) -> Result<Arc<Store<Wry>>, String> {
+    let data_dir = tauri::Manager::path(app)
+        .app_local_data_dir()
+        .map_err(|e| {
+            let msg = format!("Could not resolve app local data dir: {e}");
+            println!("{msg}");
+            msg
+        })?;
+    let store = StoreBuilder::new(app, data_dir.join(WORKSPACE_STORE_FILE))
+        .build()
+        .map_err(|e| {
+            let msg = format!("Store build error: {e}");
+            println!("{msg}");
+            msg
+        })?;
+    store.reload().map_err(|e| {
+        let msg = format!("Store reload error: {e}");
+        println!("{msg}");
+        msg
+    })?;
+    Ok(store)
+}
+
+fn read_workspace(store: &Store<Wry>, name: &str) -> Result<Workspace, String> {
+    let value = store
+        .get(name)
+        .ok_or_else(|| format!("Workspace '{}' not found", name))?;
+    serde_json::from_value(value).map_err(|e| format!("Deserialization error: {e}"))
+}
+
+fn write_workspace(store: &Store<Wry>, workspace: &Workspace) -> Result<(), String> {
+    if workspace.name.trim().is_empty() {
+        return Err("Workspace name must not be empty".to_string());
+    }
+    let value = serde_json::to_value(workspace).map_err(|e| format!("Serialization error: {e}"))?;
+    store.set(workspace.name.clone(), value);
+    store.save().map_err(|e| format!("Store save error: {e}"))
+}
+
+/// Save the current connections, shares, log settings and simulation under `name`
+#[tauri::command]
+pub async fn save_workspace(
+    manager: tauri::State<'_, Manager>,
+    sim_state: tauri::State<'_, SimulationDataState>,
+    scenario_state: tauri::State<'_, ScenarioState>,
+    app: AppHandle,
+    name: String,
+) -> Result<WorkspaceSummary, String> {
+    let store = open_workspace_store(&app)?;
+    let workspace = Workspace::capture(name, &manager, &sim_state, &scenario_state).await;
+    write_workspace(&store, &workspace)?;
+    Ok(workspace.summary())
+}
+
+#[tauri::command]
+pub async fn list_workspaces(app: AppHandle) -> Result<Vec<WorkspaceSummary>, String> {
+    let store = open_workspace_store(&app)?;
+    let mut summaries: Vec<WorkspaceSummary> = store
+        .keys()
+        .iter()
+        .filter_map(|name| read_workspace(&store, name).ok())
+        .map(|workspace| workspace.summary())
+        .collect();
+    summaries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(summaries)
+}
+
+#[tauri::command]
+pub async fn get_workspace(app: AppHandle, name: String) -> Result<Workspace, String> {
+    let store = open_workspace_store(&app)?;
+    read_workspace(&store, &name)
+}
+
+/// Replace the running setup with the workspace stored under `name`
+#[tauri::command]
+pub async fn load_workspace(
+    manager: tauri::State<'_, Manager>,
+    sim_state: tauri::State<'_, SimulationDataState>,
+    scenario_state: tauri::State<'_, ScenarioState>,
+    app: AppHandle,
+    name: String,
+) -> Result<WorkspaceReport, String> {
+    let store = open_workspace_store(&app)?;
+    let workspace = read_workspace(&store, &name)?;
+    let mut issues = workspace.validate();
+    let invalid: Vec<String> = issues
+        .iter()
+        .filter_map(|issue| issue.connection_id.clone())
+        .collect();
+
+    manager.stop_scenario().await?;
+    manager.stop_all().await;
+
+    if let Some(dir) = &workspace.log_settings.log_dir {
+        if Path::new(dir).is_dir() {
+            *LOG_DIR.lock().unwrap() = Some(dir.clone());
+        }
+    }
+
+    for schema in &workspace.schemas {
+        if let Err(message) = SCHEMAS.load(schema.clone()) {
+            issues.push(WorkspaceIssue {
+                connection_id: None,
+                message,
+            });
+        }
+    }
+
+    let connections: Vec<ConnectionInfo> = workspace
+        .connections
+        .iter()
+        .filter(|conn| !invalid.contains(&conn.id))
+        .cloned()
+        .collect();
+    let failed = restore_connections(&app, connections.clone()).await;
+    for (id, message) in &failed {
+        issues.push(WorkspaceIssue {
+            connection_id: Some(id.clone()),
+            message: message.clone(),
+        });
+    }
+    let restored_connections: Vec<String> = connections
+        .into_iter()
+        .map(|conn| conn.id)
+        .filter(|id| !failed.iter().any(|(failed_id, _)| failed_id == id))
+        .collect();
+
+    let mut restored_shares = Vec::new();
+    for share in &workspace.shares {
+        if !restored_connections.contains(&share.from_id)
+            || !restored_connections.contains(&share.to_id)
+        {
+            continue;
+        }
+        match manager
+            .share_data_between_ids(&share.from_id, &share.to_id, share.interval_ms)
+            .await
+        {
+            Ok(_) => restored_shares.push(share.clone()),
+            Err(message) => issues.push(WorkspaceIssue {
+                connection_id: Some(share.from_id.clone()),
+                message,
+            }),
+        }
+    }
+
+    if workspace.simulation.is_some() {
+        *sim_state.lock().await = workspace.simulation.clone();
+    }
+    if workspace.scenario.is_some() {
+        *scenario_state.lock().await = workspace.scenario.clone();
+    }
+
+    Ok(WorkspaceReport {
+        name: workspace.name,
+        restored_connections,
+        restored_shares,
+        issues,
+    })
+}
+
+#[tauri::command]
+pub async fn delete_workspace(app: AppHandle, name: String) -> Result<(), String> {
+    let store = open_workspace_store(&app)?;
+    if !store.delete(&name) {
+        return Err(format!("Workspace '{}' not found", name));
+    }
+    store.save().map_err(|e| format!("Store save error: {e}"))
+}
+
+/// Write a stored workspace to a portable `.json` or `.toml` file
+#[tauri::command]
+pub async fn export_workspace(app: AppHandle, name: String, path: String) -> Result<(), String> {
+    let store = open_workspace_store(&app)?;
+    let workspace = read_workspace(&store, &name)?;
+    let path = Path::new(&path);
+    let content = workspace.to_file_string(path)?;
+    std::fs::write(path, content)
+        .map_err(|e| format!("Failed to write workspace file {:?}: {}", path, e))
+}
+
+/// Read a `.json` or `.toml` workspace file, validate it against this machine and store it
+#[tauri::command]
+pub async fn import_workspace(
+    app: AppHandle,
+    path: String,
+    name: Option<String>,
+) -> Result<WorkspaceReport, String> {
+    let path = Path::new(&path);
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read workspace file {:?}: {}", path, e))?;
+    let mut workspace = Workspace::from_file_string(path, &content)?;
+    if let Some(name) = name {
+        workspace.name = name;
+    }
+    let issues = workspace.validate();
+    let store = open_workspace_store(&app)?;
+    write_workspace(&store, &workspace)?;
+    Ok(WorkspaceReport {
+        name: workspace.name,
+        issues,
+        ..Default::default()
+    })
+}
@@ -2,25 +2,42 @@
 
 pub mod commands;
 pub mod connection_manager;
+pub mod decoders;
+pub mod framing;
+pub mod instrumented;
+pub mod peers;
+pub mod picture;
+pub mod responder;
+pub mod scheduler;
+pub mod schemas;
+pub mod sensors;
 pub mod serial;
+pub mod stats;
+pub mod tracks;
 pub mod udp;
+pub mod zmq;
 use std::any::Any;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use prost::Message;
 use serde::{Deserialize, Serialize};
 
+use crate::storage::file_logger::ConnectionLogSettings;
+
 #[derive(Serialize,Deserialize, Clone, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum ConnectionType {
     Serial,
     Udp,
+    Zmq,
 }
 
 #[derive(Serialize,Deserialize, Clone, Debug, Default)]
 pub struct ConnectionInfo {
     pub id: String,
+    #[serde(default)]
     pub name: String,
     #[serde(default)]
     pub connection_type: Option<ConnectionType>,
@@ -30,6 +47,70 @@ pub struct ConnectionInfo {
     // UDP fields
     pub local_addr: Option<String>,
     pub remote_addr: Option<String>,
+    /// Every UDP destination, `remote_addr` being the first
+    #[serde(default)]
+    pub peers: Vec<String>,
+    /// Also send to whoever sent the last datagram
+    #[serde(default)]
+    pub reply_to_sender: bool,
+    #[serde(default)]
+    pub udp_options: udp::UdpOptions,
+    /// Track history and timeouts for targets received on a UDP connection
+    #[serde(default)]
+    pub tracks: tracks::TrackConfig,
+    // ZMQ fields
+    #[serde(default)]
+    pub zmq: Option<zmq::ZmqOptions>,
+    /// Framing and payload decoders for received data
+    #[serde(default)]
+    pub decoders: decoders::DecoderConfig,
+    /// Auto-responder rules replying to packets received on this connection
+    #[serde(default)]
+    pub responders: Vec<responder::ResponderRule>,
+    #[serde(default)]
+    pub logging: ConnectionLogSettings,
+}
+
+/// Emitted as `packet_sent` after a connection sends data
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PacketSentEvent {
+    pub id: String,
+    pub bytes: usize,
+    /// The sent bytes decoded as a `Packet`, if they are one
+    pub packet: Option<crate::packet::Packet>,
+}
+
+/// A running connection-to-connection share that can be restored later
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ShareInfo {
+    pub from_id: String,
+    pub to_id: String,
+    pub interval_ms: u64,
+}
+
+/// Where a set of simulated targets should be streamed and how fast.
+/// Either `connection_id` or both `local_addr` and `remote_addr` must be set.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StreamPlan {
+    /// Targets to stream, all targets when empty
+    #[serde(default)]
+    pub target_ids: Vec<u32>,
+    /// Existing connection to send through
+    pub connection_id: Option<String>,
+    /// Local address of a dedicated UDP sender
+    pub local_addr: Option<String>,
+    pub remote_addr: Option<String>,
+    pub interval_ms: u64,
+}
+
+/// Streams started for the currently running scenario
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ScenarioRun {
+    pub name: String,
+    /// `(share_id, connection_id)` keys in `Manager::share_tasks`
+    pub shares: Vec<(String, String)>,
+    /// Connection ids in `Manager::simulation_stream_tasks`
+    pub simulation_streams: Vec<String>,
 }
 
 #[async_trait]
@@ -53,6 +134,21 @@ pub trait Transport: Send + Sync {
     /// Reset packet counters for this transport
     fn reset_packet_counters(&self) {}
 
+    /// Send to one specific address instead of the configured destination
+    async fn send_to_addr(&self, addr: SocketAddr, _data: Vec<u8>) -> Result<(), String> {
+        Err(format!("{} cannot send to {}", self.name(), addr))
+    }
+
+    /// Rolling rate, error and latency statistics, if this transport keeps them
+    fn stats(&self) -> Option<Arc<stats::ConnectionStats>> {
+        None
+    }
+
+    /// Decoder pipeline for received data, if this transport supports one
+    fn decoders(&self) -> Option<Arc<decoders::DecoderPipeline>> {
+        None
+    }
+
     /// Share data from a channel to this transport in an independent Tokio task
     fn share_data_channel(
         self: Arc<Self>,
@@ -85,9 +181,9 @@ pub trait Transport: Send + Sync {
     }
 }
 pub trait StatableTransport: Transport {
-    async fn start<F: Message + Default + serde::Serialize>(
+    async fn start<F: Message + Default + serde::Serialize + 'static>(
         &mut self,
         id: String,
-        on_packet: impl FnMut(String, F) + Send + 'static,
+        on_packet: impl FnMut(String, F, Option<SocketAddr>) + Send + 'static,
     ) -> Result<(), String>;
 }
@@ -1,16 +1,35 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crate::general::simulation_commands::SimulationDataState;
+use crate::geo::commands::GeoSettingsState;
+use crate::geo::geodesy::GeoPoint;
 use crate::packet::TargetPacket;
 use crate::packet::{packet::Kind, Packet, SerialPacketEvent};
-use crate::storage::file_logger::save_packet_fast;
+use crate::storage::file_logger::{
+    connection_log_settings, set_connection_log_settings, ConnectionLogSettings,
+};
 use crate::transport::connection_manager::Manager;
+use crate::transport::decoders::{DecodedHook, DecodedPayload, DecoderConfig};
+use crate::transport::framing::parse_hex;
 
+use crate::transport::instrumented::{Instrumented, SentHook};
+use crate::transport::peers::UdpPeerInfo;
+use crate::transport::picture::{FusedTarget, PictureConfig};
+use crate::transport::responder::ResponderRule;
+use crate::transport::scheduler::{ScheduleConfig, ScheduleInfo};
+use crate::transport::schemas::{SchemaSource, SCHEMAS};
+use crate::transport::sensors::{
+    SensorClient, SensorReadingEvent, SensorRegistry, SensorStream, SensorStreamInfo,
+    SensorStreamRequest,
+};
 use crate::transport::serial::SerialTransport;
-use crate::transport::udp::UdpTransport;
-use crate::transport::{ConnectionInfo, StatableTransport};
+use crate::transport::stats::ConnectionStatsSnapshot;
+use crate::transport::tracks::{TrackConfig, TrackInfo};
+use crate::transport::udp::{UdpOptions, UdpTransport};
+use crate::transport::zmq::{ZmqOptions, ZmqPattern, ZmqTransport, DEFAULT_SUB_ENDPOINT};
+use crate::transport::{ConnectionInfo, PacketSentEvent};
 
 use prost::Message;
 use std::collections::HashMap;
@@ -19,6 +38,25 @@ use tokio::time;
 use uuid::Uuid;
 use std::net::SocketAddr;
 
+/// Emit `packet_sent` for every successful send on a connection
+fn packet_sent_hook(app: AppHandle) -> SentHook {
+    Arc::new(move |id: &str, data: &[u8]| {
+        let event = PacketSentEvent {
+            id: id.to_string(),
+            bytes: data.len(),
+            packet: Packet::decode(data).ok(),
+        };
+        let _ = app.emit("packet_sent", event);
+    })
+}
+
+/// Emit `decoded_payload` for every frame a connection's decoders produce
+fn decoded_hook(app: AppHandle) -> DecodedHook {
+    Arc::new(move |payload: &DecodedPayload| {
+        let _ = app.emit("decoded_payload", payload.clone());
+    })
+}
+
 #[tauri::command]
 pub async fn start_connection(
     state: State<'_, Manager>,
@@ -27,20 +65,25 @@ pub async fn start_connection(
     baud: u32,
     app: AppHandle,
 ) -> Result<(), String> {
-    let mut transport = SerialTransport::new(port, baud);
-
+    let serial = SerialTransport::new(port, baud);
+    serial.decoders.set_on_decoded(decoded_hook(app.clone()));
+    let mut transport =
+        Instrumented::new(id.clone(), serial).on_sent(packet_sent_hook(app.clone()));
+    let picture = state.picture.clone();
+    let responders = state.responders.clone();
     transport
-        .start::<Packet>(id.clone(), move |conn_id: String, packet: Packet| {
-            // Emit only the general event with id and packet
+        .start::<Packet>(move |conn_id: String, packet: Packet, source: Option<SocketAddr>| {
+            picture.offer_packet(&conn_id, source, &packet);
+            responders.offer_packet(&conn_id, source, &packet);
+            // Emit only the general event with id, packet and sender
             let event = SerialPacketEvent {
-                id: conn_id.clone(),
-                packet: Some(packet.clone()),
+                id: conn_id,
+                packet: Some(packet),
+                source: source.map(|addr| addr.to_string()).unwrap_or_default(),
             };
             let _ = app.emit("serial_packet", event);
-            save_packet_fast(&conn_id, &packet);
         })
-        .await
-        .unwrap();
+        .await?;
 
     state
         .add_connection(
@@ -95,6 +138,72 @@ pub async fn send_packet(
     state.send_to(&id, buf).await
 }
 
+/// Send bytes as they are, or as one frame of the connection's framing when `framed`
+async fn send_bytes(
+    state: &Manager,
+    id: &str,
+    data: Vec<u8>,
+    framed: Option<bool>,
+) -> Result<usize, String> {
+    let data = if framed.unwrap_or(false) {
+        state.decoders_of(id)?.config().framing.encode(&data)?
+    } else {
+        data
+    };
+    let len = data.len();
+    state.send_to(id, data).await?;
+    Ok(len)
+}
+
+/// Send raw bytes to a connection. Returns the number of bytes sent.
+#[tauri::command]
+pub async fn send_raw(
+    state: State<'_, Manager>,
+    id: String,
+    data: Vec<u8>,
+    framed: Option<bool>,
+) -> Result<usize, String> {
+    send_bytes(&state, &id, data, framed).await
+}
+
+#[tauri::command]
+pub async fn send_hex(
+    state: State<'_, Manager>,
+    id: String,
+    hex: String,
+    framed: Option<bool>,
+) -> Result<usize, String> {
+    send_bytes(&state, &id, parse_hex(&hex)?, framed).await
+}
+
+/// Send `text` followed by `terminator`, e.g. `"\r\n"`
+#[tauri::command]
+pub async fn send_text(
+    state: State<'_, Manager>,
+    id: String,
+    text: String,
+    terminator: Option<String>,
+    framed: Option<bool>,
+) -> Result<usize, String> {
+    let mut data = text.into_bytes();
+    data.extend_from_slice(terminator.unwrap_or_default().as_bytes());
+    send_bytes(&state, &id, data, framed).await
+}
+
+/// Send the contents of a file as one write
+#[tauri::command]
+pub async fn send_file(
+    state: State<'_, Manager>,
+    id: String,
+    path: String,
+    framed: Option<bool>,
+) -> Result<usize, String> {
+    let data = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    send_bytes(&state, &id, data, framed).await
+}
+
 #[tauri::command]
 pub async fn disconnect_all_connections(state: State<'_, Manager>) -> Result<(), String> {
     state.stop_all().await;
@@ -114,6 +223,7 @@ pub async fn start_udp_connection(
     state: State<'_, Manager>,
     id: String,
     local_addr: String,
+    options: Option<UdpOptions>,
     app: AppHandle,
 ) -> Result<(), String> {
     let addr: std::net::SocketAddr = local_addr
@@ -125,21 +235,37 @@ pub async fn start_udp_connection(
         return Err(format!("Socket address {} is already in use. Please stop any existing connections or simulation streaming using this address first.", local_addr));
     }
 
-    let mut transport = UdpTransport::new(addr)
+    let udp = UdpTransport::with_options(addr, options.unwrap_or_default())
         .await
         .map_err(|e| format!("Failed to create UDP transport: {}", e))?;
+    let sensor_app = app.clone();
+    let sensor_conn_id = id.clone();
+    udp.sensors.set_on_reading(Arc::new(move |source, reading| {
+        let event = SensorReadingEvent {
+            connection_id: sensor_conn_id.clone(),
+            source: source.to_string(),
+            reading: *reading,
+            received_ms: crate::transport::tracks::now_ms(),
+        };
+        let _ = sensor_app.emit("target_sensor_data", event);
+    }));
+    udp.decoders.set_on_decoded(decoded_hook(app.clone()));
+    let mut transport = Instrumented::new(id.clone(), udp).on_sent(packet_sent_hook(app.clone()));
+    let picture = state.picture.clone();
+    let responders = state.responders.clone();
     transport
-        .start::<Packet>(id.clone(), move |conn_id: String, packet: Packet| {
-            // Emit only the general event with id and packet
+        .start::<Packet>(move |conn_id: String, packet: Packet, source: Option<SocketAddr>| {
+            picture.offer_packet(&conn_id, source, &packet);
+            responders.offer_packet(&conn_id, source, &packet);
+            // Emit only the general event with id, packet and sender
             let event = SerialPacketEvent {
-                id: conn_id.clone(),
-                packet: Some(packet.clone()),
+                id: conn_id,
+                packet: Some(packet),
+                source: source.map(|addr| addr.to_string()).unwrap_or_default(),
             };
             let _ = app.emit("serial_packet", event);
-            save_packet_fast(&conn_id, &packet);
         })
-        .await
-        .unwrap();
+        .await?;
 
     state
         .add_connection(
@@ -163,6 +289,495 @@ pub async fn set_udp_remote_addr(
     state.set_udp_remote_addr(&id, addr).await
 }
 
+/// Add a destination to a UDP connection; sends fan out to every peer
+#[tauri::command]
+pub async fn add_udp_peer(
+    state: State<'_, Manager>,
+    id: String,
+    addr: String,
+) -> Result<(), String> {
+    let addr: SocketAddr = addr
+        .parse()
+        .map_err(|e| format!("Invalid peer address: {}", e))?;
+    state.with_udp(&id, |udp| {
+        udp.peers.add(addr);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn remove_udp_peer(
+    state: State<'_, Manager>,
+    id: String,
+    addr: String,
+) -> Result<(), String> {
+    let addr: SocketAddr = addr
+        .parse()
+        .map_err(|e| format!("Invalid peer address: {}", e))?;
+    state.with_udp(&id, |udp| {
+        if udp.peers.remove(addr) {
+            Ok(())
+        } else {
+            Err(format!("{} is not a peer of {}", addr, id))
+        }
+    })
+}
+
+#[tauri::command]
+pub async fn list_udp_peers(
+    state: State<'_, Manager>,
+    id: String,
+) -> Result<Vec<UdpPeerInfo>, String> {
+    state.with_udp(&id, |udp| Ok(udp.peers.list()))
+}
+
+/// Every address that has sent datagrams to a UDP connection, most recent first
+#[tauri::command]
+pub async fn list_udp_senders(
+    state: State<'_, Manager>,
+    id: String,
+) -> Result<Vec<UdpPeerInfo>, String> {
+    state.with_udp(&id, |udp| Ok(udp.peers.list_senders()))
+}
+
+/// Send a packet to one address that has already sent data to this UDP connection
+#[tauri::command]
+pub async fn send_packet_to_sender(
+    state: State<'_, Manager>,
+    id: String,
+    addr: String,
+    packet: Packet,
+) -> Result<(), String> {
+    let addr: SocketAddr = addr
+        .parse()
+        .map_err(|e| format!("Invalid address: {}", e))?;
+    let known = state.with_udp(&id, |udp| Ok(udp.peers.is_known_sender(addr)))?;
+    if !known {
+        return Err(format!("{} has not sent anything to {}", addr, id));
+    }
+    let mut buf = Vec::new();
+    packet.encode(&mut buf).map_err(|e| e.to_string())?;
+    state.send_to_addr(&id, addr, buf).await
+}
+
+/// Also send to whoever sent the connection its last datagram
+#[tauri::command]
+pub async fn set_udp_reply_to_sender(
+    state: State<'_, Manager>,
+    id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    state.with_udp(&id, |udp| {
+        udp.peers.set_reply_to_sender(enabled);
+        Ok(())
+    })
+}
+
+/// Join an IPv4/IPv6 multicast group on a running UDP connection
+#[tauri::command]
+pub async fn join_udp_multicast(
+    state: State<'_, Manager>,
+    id: String,
+    group: String,
+) -> Result<(), String> {
+    state.with_udp(&id, |udp| udp.join_multicast(&group))
+}
+
+#[tauri::command]
+pub async fn leave_udp_multicast(
+    state: State<'_, Manager>,
+    id: String,
+    group: String,
+) -> Result<(), String> {
+    state.with_udp(&id, |udp| udp.leave_multicast(&group))
+}
+
+/// UDP connection, registry and address of a sensor client; without `connection_id` every
+/// UDP connection is searched
+fn find_udp_sensor(
+    state: &Manager,
+    sensor_id: u32,
+    connection_id: Option<String>,
+) -> Result<(String, Arc<SensorRegistry>, SocketAddr), String> {
+    let connections: Vec<_> = state
+        .connections
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|(id, _)| !matches!(&connection_id, Some(wanted) if wanted != *id))
+        .map(|(id, conn)| (id.clone(), conn.clone()))
+        .collect();
+    for (id, conn) in connections {
+        if let Some(udp) = conn.as_any().downcast_ref::<UdpTransport>() {
+            if let Some(addr) = udp.sensors.addr_of(sensor_id) {
+                return Ok((id, udp.sensors.clone(), addr));
+            }
+        }
+    }
+    Err(format!(
+        "Sensor {} has not reported on any UDP connection",
+        sensor_id
+    ))
+}
+
+/// Sensor clients discovered on one UDP connection, or on all of them
+#[tauri::command]
+pub async fn get_udp_sensor_clients(
+    state: State<'_, Manager>,
+    connection_id: Option<String>,
+) -> Result<Vec<SensorClient>, String> {
+    if let Some(id) = connection_id {
+        return state.with_udp(&id, |udp| Ok(udp.sensors.list(&id)));
+    }
+    let mut clients: Vec<SensorClient> = state
+        .connections
+        .read()
+        .unwrap()
+        .iter()
+        .filter_map(|(id, conn)| {
+            let udp = conn.as_any().downcast_ref::<UdpTransport>()?;
+            Some(udp.sensors.list(id))
+        })
+        .flatten()
+        .collect();
+    clients.sort_by(|a, b| (a.sensor_id, &a.connection_id).cmp(&(b.sensor_id, &b.connection_id)));
+    Ok(clients)
+}
+
+/// Ask a sensor client to report as `target_id`
+#[tauri::command]
+pub async fn map_udp_sensor_target(
+    state: State<'_, Manager>,
+    sensor_id: u32,
+    target_id: u32,
+    connection_id: Option<String>,
+) -> Result<(), String> {
+    if target_id == 0 {
+        return Err("Target id 0 means unmapped, use unmap_udp_sensor_target".to_string());
+    }
+    let (id, sensors, addr) = find_udp_sensor(&state, sensor_id, connection_id)?;
+    state
+        .send_to_addr(&id, addr, format!("map:{}", target_id).into_bytes())
+        .await?;
+    sensors.set_requested_target(sensor_id, target_id);
+    Ok(())
+}
+
+/// Ask a sensor client to stop reporting
+#[tauri::command]
+pub async fn unmap_udp_sensor_target(
+    state: State<'_, Manager>,
+    sensor_id: u32,
+    connection_id: Option<String>,
+) -> Result<(), String> {
+    let (id, sensors, addr) = find_udp_sensor(&state, sensor_id, connection_id)?;
+    state.send_to_addr(&id, addr, b"unmap".to_vec()).await?;
+    sensors.set_requested_target(sensor_id, 0);
+    Ok(())
+}
+
+/// Send a text command to a sensor client and wait for its text reply
+#[tauri::command]
+pub async fn send_sensor_command(
+    state: State<'_, Manager>,
+    sensor_id: u32,
+    command: String,
+    timeout_ms: Option<u64>,
+    connection_id: Option<String>,
+) -> Result<String, String> {
+    let (id, sensors, addr) = find_udp_sensor(&state, sensor_id, connection_id)?;
+    let timeout_ms = timeout_ms.unwrap_or(1000);
+    let reply = sensors.expect_reply(addr);
+    if let Err(e) = state.send_to_addr(&id, addr, command.into_bytes()).await {
+        sensors.cancel_reply(addr);
+        return Err(e);
+    }
+    match time::timeout(Duration::from_millis(timeout_ms), reply).await {
+        Ok(Ok(text)) => Ok(text),
+        _ => {
+            sensors.cancel_reply(addr);
+            Err(format!(
+                "Sensor {} did not reply within {} ms",
+                sensor_id, timeout_ms
+            ))
+        }
+    }
+}
+
+/// Forward readings of UDP sensors to other connections as `TargetPacket`s, one stream per
+/// sensor and destination. Lat/lon are converted to the configured target units. Returns
+/// the `{sensor_id}_{connection_id}` keys of the started streams.
+#[tauri::command]
+pub async fn start_sensor_streaming(
+    state: State<'_, Manager>,
+    geo: State<'_, GeoSettingsState>,
+    request: SensorStreamRequest,
+) -> Result<Vec<String>, String> {
+    if request.stream_configs.is_empty() {
+        return Err("No sensor streams requested".to_string());
+    }
+    let units = geo.read().unwrap().target_units;
+    // Resolve every config before starting anything, so a bad one starts no stream
+    let mut resolved = Vec::with_capacity(request.stream_configs.len());
+    for mut config in request.stream_configs {
+        let udp_id = match config.udp_connection_id.clone() {
+            Some(id) => id,
+            None => find_udp_sensor(&state, config.sensor_id, None)?.0,
+        };
+        let feed = state.with_udp(&udp_id, |udp| Ok(udp.sensors.subscribe()))?;
+        if !state
+            .connections
+            .read()
+            .unwrap()
+            .contains_key(&config.connection_id)
+        {
+            return Err(format!("Connection {} not found", config.connection_id));
+        }
+        config.udp_connection_id = Some(udp_id);
+        resolved.push((config, feed));
+    }
+
+    let mut started = Vec::new();
+    for (config, mut feed) in resolved {
+        let manager = state.inner().clone();
+        let forwarded = Arc::new(AtomicU64::new(0));
+        let last_forward_ms = Arc::new(AtomicU64::new(0));
+        let task_config = config.clone();
+        let task_forwarded = forwarded.clone();
+        let task_last_forward_ms = last_forward_ms.clone();
+        let handle = tokio::spawn(async move {
+            let interval = Duration::from_millis(task_config.interval_ms);
+            let mut last_sent: Option<Instant> = None;
+            loop {
+                let reading = match feed.recv().await {
+                    Ok(reading) => reading,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                if reading.sensor_id != task_config.sensor_id
+                    || last_sent.is_some_and(|t| t.elapsed() < interval)
+                {
+                    continue;
+                }
+                let target_id = task_config.target_id.unwrap_or(if reading.target_id != 0 {
+                    reading.target_id
+                } else {
+                    reading.sensor_id
+                });
+                let now = crate::transport::tracks::now_ms();
+                let tp = GeoPoint::new(reading.lat, reading.lon, reading.alt).to_target(
+                    target_id,
+                    now as f64 / 1000.0,
+                    units,
+                );
+                let packet = Packet {
+                    kind: Some(Kind::TargetPacket(tp)),
+                };
+                match manager
+                    .send_to(&task_config.connection_id, packet.encode_to_vec())
+                    .await
+                {
+                    Ok(()) => {
+                        task_forwarded.fetch_add(1, Ordering::Relaxed);
+                        task_last_forward_ms.store(now, Ordering::Relaxed);
+                    }
+                    Err(e) => tracing::warn!(
+                        "Sensor {} -> {}: {}",
+                        task_config.sensor_id,
+                        task_config.connection_id,
+                        e
+                    ),
+                }
+                last_sent = Some(Instant::now());
+            }
+        });
+
+        let key = (config.sensor_id, config.connection_id.clone());
+        started.push(format!("{}_{}", key.0, key.1));
+        let stream = SensorStream {
+            config,
+            forwarded,
+            last_forward_ms,
+            handle,
+        };
+        // Restarting a stream replaces it
+        if let Some(old) = state.sensor_streams.lock().await.insert(key, stream) {
+            old.handle.abort();
+        }
+    }
+    Ok(started)
+}
+
+#[tauri::command]
+pub async fn stop_sensor_streaming(state: State<'_, Manager>) -> Result<(), String> {
+    for (_key, stream) in state.sensor_streams.lock().await.drain() {
+        stream.handle.abort();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_sensor_target_stream(
+    state: State<'_, Manager>,
+    sensor_id: u32,
+    connection_id: String,
+) -> Result<(), String> {
+    let stream = state
+        .sensor_streams
+        .lock()
+        .await
+        .remove(&(sensor_id, connection_id.clone()))
+        .ok_or_else(|| format!("No stream of sensor {} to {}", sensor_id, connection_id))?;
+    stream.handle.abort();
+    Ok(())
+}
+
+/// `{sensor_id}_{connection_id}` of every running sensor stream
+#[tauri::command]
+pub async fn get_active_sensor_streams(state: State<'_, Manager>) -> Result<Vec<String>, String> {
+    let mut keys: Vec<String> = state
+        .sensor_streams
+        .lock()
+        .await
+        .keys()
+        .map(|(sensor_id, connection_id)| format!("{}_{}", sensor_id, connection_id))
+        .collect();
+    keys.sort();
+    Ok(keys)
+}
+
+/// Configuration and forwarded counts of every running sensor stream
+#[tauri::command]
+pub async fn list_sensor_streams(
+    state: State<'_, Manager>,
+) -> Result<Vec<SensorStreamInfo>, String> {
+    let mut streams: Vec<SensorStreamInfo> = state
+        .sensor_streams
+        .lock()
+        .await
+        .values()
+        .map(SensorStream::info)
+        .collect();
+    streams.sort_by(|a, b| {
+        (a.config.sensor_id, &a.config.connection_id)
+            .cmp(&(b.config.sensor_id, &b.config.connection_id))
+    });
+    Ok(streams)
+}
+
+#[tauri::command]
+pub async fn get_connection_logging(
+    state: State<'_, Manager>,
+    id: String,
+) -> Result<ConnectionLogSettings, String> {
+    if !state.connections.read().unwrap().contains_key(&id) {
+        return Err(format!("Connection ID '{}' not found", id));
+    }
+    Ok(connection_log_settings(&id))
+}
+
+/// Change what a running connection logs; saved with the connection in the manager state
+#[tauri::command]
+pub async fn set_connection_logging(
+    state: State<'_, Manager>,
+    id: String,
+    settings: ConnectionLogSettings,
+) -> Result<(), String> {
+    if !state.connections.read().unwrap().contains_key(&id) {
+        return Err(format!("Connection ID '{}' not found", id));
+    }
+    if settings.sample_every == 0 {
+        return Err("sample_every must be at least 1".to_string());
+    }
+    set_connection_log_settings(&id, settings);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_connection_decoders(
+    state: State<'_, Manager>,
+    id: String,
+) -> Result<DecoderConfig, String> {
+    Ok(state.decoders_of(&id)?.config())
+}
+
+/// Replace the framing and decoders of a running connection. Frames decoded by anything
+/// but the `Packet` decoder are emitted as `decoded_payload` and logged with the decoder
+/// name.
+#[tauri::command]
+pub async fn set_connection_decoders(
+    state: State<'_, Manager>,
+    id: String,
+    config: DecoderConfig,
+) -> Result<(), String> {
+    state.decoders_of(&id)?.set_config(config)
+}
+
+#[tauri::command]
+pub async fn get_responder_rules(
+    state: State<'_, Manager>,
+    id: String,
+) -> Result<Vec<ResponderRule>, String> {
+    if !state.connections.read().unwrap().contains_key(&id) {
+        return Err(format!("Connection ID '{}' not found", id));
+    }
+    Ok(state.responders.rules(&id))
+}
+
+/// Replace the auto-responder rules of a connection. The first rule matching a received
+/// packet sends its reply; an empty list turns responding off.
+#[tauri::command]
+pub async fn set_responder_rules(
+    state: State<'_, Manager>,
+    id: String,
+    rules: Vec<ResponderRule>,
+) -> Result<(), String> {
+    if !state.connections.read().unwrap().contains_key(&id) {
+        return Err(format!("Connection ID '{}' not found", id));
+    }
+    state.responders.set_rules(&id, rules)
+}
+
+/// Load a `.proto` file or encoded `FileDescriptorSet` so connections can decode its
+/// message types. Returns the full names of the messages it defines.
+#[tauri::command]
+pub async fn load_proto_schema(
+    path: String,
+    include_dirs: Option<Vec<String>>,
+) -> Result<Vec<String>, String> {
+    SCHEMAS.load(SchemaSource {
+        path,
+        include_dirs: include_dirs.unwrap_or_default(),
+    })
+}
+
+#[tauri::command]
+pub async fn unload_proto_schema(path: String) -> Result<(), String> {
+    SCHEMAS.unload(&path)
+}
+
+#[tauri::command]
+pub async fn list_proto_schemas() -> Result<Vec<SchemaSource>, String> {
+    Ok(SCHEMAS.sources())
+}
+
+#[tauri::command]
+pub async fn list_schema_messages() -> Result<Vec<String>, String> {
+    Ok(SCHEMAS.message_names())
+}
+
+/// Encode `value`, the JSON form of a loaded message type, and send it on a connection
+#[tauri::command]
+pub async fn send_dynamic_packet(
+    state: State<'_, Manager>,
+    id: String,
+    message: String,
+    value: serde_json::Value,
+) -> Result<(), String> {
+    let data = SCHEMAS.encode_json(&message, &value)?;
+    state.send_to(&id, data).await
+}
+
 #[tauri::command]
 pub async fn start_simulation_udp_streaming(
     state: State<'_, Manager>,
@@ -347,6 +962,26 @@ pub async fn list_active_shares(
     Ok(share_tasks.keys().cloned().collect())
 }
 
+/// Send a packet to a connection periodically, as a burst or at a wall time. Returns the
+/// schedule id for `stop_schedule`.
+#[tauri::command]
+pub async fn start_schedule(
+    state: State<'_, Manager>,
+    config: ScheduleConfig,
+) -> Result<String, String> {
+    state.start_schedule(config).await
+}
+
+#[tauri::command]
+pub async fn stop_schedule(state: State<'_, Manager>, id: String) -> Result<(), String> {
+    state.stop_schedule(&id).await
+}
+
+#[tauri::command]
+pub async fn list_schedules(state: State<'_, Manager>) -> Result<Vec<ScheduleInfo>, String> {
+    Ok(state.list_schedules().await)
+}
+
 #[tauri::command]
 pub async fn list_active_simulation_streams(
     state: State<'_, Manager>,
@@ -355,11 +990,15 @@ pub async fn list_active_simulation_streams(
     Ok(simulation_stream_tasks.keys().cloned().collect())
 }
 
+/// Tracks of a UDP connection, each with up to `history_limit` recent positions
+/// received after `since_ms` (milliseconds since the Unix epoch)
 #[tauri::command]
 pub async fn list_udp_targets(
     state: State<'_, Manager>,
     connection_id: String,
-) -> Result<Vec<crate::packet::TargetPacket>, String> {
+    history_limit: Option<usize>,
+    since_ms: Option<u64>,
+) -> Result<Vec<TrackInfo>, String> {
     let connections = &state.connections;
     let conn = {
         let guard = connections.read().unwrap();
@@ -367,8 +1006,7 @@ pub async fn list_udp_targets(
     };
     if let Some(conn) = conn {
         if let Some(udp) = conn.as_any().downcast_ref::<UdpTransport>() {
-            let td = udp.target_data.lock().await;
-            Ok(td.values().cloned().collect())
+            Ok(udp.tracks.list(history_limit.unwrap_or(0), since_ms))
         } else {
             Ok(vec![])
         }
@@ -377,6 +1015,45 @@ pub async fn list_udp_targets(
     }
 }
 
+/// One track with its full kept history, or the part received after `since_ms`
+#[tauri::command]
+pub async fn get_udp_target_history(
+    state: State<'_, Manager>,
+    connection_id: String,
+    target_id: u32,
+    since_ms: Option<u64>,
+    limit: Option<usize>,
+) -> Result<TrackInfo, String> {
+    state.with_udp(&connection_id, |udp| {
+        udp.tracks
+            .track(target_id, limit.unwrap_or(usize::MAX), since_ms)
+            .ok_or_else(|| format!("No track for target {} on {}", target_id, connection_id))
+    })
+}
+
+#[tauri::command]
+pub async fn get_udp_track_config(
+    state: State<'_, Manager>,
+    id: String,
+) -> Result<TrackConfig, String> {
+    state.with_udp(&id, |udp| Ok(udp.tracks.config()))
+}
+
+#[tauri::command]
+pub async fn set_udp_track_config(
+    state: State<'_, Manager>,
+    id: String,
+    config: TrackConfig,
+) -> Result<(), String> {
+    if config.stale_after_ms > config.lost_after_ms {
+        return Err("stale_after_ms must not exceed lost_after_ms".to_string());
+    }
+    state.with_udp(&id, |udp| {
+        udp.tracks.set_config(config);
+        Ok(())
+    })
+}
+
 #[tauri::command]
 pub async fn get_total_udp_targets(state: State<'_, Manager>) -> Result<u32, String> {
     let connections = &state.connections;
@@ -384,10 +1061,7 @@ pub async fn get_total_udp_targets(state: State<'_, Manager>) -> Result<u32, Str
     let mut all_target_ids = std::collections::HashSet::new();
     for conn in conns {
         if let Some(udp) = conn.as_any().downcast_ref::<UdpTransport>() {
-            let td = udp.target_data.lock().await;
-            for target_id in td.keys() {
-                all_target_ids.insert(*target_id);
-            }
+            all_target_ids.extend(udp.tracks.target_ids());
         }
     }
     Ok(all_target_ids.len() as u32)
@@ -408,7 +1082,17 @@ pub async fn share_udp_target_to_connection(
     target_id: u32,
     dest_connection_id: String,
     interval_ms: u64,
+    // Only forward target data received from these sender addresses
+    sources: Option<Vec<String>>,
 ) -> Result<String, String> {
+    let sources: Option<Vec<SocketAddr>> = sources
+        .map(|sources| {
+            sources
+                .iter()
+                .map(|s| s.parse().map_err(|e| format!("Invalid source {}: {}", s, e)))
+                .collect::<Result<_, String>>()
+        })
+        .transpose()?;
     let id = format!(
         "udp_share_{}_{}_{}",
         udp_connection_id,
@@ -474,9 +1158,19 @@ pub async fn share_udp_target_to_connection(
                 continue;
             };
 
-            // Lock and get target data
-            let td = udp.target_data.lock().await;
-            let Some(tp) = td.get(&target_id) else {
+            // Lock and get target data, newest across the allowed sources when filtered
+            let tp = match &sources {
+                Some(sources) => {
+                    let by_source = udp.source_targets.lock().await;
+                    sources
+                        .iter()
+                        .filter_map(|addr| by_source.get(addr)?.get(&target_id))
+                        .max_by(|a, b| a.time.total_cmp(&b.time))
+                        .copied()
+                }
+                None => udp.tracks.latest(target_id),
+            };
+            let Some(tp) = tp else {
                 tracing::warn!("Target data not found for ID: {}", target_id);
                 next_time += interval;
                 continue;
@@ -485,7 +1179,7 @@ pub async fn share_udp_target_to_connection(
             // Encode and send packet
             let mut buf = Vec::with_capacity(128);
             let data = Packet {
-                kind: Some(Kind::TargetPacket(tp.clone())),
+                kind: Some(Kind::TargetPacket(tp)),
             };
 
             if let Err(e) = data.encode(&mut buf) {
@@ -608,3 +1302,251 @@ pub async fn reset_packet_counters(state: State<'_, Manager>) -> Result<(), Stri
     state.reset_packet_counters().await;
     Ok(())
 }
+
+#[tauri::command]
+pub async fn get_connection_statistics(
+    state: State<'_, Manager>,
+) -> Result<HashMap<String, ConnectionStatsSnapshot>, String> {
+    Ok(state.get_connection_statistics().await)
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct StatisticsTick {
+    pub timestamp: String,
+    pub total_received: usize,
+    pub total_sent: usize,
+    pub connections: HashMap<String, ConnectionStatsSnapshot>,
+}
+
+/// Emit a `statistics_tick` event with every connection's statistics once per second
+pub fn spawn_statistics_ticker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = time::interval(Duration::from_secs(1));
+        loop {
+            ticker.tick().await;
+            let manager = tauri::Manager::state::<Manager>(&app);
+            let tick = StatisticsTick {
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                total_received: manager.get_total_packets_received().await,
+                total_sent: manager.get_total_packets_sent().await,
+                connections: manager.get_connection_statistics().await,
+            };
+            let _ = app.emit("statistics_tick", tick);
+        }
+    });
+}
+
+/// Every target fused from all connections
+#[tauri::command]
+pub async fn get_fused_targets(state: State<'_, Manager>) -> Result<Vec<FusedTarget>, String> {
+    Ok(state.picture.list())
+}
+
+#[tauri::command]
+pub async fn get_target_picture_config(
+    state: State<'_, Manager>,
+) -> Result<PictureConfig, String> {
+    Ok(state.picture.config())
+}
+
+#[tauri::command]
+pub async fn set_target_picture_config(
+    state: State<'_, Manager>,
+    config: PictureConfig,
+) -> Result<(), String> {
+    if config.rate_ms == 0 {
+        return Err("rate_ms must be greater than 0".to_string());
+    }
+    state.picture.set_config(config);
+    Ok(())
+}
+
+/// Emit `targets_updated` with the fused picture's changes at the configured rate
+pub fn spawn_picture_publisher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let picture = tauri::Manager::state::<Manager>(&app).picture.clone();
+            time::sleep(Duration::from_millis(picture.config().rate_ms.max(1))).await;
+            let delta = picture.take_delta();
+            if !delta.is_empty() {
+                let _ = app.emit("targets_updated", delta);
+            }
+        }
+    });
+}
+
+/// Check UDP tracks for timeouts and emit `track_lost` for each track that goes lost;
+/// the same pass prunes idle senders and their per-source targets
+pub fn spawn_track_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = time::interval(Duration::from_millis(250));
+        loop {
+            ticker.tick().await;
+            let manager = tauri::Manager::state::<Manager>(&app);
+            let connections: Vec<_> = manager
+                .connections
+                .read()
+                .unwrap()
+                .iter()
+                .map(|(id, conn)| (id.clone(), conn.clone()))
+                .collect();
+            for (id, conn) in connections {
+                if let Some(udp) = conn.as_any().downcast_ref::<UdpTransport>() {
+                    for event in udp.sweep(&id).await {
+                        let _ = app.emit("track_lost", event);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Open a ZeroMQ socket. Decoded packets are emitted as `serial_packet` like every other
+/// connection; every raw message is also emitted as `zmq-message-{id}`.
+#[tauri::command]
+pub async fn start_zmq_connection(
+    state: State<'_, Manager>,
+    id: String,
+    options: ZmqOptions,
+    app: AppHandle,
+) -> Result<(), String> {
+    let message_app = app.clone();
+    let message_event = format!("zmq-message-{}", id);
+    let zmq = ZmqTransport::new(options)?.on_message(Arc::new(move |_topic: &str, data: &[u8]| {
+        let _ = message_app.emit(&message_event, data.to_vec());
+    }));
+    zmq.decoders.set_on_decoded(decoded_hook(app.clone()));
+    let mut transport = Instrumented::new(id.clone(), zmq).on_sent(packet_sent_hook(app.clone()));
+    let picture = state.picture.clone();
+    let responders = state.responders.clone();
+    transport
+        .start::<Packet>(
+            move |conn_id: String, packet: Packet, source: Option<SocketAddr>| {
+                picture.offer_packet(&conn_id, source, &packet);
+                responders.offer_packet(&conn_id, source, &packet);
+                let event = SerialPacketEvent {
+                    id: conn_id,
+                    packet: Some(packet),
+                    source: source.map(|addr| addr.to_string()).unwrap_or_default(),
+                };
+                let _ = app.emit("serial_packet", event);
+            },
+        )
+        .await?;
+
+    state
+        .add_connection(
+            id.clone(),
+            Arc::new(transport) as Arc<dyn crate::transport::Transport + Send + Sync>,
+        )
+        .await
+        .map_err(|e| format!("Failed to add connection: {}", e))?;
+    Ok(())
+}
+
+/// Add a topic filter to a SUB connection
+#[tauri::command]
+pub async fn zmq_subscribe(
+    state: State<'_, Manager>,
+    id: String,
+    topic: String,
+) -> Result<(), String> {
+    state.with_zmq(&id, |zmq| zmq.subscribe(&topic))
+}
+
+#[tauri::command]
+pub async fn zmq_unsubscribe(
+    state: State<'_, Manager>,
+    id: String,
+    topic: String,
+) -> Result<(), String> {
+    state.with_zmq(&id, |zmq| zmq.unsubscribe(&topic))
+}
+
+/// Set the endpoint `add_sub` connects to when none is given
+#[tauri::command]
+pub async fn init_zmq(endpoint: Option<String>) -> Result<(), String> {
+    if let Some(endpoint) = endpoint {
+        ZmqOptions {
+            endpoint: endpoint.clone(),
+            ..Default::default()
+        }
+        .validate()?;
+        *DEFAULT_SUB_ENDPOINT.lock().unwrap() = endpoint;
+    }
+    Ok(())
+}
+
+/// Subscribe connection `id` to `topic`, opening a SUB connection first if there is none
+#[tauri::command]
+pub async fn add_sub(
+    state: State<'_, Manager>,
+    id: String,
+    topic: String,
+    endpoint: Option<String>,
+    app: AppHandle,
+) -> Result<bool, String> {
+    let exists = state.connections.read().unwrap().contains_key(&id);
+    if exists {
+        state.with_zmq(&id, |zmq| zmq.subscribe(&topic))?;
+    } else {
+        let options = ZmqOptions {
+            pattern: ZmqPattern::Sub,
+            endpoint: endpoint.unwrap_or_else(|| DEFAULT_SUB_ENDPOINT.lock().unwrap().clone()),
+            topics: vec![topic],
+            ..Default::default()
+        };
+        start_zmq_connection(state, id, options, app).await?;
+    }
+    Ok(true)
+}
+
+/// Drop one topic of a SUB connection, or the whole connection when no topic is given
+#[tauri::command]
+pub async fn remove_sub(
+    state: State<'_, Manager>,
+    id: String,
+    topic: Option<String>,
+) -> Result<(), String> {
+    match topic {
+        Some(topic) => state.with_zmq(&id, |zmq| zmq.unsubscribe(&topic)),
+        None => {
+            state.with_zmq(&id, |_| Ok(()))?;
+            state.stop(&id).await
+        }
+    }
+}
+
+fn zmq_subs(state: &Manager) -> Vec<(String, String, bool)> {
+    let mut subs: Vec<(String, String, bool)> = state
+        .connections
+        .read()
+        .unwrap()
+        .iter()
+        .filter_map(|(id, conn)| {
+            let zmq = conn.as_any().downcast_ref::<ZmqTransport>()?;
+            let options = zmq.options();
+            (options.pattern == ZmqPattern::Sub)
+                .then(|| (id.clone(), options.topics.join(","), zmq.is_running()))
+        })
+        .collect();
+    subs.sort();
+    subs
+}
+
+/// `(id, topics)` of every SUB connection, topics comma-separated
+#[tauri::command]
+pub async fn list_subs(state: State<'_, Manager>) -> Result<Vec<(String, String)>, String> {
+    Ok(zmq_subs(&state)
+        .into_iter()
+        .map(|(id, topics, _)| (id, topics))
+        .collect())
+}
+
+/// `(id, topics, running)` of every SUB connection
+#[tauri::command]
+pub async fn list_subs_with_status(
+    state: State<'_, Manager>,
+) -> Result<Vec<(String, String, bool)>, String> {
+    Ok(zmq_subs(&state))
+}
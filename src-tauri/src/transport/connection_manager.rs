@@ -1,10 +1,17 @@
-use crate::transport::{ConnectionInfo, Transport};
+use crate::packet::TargetPacket;
+use crate::storage::file_logger::{connection_log_settings, remove_connection_log_settings};
+use crate::transport::instrumented::Instrumented;
+use crate::transport::picture::TargetPicture;
+use crate::transport::responder::ResponderRegistry;
+use crate::transport::scheduler::{Schedule, ScheduleConfig, ScheduleInfo, ScheduleTiming};
+use crate::transport::sensors::SensorStream;
+use crate::transport::{ConnectionInfo, ScenarioRun, ShareInfo, StreamPlan, Transport};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 use tokio::time;
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct Manager {
     pub connections: Arc<RwLock<HashMap<String, Arc<dyn Transport + Send + Sync>>>>,
     pub active_shares:
@@ -14,16 +21,66 @@ pub struct Manager {
     pub simulation_stream_tasks:
         Arc<tokio::sync::Mutex<HashMap<String, tokio::task::JoinHandle<()>>>>,
     pub running_flags: Arc<tokio::sync::Mutex<HashMap<(String, String), Arc<AtomicBool>>>>,
+    /// Interval of every connection-to-connection share, keyed like `share_tasks`
+    pub share_configs: Arc<tokio::sync::Mutex<HashMap<(String, String), u64>>>,
+    /// Streams belonging to the scenario started with `run_scenario_streams`
+    pub active_scenario: Arc<tokio::sync::Mutex<Option<ScenarioRun>>>,
+    /// Targets fused from every connection
+    pub picture: Arc<TargetPicture>,
+    /// Sensor readings forwarded as target packets, keyed by `(sensor_id, connection_id)`
+    pub sensor_streams: Arc<tokio::sync::Mutex<HashMap<(u32, String), SensorStream>>>,
+    /// Scheduled packet transmissions, keyed by schedule id
+    pub schedules: Arc<tokio::sync::Mutex<HashMap<String, Schedule>>>,
+    /// Auto-responder rules replying to received packets
+    pub responders: Arc<ResponderRegistry>,
+}
+
+/// Resolved destination of a `StreamPlan`
+enum StreamTarget {
+    Connection(String),
+    Udp(std::net::SocketAddr, std::net::SocketAddr),
+}
+
+/// Group packets by target and return one list of packets per time step
+fn group_by_step(packets: Vec<TargetPacket>) -> Vec<Vec<TargetPacket>> {
+    let mut target_map: HashMap<u32, Vec<TargetPacket>> = HashMap::new();
+    for packet in packets {
+        target_map.entry(packet.target_id).or_default().push(packet);
+    }
+    let max_steps = target_map.values().map(|v| v.len()).max().unwrap_or(0);
+    let mut target_ids: Vec<u32> = target_map.keys().cloned().collect();
+    target_ids.sort_unstable();
+    (0..max_steps)
+        .map(|step| {
+            target_ids
+                .iter()
+                .filter_map(|id| target_map.get(id).and_then(|p| p.get(step)).cloned())
+                .collect()
+        })
+        .collect()
+}
+
+impl Default for Manager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Manager {
     pub fn new() -> Self {
+        let connections = Arc::new(RwLock::new(HashMap::new()));
         Self {
-            connections: Arc::new(RwLock::new(HashMap::new())),
+            connections: connections.clone(),
             active_shares: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
             share_tasks: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
             simulation_stream_tasks: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
             running_flags: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            share_configs: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            active_scenario: Arc::new(tokio::sync::Mutex::new(None)),
+            picture: Arc::new(TargetPicture::default()),
+            sensor_streams: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            schedules: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            responders: Arc::new(ResponderRegistry::new(connections)),
         }
     }
 
@@ -62,6 +119,21 @@ impl Manager {
             .collect()
     }
 
+    /// Get rolling rate, error and latency statistics for each connection
+    pub async fn get_connection_statistics(
+        &self,
+    ) -> HashMap<String, crate::transport::stats::ConnectionStatsSnapshot> {
+        let guard = self.connections.read().unwrap();
+        guard
+            .iter()
+            .filter_map(|(id, transport)| {
+                transport
+                    .stats()
+                    .map(|stats| (id.clone(), stats.snapshot()))
+            })
+            .collect()
+    }
+
     /// Get total number of active connections
     pub async fn get_connection_count(&self) -> usize {
         let guard = self.connections.read().unwrap();
@@ -127,10 +199,29 @@ impl Manager {
             }
         });
         let mut share_tasks = self.share_tasks.lock().await;
-        share_tasks.insert((from_id_owned, to_id_owned), handle);
+        share_tasks.insert((from_id_owned.clone(), to_id_owned.clone()), handle);
+        drop(share_tasks);
+        self.share_configs
+            .lock()
+            .await
+            .insert((from_id_owned, to_id_owned), interval_ms);
         Ok(tx)
     }
 
+    /// List the connection-to-connection shares started with `share_data_between_ids`
+    pub async fn list_shares(&self) -> Vec<ShareInfo> {
+        self.share_configs
+            .lock()
+            .await
+            .iter()
+            .map(|((from_id, to_id), interval_ms)| ShareInfo {
+                from_id: from_id.clone(),
+                to_id: to_id.clone(),
+                interval_ms: *interval_ms,
+            })
+            .collect()
+    }
+
     /// Stop sharing by aborting the share task and dropping the sender for a specific from/to pair
     pub async fn stop_share(&self, from_id: &str, to_id: &str) -> Result<(), String> {
         if let Some(flag) = self.running_flags.lock().await.remove(&(from_id.to_string(), to_id.to_string())) {
@@ -142,6 +233,10 @@ impl Manager {
         }
         let mut active = self.active_shares.lock().await;
         active.remove(&(from_id.to_string(), to_id.to_string()));
+        self.share_configs
+            .lock()
+            .await
+            .remove(&(from_id.to_string(), to_id.to_string()));
         Ok(())
     }
 
@@ -171,6 +266,24 @@ impl Manager {
         }
     }
 
+    /// Send to one address on a connection, e.g. a known UDP sender
+    pub async fn send_to_addr(
+        &self,
+        id: &str,
+        addr: std::net::SocketAddr,
+        data: Vec<u8>,
+    ) -> Result<(), String> {
+        let transport = {
+            let guard = self.connections.read().unwrap();
+            guard.get(id).cloned()
+        };
+        if let Some(transport) = transport {
+            transport.send_to_addr(addr, data).await
+        } else {
+            Err(format!("No transport found for ID: {}", id))
+        }
+    }
+
     pub async fn stop_all(&self) {
         let ids: Vec<_> = self.connections.read().unwrap().keys().cloned().collect();
         for id in &ids {
@@ -180,17 +293,27 @@ impl Manager {
                 let _ =
                     tokio::time::timeout(std::time::Duration::from_secs(3), transport.stop()).await;
             }
+            remove_connection_log_settings(id);
         }
+        self.picture.clear();
         // Abort and remove all share tasks
         let mut share_tasks = self.share_tasks.lock().await;
         for (_key, handle) in share_tasks.drain() {
             handle.abort();
         }
+        self.share_configs.lock().await.clear();
         // Abort and remove all simulation streaming tasks
         let mut simulation_stream_tasks = self.simulation_stream_tasks.lock().await;
         for (_key, handle) in simulation_stream_tasks.drain() {
             handle.abort();
         }
+        for (_key, stream) in self.sensor_streams.lock().await.drain() {
+            stream.handle.abort();
+        }
+        for (_key, schedule) in self.schedules.lock().await.drain() {
+            schedule.handle.abort();
+        }
+        self.responders.clear();
     }
     pub async fn stop(&self, id: &str) -> Result<(), String> {
         println!("[manager] Stopping connection {}", id);
@@ -251,6 +374,29 @@ impl Manager {
                     }
                 }
             }
+            self.share_configs
+                .lock()
+                .await
+                .retain(|(from_id, to_id), _| from_id != id && to_id != id);
+            // Sensor streams into or out of this connection
+            self.sensor_streams.lock().await.retain(|_, stream| {
+                let involved = stream.config.connection_id == id
+                    || stream.config.udp_connection_id.as_deref() == Some(id);
+                if involved {
+                    stream.handle.abort();
+                }
+                !involved
+            });
+            self.schedules.lock().await.retain(|_, schedule| {
+                let involved = schedule.config.connection_id == id;
+                if involved {
+                    schedule.handle.abort();
+                }
+                !involved
+            });
+            remove_connection_log_settings(id);
+            self.picture.remove_connection(id);
+            self.responders.remove_connection(id);
             println!("[manager] Successfully stopped connection {}", id);
             Ok(())
         } else {
@@ -279,6 +425,14 @@ impl Manager {
                         baud_rate: Some(serial.baud_rate),
                         local_addr: None,
                         remote_addr: None,
+                        peers: Vec::new(),
+                        reply_to_sender: false,
+                        udp_options: Default::default(),
+                        tracks: Default::default(),
+                        zmq: None,
+                        decoders: transport.decoders().map(|d| d.config()).unwrap_or_default(),
+                        responders: self.responders.rules(id),
+                        logging: connection_log_settings(id),
                     }
                 } else if let Some(udp) = transport
                     .as_any()
@@ -291,7 +445,36 @@ impl Manager {
                         port: None,
                         baud_rate: None,
                         local_addr: Some(udp.local_addr.to_string()),
-                        remote_addr: udp.remote_addr.map(|a| a.to_string()),
+                        remote_addr: udp.remote_addr().map(|a| a.to_string()),
+                        peers: udp.peers.addrs().iter().map(|a| a.to_string()).collect(),
+                        reply_to_sender: udp.peers.reply_to_sender(),
+                        udp_options: udp.options.lock().unwrap().clone(),
+                        tracks: udp.tracks.config(),
+                        zmq: None,
+                        decoders: transport.decoders().map(|d| d.config()).unwrap_or_default(),
+                        responders: self.responders.rules(id),
+                        logging: connection_log_settings(id),
+                    }
+                } else if let Some(zmq) = transport
+                    .as_any()
+                    .downcast_ref::<crate::transport::zmq::ZmqTransport>()
+                {
+                    ConnectionInfo {
+                        id: id.clone(),
+                        name: transport.name(),
+                        connection_type: Some(crate::transport::ConnectionType::Zmq),
+                        port: None,
+                        baud_rate: None,
+                        local_addr: None,
+                        remote_addr: None,
+                        peers: Vec::new(),
+                        reply_to_sender: false,
+                        udp_options: Default::default(),
+                        tracks: Default::default(),
+                        zmq: Some(zmq.options()),
+                        decoders: transport.decoders().map(|d| d.config()).unwrap_or_default(),
+                        responders: self.responders.rules(id),
+                        logging: connection_log_settings(id),
                     }
                 } else {
                     ConnectionInfo {
@@ -302,32 +485,86 @@ impl Manager {
                         baud_rate: None,
                         local_addr: None,
                         remote_addr: None,
+                        peers: Vec::new(),
+                        reply_to_sender: false,
+                        udp_options: Default::default(),
+                        tracks: Default::default(),
+                        zmq: None,
+                        decoders: transport.decoders().map(|d| d.config()).unwrap_or_default(),
+                        responders: self.responders.rules(id),
+                        logging: connection_log_settings(id),
                     }
                 }
             })
             .collect()
     }
 
+    /// Make `remote_addr` the only configured peer of a UDP connection
     pub async fn set_udp_remote_addr(
         &self,
         id: &str,
         remote_addr: std::net::SocketAddr,
     ) -> Result<(), String> {
-        let mut guard = self.connections.write().unwrap();
-        if let Some(conn) = guard.get_mut(id) {
-            // Downcast the Arc<dyn Transport> to UdpTransport
-            if let Some(udp) = Arc::get_mut(&mut *conn).and_then(|t| {
-                t.as_any_mut()
-                    .downcast_mut::<crate::transport::udp::UdpTransport>()
-            }) {
-                udp.remote_addr = Some(remote_addr);
-                Ok(())
-            } else {
-                Err("Connection is not a UdpTransport or is shared".to_string())
-            }
-        } else {
-            Err(format!("No connection found for id {}", id))
-        }
+        self.with_udp(id, |udp| {
+            udp.peers.set_single(remote_addr);
+            Ok(())
+        })
+    }
+
+    /// Run `f` on the UDP transport behind connection `id`
+    pub fn with_udp<R>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&crate::transport::udp::UdpTransport) -> Result<R, String>,
+    ) -> Result<R, String> {
+        let conn = self
+            .connections
+            .read()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("No connection found for id {}", id))?;
+        let udp = conn
+            .as_any()
+            .downcast_ref::<crate::transport::udp::UdpTransport>()
+            .ok_or_else(|| format!("Connection {} is not a UDP connection", id))?;
+        f(udp)
+    }
+
+    /// Run `f` on the ZeroMQ transport behind connection `id`
+    pub fn with_zmq<R>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&crate::transport::zmq::ZmqTransport) -> Result<R, String>,
+    ) -> Result<R, String> {
+        let conn = self
+            .connections
+            .read()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("No connection found for id {}", id))?;
+        let zmq = conn
+            .as_any()
+            .downcast_ref::<crate::transport::zmq::ZmqTransport>()
+            .ok_or_else(|| format!("Connection {} is not a ZeroMQ connection", id))?;
+        f(zmq)
+    }
+
+    /// Decoder pipeline of connection `id`
+    pub fn decoders_of(
+        &self,
+        id: &str,
+    ) -> Result<Arc<crate::transport::decoders::DecoderPipeline>, String> {
+        let conn = self
+            .connections
+            .read()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("No connection found for id {}", id))?;
+        conn.decoders()
+            .ok_or_else(|| format!("Connection {} does not support decoders", id))
     }
 
     /// Check if a socket address is already in use by any connection
@@ -353,7 +590,7 @@ impl Manager {
         interval_ms: u64,
         packets: Vec<crate::packet::TargetPacket>,
     ) -> Result<String, String> {
-        use crate::packet::{packet::Kind, Packet, TargetPacket, TargetPacketList};
+        use crate::packet::{packet::Kind, Packet, TargetPacketList};
         use crate::transport::udp::UdpTransport;
         use prost::Message;
         use std::sync::Arc;
@@ -361,30 +598,16 @@ impl Manager {
 
         let id = format!("sim_udp_{}", Uuid::new_v4());
         let mut transport = UdpTransport::new(local_addr).await?;
-        transport.remote_addr = Some(remote_addr);
-        let transport = Arc::new(transport) as Arc<dyn crate::transport::Transport + Send + Sync>;
+        transport.peers.set_single(remote_addr);
+        let transport = Arc::new(Instrumented::new(id.clone(), transport))
+            as Arc<dyn crate::transport::Transport + Send + Sync>;
         self.add_connection(id.clone(), transport.clone()).await?;
 
         // Group packets by target_id and align by time step
-        let mut target_map: std::collections::HashMap<u32, Vec<TargetPacket>> =
-            std::collections::HashMap::new();
-        for packet in packets {
-            target_map.entry(packet.target_id).or_default().push(packet);
-        }
-        // Find the max number of time steps
-        let max_steps = target_map.values().map(|v| v.len()).max().unwrap_or(0);
-        let target_ids: Vec<u32> = target_map.keys().cloned().collect();
+        let steps = group_by_step(packets);
 
         let handle = tokio::spawn(async move {
-            for step in 0..max_steps {
-                let mut step_packets = Vec::new();
-                for &target_id in &target_ids {
-                    if let Some(packets) = target_map.get(&target_id) {
-                        if let Some(packet) = packets.get(step) {
-                            step_packets.push(packet.clone());
-                        }
-                    }
-                }
+            for step_packets in steps {
                 if !step_packets.is_empty() {
                     let mut buf = Vec::new();
                     let data = Packet {
@@ -449,4 +672,241 @@ impl Manager {
         );
         Ok(())
     }
+
+    /// Stream simulation steps as `TargetPacketList`s through an existing connection
+    pub async fn stream_steps_to_connection(
+        &self,
+        connection_id: &str,
+        interval_ms: u64,
+        packets: Vec<TargetPacket>,
+    ) -> Result<String, String> {
+        use crate::packet::{packet::Kind, Packet, TargetPacketList};
+        use prost::Message;
+        use uuid::Uuid;
+
+        if !self.connections.read().unwrap().contains_key(connection_id) {
+            return Err(format!("No transport found for ID: {}", connection_id));
+        }
+        let id = format!("scenario_{}", Uuid::new_v4());
+        let steps = group_by_step(packets);
+        let manager = self.clone();
+        let conn_id = connection_id.to_string();
+        let handle = tokio::spawn(async move {
+            for step_packets in steps {
+                let mut buf = Vec::new();
+                let data = Packet {
+                    kind: Some(Kind::TargetPacketList(TargetPacketList {
+                        packets: step_packets,
+                    })),
+                };
+                if let Ok(()) = data.encode(&mut buf) {
+                    let _ = manager.send_to(&conn_id, buf).await;
+                }
+                time::sleep(time::Duration::from_millis(interval_ms)).await;
+            }
+        });
+        let mut share_tasks = self.share_tasks.lock().await;
+        share_tasks.insert((id.clone(), connection_id.to_string()), handle);
+        Ok(id)
+    }
+
+    /// Start every stream of a scenario, replacing the streams of any scenario already running
+    pub async fn run_scenario_streams(
+        &self,
+        name: &str,
+        plans: Vec<(StreamPlan, Vec<TargetPacket>)>,
+    ) -> Result<ScenarioRun, String> {
+        // Validate every plan before starting anything
+        let mut targets = Vec::with_capacity(plans.len());
+        for (plan, _) in &plans {
+            let target = match (&plan.connection_id, &plan.local_addr, &plan.remote_addr) {
+                (Some(connection_id), _, _) => StreamTarget::Connection(connection_id.clone()),
+                (None, Some(local_addr), Some(remote_addr)) => StreamTarget::Udp(
+                    local_addr
+                        .parse()
+                        .map_err(|e| format!("Invalid local_addr: {}", e))?,
+                    remote_addr
+                        .parse()
+                        .map_err(|e| format!("Invalid remote_addr: {}", e))?,
+                ),
+                _ => {
+                    return Err(
+                        "Stream plan needs a connection_id or both local_addr and remote_addr"
+                            .to_string(),
+                    )
+                }
+            };
+            targets.push(target);
+        }
+
+        self.stop_scenario().await?;
+        let mut run = ScenarioRun {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        for ((plan, packets), target) in plans.into_iter().zip(targets) {
+            if packets.is_empty() {
+                tracing::warn!("Scenario {}: no packets for targets {:?}", name, plan.target_ids);
+                continue;
+            }
+            let started = match target {
+                StreamTarget::Connection(connection_id) => self
+                    .stream_steps_to_connection(&connection_id, plan.interval_ms, packets)
+                    .await
+                    .map(|id| run.shares.push((id, connection_id))),
+                StreamTarget::Udp(local_addr, remote_addr) => self
+                    .simulation_init_and_stream(local_addr, remote_addr, plan.interval_ms, packets)
+                    .await
+                    .map(|id| run.simulation_streams.push(id)),
+            };
+            if let Err(e) = started {
+                // Do not leave half a scenario running
+                *self.active_scenario.lock().await = Some(run);
+                self.stop_scenario().await?;
+                return Err(e);
+            }
+        }
+        *self.active_scenario.lock().await = Some(run.clone());
+        Ok(run)
+    }
+
+    /// Start sending `config.packet` to `config.connection_id` on its schedule. Packets are
+    /// looked up through the connection map on every send, so a schedule whose connection
+    /// is gone counts errors until it is stopped.
+    pub async fn start_schedule(&self, config: ScheduleConfig) -> Result<String, String> {
+        use prost::Message;
+        use uuid::Uuid;
+
+        config.validate()?;
+        if !self
+            .connections
+            .read()
+            .unwrap()
+            .contains_key(&config.connection_id)
+        {
+            return Err(format!(
+                "Connection ID '{}' not found",
+                config.connection_id
+            ));
+        }
+        let id = format!("schedule_{}", Uuid::new_v4());
+        let sent = Arc::new(AtomicU64::new(0));
+        let errors = Arc::new(AtomicU64::new(0));
+        let last_sent_ms = Arc::new(AtomicU64::new(0));
+
+        let connections = self.connections.clone();
+        let task_config = config.clone();
+        let task_sent = sent.clone();
+        let task_errors = errors.clone();
+        let task_last_sent = last_sent_ms.clone();
+        let handle = tokio::spawn(async move {
+            let send = |seq: u64| {
+                let data = task_config.render(seq).encode_to_vec();
+                let conn = connections
+                    .read()
+                    .unwrap()
+                    .get(&task_config.connection_id)
+                    .cloned();
+                let sent = task_sent.clone();
+                let errors = task_errors.clone();
+                let last_sent = task_last_sent.clone();
+                async move {
+                    let result = match conn {
+                        Some(conn) => conn.send(data).await,
+                        None => Err("connection closed".to_string()),
+                    };
+                    match result {
+                        Ok(()) => {
+                            sent.fetch_add(1, Ordering::Relaxed);
+                            last_sent.store(crate::transport::tracks::now_ms(), Ordering::Relaxed);
+                        }
+                        Err(e) => {
+                            errors.fetch_add(1, Ordering::Relaxed);
+                            tracing::warn!("Scheduled send failed: {}", e);
+                        }
+                    }
+                }
+            };
+            match task_config.timing.clone() {
+                ScheduleTiming::Periodic { interval_ms, count } => {
+                    let mut ticker = time::interval(std::time::Duration::from_millis(interval_ms));
+                    ticker.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+                    let mut seq = 0;
+                    while count.map(|count| seq < count).unwrap_or(true) {
+                        ticker.tick().await;
+                        send(seq).await;
+                        seq += 1;
+                    }
+                }
+                ScheduleTiming::Burst { count, spacing_ms } => {
+                    for seq in 0..count {
+                        if seq > 0 && spacing_ms > 0 {
+                            time::sleep(std::time::Duration::from_millis(spacing_ms)).await;
+                        }
+                        send(seq).await;
+                    }
+                }
+                ScheduleTiming::At { at } => {
+                    let wait = (at - chrono::Utc::now()).to_std().unwrap_or_default();
+                    time::sleep(wait).await;
+                    send(0).await;
+                }
+            }
+        });
+        self.schedules.lock().await.insert(
+            id.clone(),
+            Schedule {
+                config,
+                sent,
+                errors,
+                last_sent_ms,
+                handle,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Every schedule, finished ones included until they are stopped
+    pub async fn list_schedules(&self) -> Vec<ScheduleInfo> {
+        let mut list: Vec<ScheduleInfo> = self
+            .schedules
+            .lock()
+            .await
+            .iter()
+            .map(|(id, schedule)| schedule.info(id))
+            .collect();
+        list.sort_by(|a, b| a.id.cmp(&b.id));
+        list
+    }
+
+    pub async fn stop_schedule(&self, id: &str) -> Result<(), String> {
+        let schedule = self
+            .schedules
+            .lock()
+            .await
+            .remove(id)
+            .ok_or_else(|| format!("Schedule '{}' not found", id))?;
+        schedule.handle.abort();
+        Ok(())
+    }
+
+    /// Stop every stream started by the running scenario
+    pub async fn stop_scenario(&self) -> Result<(), String> {
+        let Some(run) = self.active_scenario.lock().await.take() else {
+            return Ok(());
+        };
+        {
+            let mut share_tasks = self.share_tasks.lock().await;
+            for key in &run.shares {
+                if let Some(handle) = share_tasks.remove(key) {
+                    handle.abort();
+                }
+            }
+        }
+        for id in &run.simulation_streams {
+            self.stop_simulation_udp_streaming(id).await?;
+        }
+        println!("[manager] Stopped scenario {}", run.name);
+        Ok(())
+    }
 }
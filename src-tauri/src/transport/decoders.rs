@@ -0,0 +1,500 @@
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::packet::Packet;
+use crate::simulation::SimulationResultList;
+use crate::storage::file_logger::log_decoded;
+use crate::transport::framing::Framing;
+use crate::transport::schemas::SCHEMAS;
+
+/// Sources with a partial frame kept before the buffers are dropped
+const MAX_PENDING_SOURCES: usize = 256;
+
+fn comma() -> char {
+    ','
+}
+
+/// Scalar type of a fixed-layout struct field
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    /// `len` bytes, shown as hex
+    Bytes,
+    /// `len` bytes of text, trailing NULs removed
+    Str,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StructField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: FieldType,
+    /// Size of `bytes` and `str` fields
+    #[serde(default)]
+    pub len: usize,
+}
+
+impl StructField {
+    fn size(&self) -> usize {
+        match self.ty {
+            FieldType::U8 | FieldType::I8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+            FieldType::U64 | FieldType::I64 | FieldType::F64 => 8,
+            FieldType::Bytes | FieldType::Str => self.len,
+        }
+    }
+}
+
+/// Packed binary struct; a frame matches when its length is exactly the struct size
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StructLayout {
+    #[serde(default)]
+    pub name: String,
+    pub fields: Vec<StructField>,
+    #[serde(default)]
+    pub little_endian: bool,
+}
+
+impl StructLayout {
+    pub fn size(&self) -> usize {
+        self.fields.iter().map(StructField::size).sum()
+    }
+
+    fn decode(&self, data: &[u8]) -> Option<Value> {
+        if data.len() != self.size() {
+            return None;
+        }
+        let mut object = Map::new();
+        let mut offset = 0;
+        for field in &self.fields {
+            let bytes = &data[offset..offset + field.size()];
+            offset += field.size();
+            macro_rules! num {
+                ($t:ty) => {{
+                    let raw = bytes.try_into().ok()?;
+                    let value = if self.little_endian {
+                        <$t>::from_le_bytes(raw)
+                    } else {
+                        <$t>::from_be_bytes(raw)
+                    };
+                    json!(value)
+                }};
+            }
+            let value = match field.ty {
+                FieldType::U8 => num!(u8),
+                FieldType::I8 => num!(i8),
+                FieldType::U16 => num!(u16),
+                FieldType::I16 => num!(i16),
+                FieldType::U32 => num!(u32),
+                FieldType::I32 => num!(i32),
+                FieldType::U64 => num!(u64),
+                FieldType::I64 => num!(i64),
+                FieldType::F32 => num!(f32),
+                FieldType::F64 => num!(f64),
+                FieldType::Bytes => json!(to_hex(bytes)),
+                FieldType::Str => {
+                    json!(String::from_utf8_lossy(bytes).trim_end_matches('\0'))
+                }
+            };
+            object.insert(field.name.clone(), value);
+        }
+        Some(Value::Object(object))
+    }
+}
+
+/// One way of turning a frame into a value
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DecoderSpec {
+    /// The compiled-in `Packet` protobuf
+    Packet,
+    SimulationResultList,
+    /// A message type from a schema loaded with `load_proto_schema`, by full name
+    Protobuf {
+        message: String,
+    },
+    /// Delimited text; numeric fields become numbers. With `columns` the value is an object
+    /// and the field count must match, otherwise an array.
+    Csv {
+        #[serde(default = "comma")]
+        delimiter: char,
+        #[serde(default)]
+        columns: Vec<String>,
+    },
+    /// Always matches: the bytes as hex, and as text when they are UTF-8
+    Raw,
+    Struct(StructLayout),
+}
+
+impl DecoderSpec {
+    /// Name carried in `decoded_payload` events
+    pub fn name(&self) -> String {
+        match self {
+            DecoderSpec::Packet => "packet".to_string(),
+            DecoderSpec::SimulationResultList => "simulation_result_list".to_string(),
+            DecoderSpec::Protobuf { message } => message.clone(),
+            DecoderSpec::Csv { .. } => "csv".to_string(),
+            DecoderSpec::Raw => "raw".to_string(),
+            DecoderSpec::Struct(layout) if !layout.name.is_empty() => layout.name.clone(),
+            DecoderSpec::Struct(_) => "struct".to_string(),
+        }
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Option<Value> {
+        match self {
+            DecoderSpec::Packet => serde_json::to_value(Packet::decode(data).ok()?).ok(),
+            DecoderSpec::SimulationResultList => {
+                serde_json::to_value(SimulationResultList::decode(data).ok()?).ok()
+            }
+            DecoderSpec::Protobuf { message } => SCHEMAS.decode_json(message, data).ok(),
+            DecoderSpec::Csv { delimiter, columns } => {
+                let text = std::str::from_utf8(data).ok()?.trim();
+                if text.is_empty() {
+                    return None;
+                }
+                let fields: Vec<Value> = text
+                    .split(*delimiter)
+                    .map(|field| {
+                        let field = field.trim();
+                        match field.parse::<f64>() {
+                            Ok(number) => json!(number),
+                            Err(_) => json!(field),
+                        }
+                    })
+                    .collect();
+                if columns.is_empty() {
+                    return Some(Value::Array(fields));
+                }
+                if fields.len() != columns.len() {
+                    return None;
+                }
+                Some(Value::Object(columns.iter().cloned().zip(fields).collect()))
+            }
+            DecoderSpec::Raw => Some(json!({
+                "len": data.len(),
+                "hex": to_hex(data),
+                "text": std::str::from_utf8(data).ok(),
+            })),
+            DecoderSpec::Struct(layout) => layout.decode(data),
+        }
+    }
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Framing and decoders of a connection, persisted in `ConnectionInfo`. Decoders are tried
+/// in order and the first that matches a frame wins.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DecoderConfig {
+    #[serde(default)]
+    pub framing: Framing,
+    pub decoders: Vec<DecoderSpec>,
+}
+
+impl Default for DecoderConfig {
+    fn default() -> Self {
+        Self {
+            framing: Framing::None,
+            decoders: vec![DecoderSpec::Packet],
+        }
+    }
+}
+
+impl DecoderConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        self.framing.validate()?;
+        if self.decoders.is_empty() {
+            return Err("At least one decoder is required".to_string());
+        }
+        for decoder in &self.decoders {
+            if let DecoderSpec::Struct(layout) = decoder {
+                if layout.size() == 0 {
+                    return Err(format!("Struct {} has no fields", decoder.name()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Emitted as `decoded_payload` for every frame a non-default pipeline decodes
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DecodedPayload {
+    pub id: String,
+    pub decoder: String,
+    pub value: Value,
+    pub source: Option<String>,
+    pub bytes: usize,
+}
+
+pub type DecodedHook = Arc<dyn Fn(&DecodedPayload) + Send + Sync>;
+
+/// Frames fed through a pipeline
+#[derive(Debug, Default)]
+pub struct Decoded {
+    /// Frames decoded as `Packet`, for the transport's usual packet handling
+    pub packets: Vec<Vec<u8>>,
+    /// Frames no decoder matched
+    pub undecoded: usize,
+}
+
+/// A connection's decoders. With the default config transports keep decoding `Packet`s
+/// themselves; otherwise they feed every received chunk through `feed`.
+#[derive(Default)]
+pub struct DecoderPipeline {
+    config: RwLock<DecoderConfig>,
+    /// Partial frames per source, so datagrams from different UDP peers are never spliced
+    pending: Mutex<HashMap<Option<SocketAddr>, Vec<u8>>>,
+    on_decoded: RwLock<Option<DecodedHook>>,
+}
+
+impl std::fmt::Debug for DecoderPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecoderPipeline")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DecoderPipeline {
+    pub fn config(&self) -> DecoderConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Use `config` from now on. Its protobuf message types must already be loaded.
+    pub fn set_config(&self, config: DecoderConfig) -> Result<(), String> {
+        config.validate()?;
+        for decoder in &config.decoders {
+            if let DecoderSpec::Protobuf { message } = decoder {
+                if SCHEMAS.message(message).is_none() {
+                    return Err(format!("Message type {} is not loaded", message));
+                }
+            }
+        }
+        *self.config.write().unwrap() = config;
+        self.pending.lock().unwrap().clear();
+        Ok(())
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self.config.read().unwrap() == DecoderConfig::default()
+    }
+
+    pub fn set_on_decoded(&self, hook: DecodedHook) {
+        *self.on_decoded.write().unwrap() = Some(hook);
+    }
+
+    /// Split `data` into frames and decode each one, reporting it to the hook and, unless
+    /// it is a `Packet` the transport will log itself, to the connection log
+    pub fn feed(&self, id: &str, data: &[u8], source: Option<SocketAddr>) -> Decoded {
+        let config = self.config.read().unwrap();
+        let frames = {
+            let mut pending = self.pending.lock().unwrap();
+            if pending.len() >= MAX_PENDING_SOURCES && !pending.contains_key(&source) {
+                pending.clear();
+            }
+            let buffer = pending.entry(source).or_default();
+            let frames = config.framing.split(buffer, data);
+            if buffer.is_empty() {
+                pending.remove(&source);
+            }
+            frames
+        };
+        let hook = self.on_decoded.read().unwrap().clone();
+        let mut decoded = Decoded::default();
+        for frame in frames {
+            let Some((decoder, value)) = config
+                .decoders
+                .iter()
+                .find_map(|d| d.decode(&frame).map(|value| (d, value)))
+            else {
+                decoded.undecoded += 1;
+                continue;
+            };
+            let name = decoder.name();
+            if *decoder != DecoderSpec::Packet {
+                log_decoded(id, &name, &value, &frame);
+            }
+            if let Some(hook) = &hook {
+                hook(&DecodedPayload {
+                    id: id.to_string(),
+                    decoder: name,
+                    value,
+                    source: source.map(|addr| addr.to_string()),
+                    bytes: frame.len(),
+                });
+            }
+            if *decoder == DecoderSpec::Packet {
+                decoded.packets.push(frame);
+            }
+        }
+        decoded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::file_logger::{
+        set_connection_log_settings, ConnectionLogSettings, LogDirection,
+    };
+
+    const ID: &str = "decoder_test";
+
+    fn text_pipeline(framing: Framing) -> DecoderPipeline {
+        set_connection_log_settings(
+            ID,
+            ConnectionLogSettings {
+                direction: LogDirection::None,
+                ..Default::default()
+            },
+        );
+        let pipeline = DecoderPipeline::default();
+        pipeline
+            .set_config(DecoderConfig {
+                framing,
+                decoders: vec![DecoderSpec::Raw],
+            })
+            .unwrap();
+        pipeline
+    }
+
+    /// Source and text of every payload a pipeline reports
+    type Seen = Arc<Mutex<Vec<(Option<String>, Value)>>>;
+
+    fn collect(pipeline: &DecoderPipeline) -> Seen {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        pipeline.set_on_decoded(Arc::new(move |payload: &DecodedPayload| {
+            let text = payload.value["text"].clone();
+            sink.lock().unwrap().push((payload.source.clone(), text));
+        }));
+        seen
+    }
+
+    #[test]
+    fn partial_frames_are_kept_per_source() {
+        let pipeline = text_pipeline(Framing::Delimited {
+            delimiter: "\n".to_string(),
+        });
+        let seen = collect(&pipeline);
+        let a: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+        let b: SocketAddr = "127.0.0.1:2000".parse().unwrap();
+        pipeline.feed(ID, b"hel", Some(a));
+        pipeline.feed(ID, b"wor", Some(b));
+        pipeline.feed(ID, b"lo\n", Some(a));
+        pipeline.feed(ID, b"ld\n", Some(b));
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                (Some(a.to_string()), json!("hello")),
+                (Some(b.to_string()), json!("world")),
+            ]
+        );
+        assert!(pipeline.pending.lock().unwrap().is_empty());
+    }
+
+    fn csv(delimiter: char, columns: &[&str]) -> DecoderSpec {
+        DecoderSpec::Csv {
+            delimiter,
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn csv_without_columns_is_an_array() {
+        assert_eq!(
+            csv(',', &[]).decode(b" 1, 2.5 ,abc\n"),
+            Some(json!([1.0, 2.5, "abc"]))
+        );
+        assert_eq!(csv(',', &[]).decode(b"  \n"), None);
+        assert_eq!(csv(',', &[]).decode(&[0xff, 0xfe]), None);
+    }
+
+    #[test]
+    fn csv_with_columns_is_an_object() {
+        let spec = csv(';', &["id", "name"]);
+        assert_eq!(
+            spec.decode(b"7;alpha"),
+            Some(json!({"id": 7.0, "name": "alpha"}))
+        );
+        assert_eq!(spec.decode(b"7;alpha;extra"), None);
+        assert_eq!(spec.decode(b"7"), None);
+    }
+
+    fn field(name: &str, ty: FieldType, len: usize) -> StructField {
+        StructField {
+            name: name.to_string(),
+            ty,
+            len,
+        }
+    }
+
+    fn layout(little_endian: bool) -> StructLayout {
+        StructLayout {
+            name: "sample".to_string(),
+            fields: vec![
+                field("id", FieldType::U16, 0),
+                field("delta", FieldType::I32, 0),
+                field("tag", FieldType::Str, 4),
+                field("raw", FieldType::Bytes, 2),
+            ],
+            little_endian,
+        }
+    }
+
+    #[test]
+    fn struct_big_endian() {
+        let data = [
+            0x01, 0x02, 0xff, 0xff, 0xff, 0xfe, b'a', b'b', 0, 0, 0xde, 0xad,
+        ];
+        assert_eq!(layout(false).size(), data.len());
+        assert_eq!(
+            layout(false).decode(&data),
+            Some(json!({"id": 0x0102, "delta": -2, "tag": "ab", "raw": "dead"}))
+        );
+    }
+
+    #[test]
+    fn struct_little_endian() {
+        let data = [
+            0x02, 0x01, 0xfe, 0xff, 0xff, 0xff, b'a', b'b', b'c', b'd', 0, 1,
+        ];
+        assert_eq!(
+            layout(true).decode(&data),
+            Some(json!({"id": 0x0102, "delta": -2, "tag": "abcd", "raw": "0001"}))
+        );
+    }
+
+    #[test]
+    fn struct_needs_exact_size() {
+        let layout = StructLayout {
+            name: String::new(),
+            fields: vec![field("x", FieldType::F32, 0), field("y", FieldType::F64, 0)],
+            little_endian: false,
+        };
+        let mut data = 1.5f32.to_be_bytes().to_vec();
+        data.extend_from_slice(&(-0.25f64).to_be_bytes());
+        assert_eq!(layout.decode(&data), Some(json!({"x": 1.5, "y": -0.25})));
+        assert_eq!(layout.decode(&data[..11]), None);
+        data.push(0);
+        assert_eq!(layout.decode(&data), None);
+    }
+}
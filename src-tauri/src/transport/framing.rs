@@ -0,0 +1,319 @@
+use serde::{Deserialize, Serialize};
+
+/// Bytes kept while waiting for the rest of a frame before the buffer is dropped
+const MAX_PENDING: usize = 1024 * 1024;
+
+fn newline() -> String {
+    "\n".to_string()
+}
+
+/// How received bytes are split into frames before decoding
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Framing {
+    /// Every read, datagram or message is one frame
+    #[default]
+    None,
+    /// Frames end with `delimiter`, which is not part of the frame
+    Delimited {
+        #[serde(default = "newline")]
+        delimiter: String,
+    },
+    /// Every frame is `size` bytes
+    Fixed { size: usize },
+    /// Frames start with their length as a `width`-byte unsigned integer (1, 2 or 4),
+    /// big-endian unless `little_endian`
+    LengthPrefixed {
+        width: u8,
+        #[serde(default)]
+        little_endian: bool,
+    },
+}
+
+impl Framing {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Framing::Delimited { delimiter } if delimiter.is_empty() => {
+                Err("Frame delimiter cannot be empty".to_string())
+            }
+            Framing::Fixed { size: 0 } => Err("Frame size must be greater than 0".to_string()),
+            Framing::LengthPrefixed { width, .. } if ![1, 2, 4].contains(width) => Err(format!(
+                "Length prefix must be 1, 2 or 4 bytes, not {}",
+                width
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Wrap `data` as one frame for sending; short fixed-size frames are padded with zeros
+    pub fn encode(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            Framing::None => Ok(data.to_vec()),
+            Framing::Delimited { delimiter } => {
+                let mut frame = data.to_vec();
+                frame.extend_from_slice(delimiter.as_bytes());
+                Ok(frame)
+            }
+            Framing::Fixed { size } if data.len() > *size => Err(format!(
+                "{} bytes do not fit in a {}-byte frame",
+                data.len(),
+                size
+            )),
+            Framing::Fixed { size } => {
+                let mut frame = data.to_vec();
+                frame.resize(*size, 0);
+                Ok(frame)
+            }
+            Framing::LengthPrefixed {
+                width,
+                little_endian,
+            } => {
+                let width = *width as usize;
+                let max = if width == 4 {
+                    u32::MAX as usize
+                } else {
+                    (1usize << (8 * width)) - 1
+                };
+                if data.len() > max {
+                    return Err(format!(
+                        "{} bytes do not fit a {}-byte length prefix",
+                        data.len(),
+                        width
+                    ));
+                }
+                let len = data.len() as u32;
+                let mut frame = if *little_endian {
+                    len.to_le_bytes()[..width].to_vec()
+                } else {
+                    len.to_be_bytes()[4 - width..].to_vec()
+                };
+                frame.extend_from_slice(data);
+                Ok(frame)
+            }
+        }
+    }
+
+    /// Append `data` to `pending` and take every complete frame out of it
+    pub fn split(&self, pending: &mut Vec<u8>, data: &[u8]) -> Vec<Vec<u8>> {
+        if *self == Framing::None {
+            return vec![data.to_vec()];
+        }
+        pending.extend_from_slice(data);
+        let mut frames = Vec::new();
+        match self {
+            Framing::None => {}
+            Framing::Delimited { delimiter } => {
+                let delimiter = delimiter.as_bytes();
+                while let Some(end) = pending
+                    .windows(delimiter.len())
+                    .position(|w| w == delimiter)
+                {
+                    let frame: Vec<u8> = pending.drain(..end + delimiter.len()).take(end).collect();
+                    if !frame.is_empty() {
+                        frames.push(frame);
+                    }
+                }
+            }
+            Framing::Fixed { size } => {
+                while pending.len() >= *size {
+                    frames.push(pending.drain(..*size).collect());
+                }
+            }
+            Framing::LengthPrefixed {
+                width,
+                little_endian,
+            } => {
+                let width = *width as usize;
+                while pending.len() >= width {
+                    let mut prefix = [0u8; 4];
+                    if *little_endian {
+                        prefix[..width].copy_from_slice(&pending[..width]);
+                    } else {
+                        prefix[4 - width..].copy_from_slice(&pending[..width]);
+                    }
+                    let len = if *little_endian {
+                        u32::from_le_bytes(prefix)
+                    } else {
+                        u32::from_be_bytes(prefix)
+                    } as usize;
+                    if pending.len() < width + len {
+                        break;
+                    }
+                    frames.push(pending.drain(..width + len).skip(width).collect());
+                }
+            }
+        }
+        if pending.len() > MAX_PENDING {
+            pending.clear();
+        }
+        frames
+    }
+}
+
+/// Parse hex such as `"01 02 ff"`, `"0x0102FF"` or `"01:02:ff"`
+pub fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let digits: String = hex
+        .split_whitespace()
+        .flat_map(|part| part.split([':', ',', '-']))
+        .map(|part| part.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect();
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid hex digit '{}' in '{}'", c, hex));
+    }
+    if digits.len() % 2 != 0 {
+        return Err(format!("Odd number of hex digits in '{}'", hex));
+    }
+    // Only ASCII hex digits are left, so every pair is a valid slice and byte
+    Ok(digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delimited(delimiter: &str) -> Framing {
+        Framing::Delimited {
+            delimiter: delimiter.to_string(),
+        }
+    }
+
+    #[test]
+    fn none_passes_reads_through() {
+        let mut pending = Vec::new();
+        assert_eq!(
+            Framing::None.split(&mut pending, b"a\nb"),
+            vec![b"a\nb".to_vec()]
+        );
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn delimiter_split_across_reads() {
+        let framing = delimited("\r\n");
+        let mut pending = Vec::new();
+        assert!(framing.split(&mut pending, b"ab\r").is_empty());
+        assert_eq!(
+            framing.split(&mut pending, b"\n\r\ncd\r\nef"),
+            vec![b"ab".to_vec(), b"cd".to_vec()]
+        );
+        assert_eq!(pending, b"ef");
+    }
+
+    #[test]
+    fn fixed_frames_keep_the_remainder() {
+        let framing = Framing::Fixed { size: 3 };
+        let mut pending = Vec::new();
+        assert!(framing.split(&mut pending, b"ab").is_empty());
+        assert_eq!(
+            framing.split(&mut pending, b"cdefg"),
+            vec![b"abc".to_vec(), b"def".to_vec()]
+        );
+        assert_eq!(pending, b"g");
+    }
+
+    #[test]
+    fn length_prefix_split_across_reads() {
+        let framing = Framing::LengthPrefixed {
+            width: 2,
+            little_endian: false,
+        };
+        let mut pending = Vec::new();
+        assert!(framing.split(&mut pending, &[0]).is_empty());
+        assert!(framing.split(&mut pending, &[3, b'a']).is_empty());
+        assert_eq!(
+            framing.split(&mut pending, &[b'b', b'c', 0]),
+            vec![b"abc".to_vec()]
+        );
+        assert_eq!(pending, [0]);
+    }
+
+    #[test]
+    fn length_prefix_zero_length_frame() {
+        let framing = Framing::LengthPrefixed {
+            width: 4,
+            little_endian: true,
+        };
+        let mut pending = Vec::new();
+        assert_eq!(
+            framing.split(&mut pending, &[0, 0, 0, 0, 1, 0, 0, 0, b'x']),
+            vec![Vec::new(), b"x".to_vec()]
+        );
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn pending_overflow_is_dropped() {
+        let framing = delimited("\n");
+        let mut pending = Vec::new();
+        assert!(framing
+            .split(&mut pending, &vec![b'a'; MAX_PENDING + 1])
+            .is_empty());
+        assert!(pending.is_empty());
+        assert_eq!(framing.split(&mut pending, b"b\n"), vec![b"b".to_vec()]);
+    }
+
+    #[test]
+    fn parse_hex_separators() {
+        let bytes = vec![0x01, 0x02, 0xff];
+        for hex in [
+            "0102ff", "01 02 FF", "0x0102FF", "01:02:ff", "01,02,ff", "01-02-ff",
+        ] {
+            assert_eq!(parse_hex(hex), Ok(bytes.clone()), "{}", hex);
+        }
+        assert_eq!(parse_hex("0x01 0X02\tff"), Ok(bytes));
+        assert_eq!(parse_hex(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn parse_hex_rejects_invalid_input() {
+        for hex in ["aé0", "é", "+1", "0g", "12 3", "-1"] {
+            assert!(parse_hex(hex).is_err(), "{}", hex);
+        }
+    }
+
+    #[test]
+    fn encode_round_trips_through_split() {
+        let framings = [
+            Framing::None,
+            delimited("\r\n"),
+            Framing::LengthPrefixed {
+                width: 1,
+                little_endian: false,
+            },
+            Framing::LengthPrefixed {
+                width: 2,
+                little_endian: true,
+            },
+            Framing::LengthPrefixed {
+                width: 4,
+                little_endian: false,
+            },
+        ];
+        for framing in framings {
+            let mut pending = Vec::new();
+            let frame = framing.encode(b"payload").unwrap();
+            assert_eq!(
+                framing.split(&mut pending, &frame),
+                vec![b"payload".to_vec()],
+                "{:?}",
+                framing
+            );
+            assert!(pending.is_empty());
+        }
+
+        let fixed = Framing::Fixed { size: 4 };
+        let mut pending = Vec::new();
+        let frame = fixed.encode(b"ab").unwrap();
+        assert_eq!(fixed.split(&mut pending, &frame), vec![b"ab\0\0".to_vec()]);
+        assert!(fixed.encode(b"abcde").is_err());
+        let byte_prefix = Framing::LengthPrefixed {
+            width: 1,
+            little_endian: false,
+        };
+        assert!(byte_prefix.encode(&[0; 256]).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use async_trait::async_trait;
+use prost::Message;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::storage::file_logger::{log_sent_data, save_packet_fast, LogKind};
+use crate::transport::stats::ConnectionStats;
+use crate::transport::{StatableTransport, Transport};
+
+/// Called with the connection id and the bytes of every successful send
+pub type SentHook = Arc<dyn Fn(&str, &[u8]) + Send + Sync>;
+
+/// Wraps a transport with the rx/tx counting, statistics and logging every connection
+/// gets. `as_any` is forwarded, so downcasts still reach the wrapped transport.
+pub struct Instrumented<T> {
+    id: String,
+    inner: T,
+    stats: Arc<ConnectionStats>,
+    received: Arc<AtomicUsize>,
+    sent: AtomicUsize,
+    on_sent: Option<SentHook>,
+}
+
+impl<T: Transport> Instrumented<T> {
+    pub fn new(id: String, inner: T) -> Self {
+        // Share the transport's own stats so its decode errors and overflows land in the same place
+        let stats = inner
+            .stats()
+            .unwrap_or_else(|| Arc::new(ConnectionStats::new()));
+        Self {
+            id,
+            inner,
+            stats,
+            received: Arc::new(AtomicUsize::new(0)),
+            sent: AtomicUsize::new(0),
+            on_sent: None,
+        }
+    }
+
+    pub fn on_sent(mut self, hook: SentHook) -> Self {
+        self.on_sent = Some(hook);
+        self
+    }
+
+    fn record_sent(&self, data: &[u8], len: usize, started: Instant) {
+        self.stats.record_tx(len, started.elapsed());
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        log_sent_data(&self.id, data);
+        if let Some(hook) = &self.on_sent {
+            hook(&self.id, data);
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: StatableTransport> Instrumented<T> {
+    /// Start the wrapped transport; every decoded packet is counted and logged before
+    /// it reaches `on_packet`
+    pub async fn start<F>(
+        &mut self,
+        mut on_packet: impl FnMut(String, F, Option<SocketAddr>) + Send + 'static,
+    ) -> Result<(), String>
+    where
+        F: Message + Default + serde::Serialize + LogKind + Clone + 'static,
+    {
+        let received = self.received.clone();
+        let stats = self.stats.clone();
+        self.inner
+            .start::<F>(
+                self.id.clone(),
+                move |conn_id: String, packet: F, source| {
+                    received.fetch_add(1, Ordering::Relaxed);
+                    stats.record_rx(packet.encoded_len());
+                    save_packet_fast(&conn_id, &packet);
+                    on_packet(conn_id, packet, source);
+                },
+            )
+            .await
+    }
+}
+
+#[async_trait]
+impl<T: Transport + 'static> Transport for Instrumented<T> {
+    async fn send(&self, data: Vec<u8>) -> Result<(), String> {
+        let len = data.len();
+        let started = Instant::now();
+        self.inner.send(data.clone()).await?;
+        self.record_sent(&data, len, started);
+        Ok(())
+    }
+
+    async fn send_to_addr(&self, addr: SocketAddr, data: Vec<u8>) -> Result<(), String> {
+        let len = data.len();
+        let started = Instant::now();
+        self.inner.send_to_addr(addr, data.clone()).await?;
+        self.record_sent(&data, len, started);
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        self.inner.stop().await
+    }
+
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self.inner.as_any()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self.inner.as_any_mut()
+    }
+
+    fn get_packet_received_count(&self) -> usize {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    fn get_packet_sent_count(&self) -> usize {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    fn reset_packet_counters(&self) {
+        self.received.store(0, Ordering::Relaxed);
+        self.sent.store(0, Ordering::Relaxed);
+        self.inner.reset_packet_counters();
+        self.stats.reset();
+    }
+
+    fn stats(&self) -> Option<Arc<ConnectionStats>> {
+        Some(self.stats.clone())
+    }
+
+    fn decoders(&self) -> Option<Arc<crate::transport::decoders::DecoderPipeline>> {
+        self.inner.decoders()
+    }
+}
@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Sender table entries kept; the one heard from least recently makes room for a new one
+const MAX_SENDERS: usize = 4096;
+
+#[derive(Debug, Default)]
+struct PeerCounters {
+    tx_packets: AtomicU64,
+    tx_bytes: AtomicU64,
+    tx_errors: AtomicU64,
+    rx_packets: AtomicU64,
+    rx_bytes: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Peer {
+    addr: SocketAddr,
+    counters: PeerCounters,
+    first_rx: std::sync::Mutex<Option<Instant>>,
+    last_rx: std::sync::Mutex<Option<Instant>>,
+}
+
+/// Per-peer counters, as returned by `list_udp_peers`
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UdpPeerInfo {
+    pub addr: String,
+    pub tx_packets: u64,
+    pub tx_bytes: u64,
+    pub tx_errors: u64,
+    pub rx_packets: u64,
+    pub rx_bytes: u64,
+    /// Milliseconds since this peer first sent us a datagram
+    pub first_rx_ms_ago: Option<u64>,
+    /// Milliseconds since this peer last sent us a datagram
+    pub last_rx_ms_ago: Option<u64>,
+    /// True when the address was learned from received traffic rather than configured
+    pub learned: bool,
+}
+
+/// Destinations of a UDP connection. Sends fan out to every configured peer and, in
+/// reply-to-sender mode, to whoever sent us the last datagram. Source addresses seen on
+/// the socket are kept in a bounded sender table.
+#[derive(Debug, Default)]
+pub struct UdpPeers {
+    peers: RwLock<Vec<Arc<Peer>>>,
+    reply_to_sender: AtomicBool,
+    last_sender: RwLock<Option<Arc<Peer>>>,
+    senders: RwLock<HashMap<SocketAddr, Arc<Peer>>>,
+}
+
+impl Peer {
+    fn new(addr: SocketAddr) -> Arc<Self> {
+        Arc::new(Self {
+            addr,
+            counters: PeerCounters::default(),
+            first_rx: std::sync::Mutex::new(None),
+            last_rx: std::sync::Mutex::new(None),
+        })
+    }
+
+    fn info(&self, learned: bool) -> UdpPeerInfo {
+        let c = &self.counters;
+        UdpPeerInfo {
+            addr: self.addr.to_string(),
+            tx_packets: c.tx_packets.load(Ordering::Relaxed),
+            tx_bytes: c.tx_bytes.load(Ordering::Relaxed),
+            tx_errors: c.tx_errors.load(Ordering::Relaxed),
+            rx_packets: c.rx_packets.load(Ordering::Relaxed),
+            rx_bytes: c.rx_bytes.load(Ordering::Relaxed),
+            first_rx_ms_ago: self
+                .first_rx
+                .lock()
+                .unwrap()
+                .map(|t| t.elapsed().as_millis() as u64),
+            last_rx_ms_ago: self
+                .last_rx
+                .lock()
+                .unwrap()
+                .map(|t| t.elapsed().as_millis() as u64),
+            learned,
+        }
+    }
+}
+
+impl UdpPeers {
+    /// Existing sender entry for `addr`, so a peer keeps the counters it already has
+    fn peer_for(&self, addr: SocketAddr) -> Arc<Peer> {
+        self.senders
+            .read()
+            .unwrap()
+            .get(&addr)
+            .cloned()
+            .unwrap_or_else(|| Peer::new(addr))
+    }
+
+    /// Add a destination; returns false if it was already present
+    pub fn add(&self, addr: SocketAddr) -> bool {
+        let mut peers = self.peers.write().unwrap();
+        if peers.iter().any(|p| p.addr == addr) {
+            return false;
+        }
+        peers.push(self.peer_for(addr));
+        true
+    }
+
+    pub fn remove(&self, addr: SocketAddr) -> bool {
+        let mut peers = self.peers.write().unwrap();
+        let before = peers.len();
+        peers.retain(|p| p.addr != addr);
+        peers.len() != before
+    }
+
+    /// Replace every configured peer with `addr`
+    pub fn set_single(&self, addr: SocketAddr) {
+        let peer = self.peer_for(addr);
+        *self.peers.write().unwrap() = vec![peer];
+    }
+
+    pub fn clear(&self) {
+        self.peers.write().unwrap().clear();
+    }
+
+    pub fn addrs(&self) -> Vec<SocketAddr> {
+        self.peers.read().unwrap().iter().map(|p| p.addr).collect()
+    }
+
+    /// First configured peer, kept for the single `remote_addr` of `ConnectionInfo`
+    pub fn first(&self) -> Option<SocketAddr> {
+        self.peers.read().unwrap().first().map(|p| p.addr)
+    }
+
+    pub fn reply_to_sender(&self) -> bool {
+        self.reply_to_sender.load(Ordering::Relaxed)
+    }
+
+    pub fn set_reply_to_sender(&self, enabled: bool) {
+        self.reply_to_sender.store(enabled, Ordering::Relaxed);
+    }
+
+    fn configured(&self, addr: SocketAddr) -> Option<Arc<Peer>> {
+        self.peers
+            .read()
+            .unwrap()
+            .iter()
+            .find(|p| p.addr == addr)
+            .cloned()
+    }
+
+    /// Record a datagram from `addr` in the sender table and remember it as the last sender
+    pub fn record_rx(&self, addr: SocketAddr, bytes: usize) {
+        let known = self.senders.read().unwrap().get(&addr).cloned();
+        let peer = match known {
+            Some(peer) => peer,
+            None => {
+                // Configured peers share their counters with the sender entry
+                let peer = self.configured(addr).unwrap_or_else(|| Peer::new(addr));
+                let mut senders = self.senders.write().unwrap();
+                if senders.len() >= MAX_SENDERS && !senders.contains_key(&addr) {
+                    let oldest = senders
+                        .values()
+                        .min_by_key(|p| *p.last_rx.lock().unwrap())
+                        .map(|p| p.addr);
+                    if let Some(oldest) = oldest {
+                        senders.remove(&oldest);
+                    }
+                }
+                senders.entry(addr).or_insert(peer).clone()
+            }
+        };
+        peer.counters.rx_packets.fetch_add(1, Ordering::Relaxed);
+        peer.counters
+            .rx_bytes
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        let now = Instant::now();
+        peer.first_rx.lock().unwrap().get_or_insert(now);
+        *peer.last_rx.lock().unwrap() = Some(now);
+        *self.last_sender.write().unwrap() = Some(peer);
+    }
+
+    pub fn is_known_sender(&self, addr: SocketAddr) -> bool {
+        self.senders.read().unwrap().contains_key(&addr)
+    }
+
+    /// Every address that has sent us a datagram, most recent first
+    pub fn list_senders(&self) -> Vec<UdpPeerInfo> {
+        let configured = self.addrs();
+        let senders = self.senders.read().unwrap();
+        let mut list: Vec<(Option<Instant>, UdpPeerInfo)> = senders
+            .values()
+            .map(|p| {
+                let last_rx = *p.last_rx.lock().unwrap();
+                (last_rx, p.info(!configured.contains(&p.addr)))
+            })
+            .collect();
+        list.sort_by_key(|(last_rx, _)| std::cmp::Reverse(*last_rx));
+        list.into_iter().map(|(_, info)| info).collect()
+    }
+
+    /// Forget senders not heard from for `max_idle`
+    pub fn prune_senders(&self, max_idle: Duration) {
+        self.senders.write().unwrap().retain(|_, p| {
+            p.last_rx
+                .lock()
+                .unwrap()
+                .is_some_and(|t| t.elapsed() < max_idle)
+        });
+    }
+
+    pub fn clear_senders(&self) {
+        self.senders.write().unwrap().clear();
+        *self.last_sender.write().unwrap() = None;
+    }
+
+    /// Send `data` to `addr` only, counting it against the matching peer or sender
+    pub async fn send_one<Fut>(
+        &self,
+        addr: SocketAddr,
+        data: &[u8],
+        send: Fut,
+    ) -> Result<(), String>
+    where
+        Fut: std::future::Future<Output = std::io::Result<usize>>,
+    {
+        let peer = self
+            .configured(addr)
+            .or_else(|| self.senders.read().unwrap().get(&addr).cloned());
+        match send.await {
+            Ok(_) => {
+                if let Some(peer) = peer {
+                    peer.counters.tx_packets.fetch_add(1, Ordering::Relaxed);
+                    peer.counters
+                        .tx_bytes
+                        .fetch_add(data.len() as u64, Ordering::Relaxed);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                if let Some(peer) = peer {
+                    peer.counters.tx_errors.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(format!("{}: {}", addr, e))
+            }
+        }
+    }
+
+    /// Everyone a send should reach, without duplicates
+    fn targets(&self) -> Vec<Arc<Peer>> {
+        let mut targets = self.peers.read().unwrap().clone();
+        if self.reply_to_sender() {
+            if let Some(last) = self.last_sender.read().unwrap().clone() {
+                if !targets.iter().any(|p| p.addr == last.addr) {
+                    targets.push(last);
+                }
+            }
+        }
+        targets
+    }
+
+    /// Send `data` to every target with `send_one`, updating per-peer counters.
+    /// Succeeds if at least one target accepted the datagram.
+    pub async fn fan_out<F, Fut>(&self, data: &[u8], send_one: F) -> Result<(), String>
+    where
+        F: Fn(SocketAddr) -> Fut,
+        Fut: std::future::Future<Output = std::io::Result<usize>>,
+    {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return Err("Remote address not set".to_string());
+        }
+        let mut errors = Vec::new();
+        for peer in &targets {
+            match send_one(peer.addr).await {
+                Ok(_) => {
+                    peer.counters.tx_packets.fetch_add(1, Ordering::Relaxed);
+                    peer.counters
+                        .tx_bytes
+                        .fetch_add(data.len() as u64, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    peer.counters.tx_errors.fetch_add(1, Ordering::Relaxed);
+                    errors.push(format!("{}: {}", peer.addr, e));
+                }
+            }
+        }
+        if errors.len() == targets.len() {
+            Err(errors.join("; "))
+        } else {
+            if !errors.is_empty() {
+                tracing::warn!("[udp] Partial send failure: {}", errors.join("; "));
+            }
+            Ok(())
+        }
+    }
+
+    pub fn list(&self) -> Vec<UdpPeerInfo> {
+        let mut list: Vec<UdpPeerInfo> = self
+            .peers
+            .read()
+            .unwrap()
+            .iter()
+            .map(|p| p.info(false))
+            .collect();
+        if self.reply_to_sender() {
+            if let Some(last) = self.last_sender.read().unwrap().as_ref() {
+                if !list.iter().any(|p| p.addr == last.addr.to_string()) {
+                    list.push(last.info(true));
+                }
+            }
+        }
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn idle_senders_are_pruned() {
+        let peers = UdpPeers::default();
+        peers.record_rx(addr(1), 10);
+        std::thread::sleep(Duration::from_millis(30));
+        peers.record_rx(addr(2), 10);
+        peers.prune_senders(Duration::from_millis(20));
+        assert!(!peers.is_known_sender(addr(1)));
+        assert!(peers.is_known_sender(addr(2)));
+    }
+
+    #[test]
+    fn sender_table_is_bounded() {
+        let peers = UdpPeers::default();
+        peers.record_rx(addr(1), 1);
+        std::thread::sleep(Duration::from_millis(5));
+        for port in 2..=MAX_SENDERS as u16 {
+            peers.record_rx(addr(port), 1);
+        }
+        peers.record_rx(addr(u16::MAX), 1);
+        assert_eq!(peers.list_senders().len(), MAX_SENDERS);
+        assert!(!peers.is_known_sender(addr(1)));
+        assert!(peers.is_known_sender(addr(2)));
+        assert!(peers.is_known_sender(addr(u16::MAX)));
+    }
+}
@@ -0,0 +1,312 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Mutex, RwLock};
+
+use crate::packet::{packet::Kind, Packet, TargetPacket};
+use crate::transport::tracks::{now_ms, TrackPoint};
+
+/// How a report from one connection competes with the fused entry from another
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FusionPolicy {
+    /// The report with the greatest target time wins
+    #[default]
+    LatestTime,
+    /// Connections earlier in `priorities` win; ties fall back to the latest time
+    SourcePriority,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PictureConfig {
+    pub policy: FusionPolicy,
+    /// Connection ids from highest to lowest priority, unlisted connections rank last
+    pub priorities: Vec<String>,
+    /// Interval between `targets_updated` events
+    pub rate_ms: u64,
+    /// Another connection takes over a target its owner stopped updating for this long,
+    /// whatever the policy
+    pub takeover_after_ms: u64,
+    /// Targets nobody updated for this long leave the picture
+    pub expire_after_ms: u64,
+}
+
+impl Default for PictureConfig {
+    fn default() -> Self {
+        Self {
+            policy: FusionPolicy::LatestTime,
+            priorities: Vec::new(),
+            rate_ms: 500,
+            takeover_after_ms: 2_000,
+            expire_after_ms: 30_000,
+        }
+    }
+}
+
+/// One target of the fused picture and where its latest fix came from
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FusedTarget {
+    #[serde(flatten)]
+    pub packet: TargetPacket,
+    pub connection_id: String,
+    /// Sender address, for connections that have one
+    pub source: Option<String>,
+    pub updated_ms: u64,
+}
+
+/// Emitted as `targets_updated` with the changes since the previous event
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TargetsDelta {
+    pub updated: Vec<FusedTarget>,
+    pub removed: Vec<u32>,
+}
+
+impl TargetsDelta {
+    pub fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Targets merged from every connection, one entry per target id
+#[derive(Debug, Default)]
+pub struct TargetPicture {
+    targets: RwLock<HashMap<u32, FusedTarget>>,
+    config: RwLock<PictureConfig>,
+    changed: Mutex<HashSet<u32>>,
+    removed: Mutex<HashSet<u32>>,
+    /// Last two fixes of each target per connection, oldest first, whatever the transport
+    fixes: Mutex<HashMap<(String, u32), Vec<TrackPoint>>>,
+}
+
+impl TargetPicture {
+    pub fn config(&self) -> PictureConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    pub fn set_config(&self, config: PictureConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// Offer every target in a received packet
+    pub fn offer_packet(&self, connection_id: &str, source: Option<SocketAddr>, packet: &Packet) {
+        match &packet.kind {
+            Some(Kind::TargetPacket(tp)) => {
+                self.offer(connection_id, source, tp);
+            }
+            Some(Kind::TargetPacketList(tpl)) => {
+                for tp in &tpl.packets {
+                    self.offer(connection_id, source, tp);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Merge one report; returns true if it replaced the fused entry
+    pub fn offer(
+        &self,
+        connection_id: &str,
+        source: Option<SocketAddr>,
+        tp: &TargetPacket,
+    ) -> bool {
+        let config = self.config.read().unwrap();
+        let now = now_ms();
+        self.record_fix(connection_id, tp, now);
+        let mut targets = self.targets.write().unwrap();
+        let wins = match targets.get(&tp.target_id) {
+            None => true,
+            // The owning connection always refreshes its own entry
+            Some(current) if current.connection_id == connection_id => true,
+            Some(current) if now.saturating_sub(current.updated_ms) >= config.takeover_after_ms => {
+                true
+            }
+            Some(current) => match config.policy {
+                FusionPolicy::LatestTime => tp.time > current.packet.time,
+                FusionPolicy::SourcePriority => {
+                    let rank = |id: &str| {
+                        config
+                            .priorities
+                            .iter()
+                            .position(|p| p == id)
+                            .unwrap_or(usize::MAX)
+                    };
+                    let (new_rank, current_rank) =
+                        (rank(connection_id), rank(&current.connection_id));
+                    new_rank < current_rank
+                        || (new_rank == current_rank && tp.time > current.packet.time)
+                }
+            },
+        };
+        if wins {
+            targets.insert(
+                tp.target_id,
+                FusedTarget {
+                    packet: *tp,
+                    connection_id: connection_id.to_string(),
+                    source: source.map(|addr| addr.to_string()),
+                    updated_ms: now,
+                },
+            );
+            self.changed.lock().unwrap().insert(tp.target_id);
+            self.removed.lock().unwrap().remove(&tp.target_id);
+        }
+        wins
+    }
+
+    fn record_fix(&self, connection_id: &str, tp: &TargetPacket, now: u64) {
+        let fix = TrackPoint {
+            lat: tp.lat,
+            lon: tp.lon,
+            alt: tp.alt,
+            time: tp.time,
+            received_ms: now,
+        };
+        let mut fixes = self.fixes.lock().unwrap();
+        let key = (connection_id.to_string(), tp.target_id);
+        let kept = fixes.entry(key).or_default();
+        if kept.len() == 2 {
+            kept.remove(0);
+        }
+        kept.push(fix);
+    }
+
+    /// The last two fixes of a target from one connection, oldest first
+    pub fn last_fixes(
+        &self,
+        connection_id: &str,
+        target_id: u32,
+    ) -> Option<(TrackPoint, TrackPoint)> {
+        let fixes = self.fixes.lock().unwrap();
+        match fixes.get(&(connection_id.to_string(), target_id))?[..] {
+            [from, to] => Some((from, to)),
+            _ => None,
+        }
+    }
+
+    /// Drop every target last reported by `connection_id`, e.g. when it stops
+    pub fn remove_connection(&self, connection_id: &str) {
+        let mut targets = self.targets.write().unwrap();
+        let gone: Vec<u32> = targets
+            .iter()
+            .filter(|(_, t)| t.connection_id == connection_id)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in gone {
+            targets.remove(&id);
+            self.mark_removed(id);
+        }
+        self.fixes
+            .lock()
+            .unwrap()
+            .retain(|(id, _), _| id != connection_id);
+    }
+
+    pub fn clear(&self) {
+        let ids: Vec<u32> = self
+            .targets
+            .write()
+            .unwrap()
+            .drain()
+            .map(|(id, _)| id)
+            .collect();
+        for id in ids {
+            self.mark_removed(id);
+        }
+        self.fixes.lock().unwrap().clear();
+    }
+
+    fn mark_removed(&self, id: u32) {
+        self.changed.lock().unwrap().remove(&id);
+        self.removed.lock().unwrap().insert(id);
+    }
+
+    /// The fused picture, ordered by target id
+    pub fn list(&self) -> Vec<FusedTarget> {
+        let mut list: Vec<FusedTarget> = self.targets.read().unwrap().values().cloned().collect();
+        list.sort_by_key(|t| t.packet.target_id);
+        list
+    }
+
+    pub fn get(&self, target_id: u32) -> Option<FusedTarget> {
+        self.targets.read().unwrap().get(&target_id).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Expire old targets and return everything that changed since the last call
+    pub fn take_delta(&self) -> TargetsDelta {
+        let expire_after = self.config.read().unwrap().expire_after_ms;
+        let now = now_ms();
+        {
+            let mut targets = self.targets.write().unwrap();
+            let expired: Vec<u32> = targets
+                .iter()
+                .filter(|(_, t)| now.saturating_sub(t.updated_ms) >= expire_after)
+                .map(|(id, _)| *id)
+                .collect();
+            for id in expired {
+                targets.remove(&id);
+                self.mark_removed(id);
+            }
+        }
+        self.fixes.lock().unwrap().retain(|_, kept| {
+            kept.last()
+                .is_some_and(|fix| now.saturating_sub(fix.received_ms) < expire_after)
+        });
+        let changed: Vec<u32> = self.changed.lock().unwrap().drain().collect();
+        let targets = self.targets.read().unwrap();
+        let mut updated: Vec<FusedTarget> = changed
+            .iter()
+            .filter_map(|id| targets.get(id).cloned())
+            .collect();
+        updated.sort_by_key(|t| t.packet.target_id);
+        let mut removed: Vec<u32> = self.removed.lock().unwrap().drain().collect();
+        removed.sort_unstable();
+        TargetsDelta { updated, removed }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(target_id: u32, lat: f64, time: f64) -> TargetPacket {
+        TargetPacket {
+            target_id,
+            lat,
+            lon: 0.0,
+            alt: 100.0,
+            time,
+        }
+    }
+
+    #[test]
+    fn last_fixes_are_kept_per_connection() {
+        let picture = TargetPicture::default();
+        picture.offer("serial_1", None, &target(7, 1.0, 1.0));
+        assert!(picture.last_fixes("serial_1", 7).is_none());
+        picture.offer("serial_1", None, &target(7, 2.0, 2.0));
+        // A losing report from another connection is kept apart
+        picture.offer("zmq_1", None, &target(7, 9.0, 0.5));
+        picture.offer("serial_1", None, &target(7, 3.0, 3.0));
+
+        let (from, to) = picture.last_fixes("serial_1", 7).unwrap();
+        assert_eq!((from.lat, to.lat), (2.0, 3.0));
+        assert_eq!((from.time, to.time), (2.0, 3.0));
+        assert!(picture.last_fixes("zmq_1", 7).is_none());
+
+        picture.offer("zmq_1", None, &target(7, 9.5, 1.5));
+        picture.remove_connection("serial_1");
+        assert!(picture.last_fixes("serial_1", 7).is_none());
+        assert!(picture.last_fixes("zmq_1", 7).is_some());
+        picture.clear();
+        assert!(picture.last_fixes("zmq_1", 7).is_none());
+    }
+}
@@ -0,0 +1,343 @@
+use prost::Message;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::runtime::Handle;
+
+use crate::packet::Packet;
+use crate::transport::tracks::now_ms;
+use crate::transport::Transport;
+
+type Connections = Arc<RwLock<HashMap<String, Arc<dyn Transport + Send + Sync>>>>;
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PredicateOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Substring of a string field, or element of a list field
+    Contains,
+    Exists,
+}
+
+/// A test on one field of the received packet, e.g. `id gt 100`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FieldPredicate {
+    /// Dot-separated path inside the packet kind, e.g. `id` or `position.lat`
+    pub field: String,
+    pub op: PredicateOp,
+    #[serde(default)]
+    pub value: Value,
+}
+
+impl FieldPredicate {
+    pub(crate) fn matches(&self, body: &Value) -> bool {
+        let Some(actual) = lookup(body, &self.field) else {
+            return false;
+        };
+        let ordering = match (actual.as_f64(), self.value.as_f64()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        };
+        match self.op {
+            PredicateOp::Eq => ordering.map(|o| o.is_eq()).unwrap_or(*actual == self.value),
+            PredicateOp::Ne => !ordering.map(|o| o.is_eq()).unwrap_or(*actual == self.value),
+            PredicateOp::Lt => matches!(ordering, Some(o) if o.is_lt()),
+            PredicateOp::Le => matches!(ordering, Some(o) if o.is_le()),
+            PredicateOp::Gt => matches!(ordering, Some(o) if o.is_gt()),
+            PredicateOp::Ge => matches!(ordering, Some(o) if o.is_ge()),
+            PredicateOp::Contains => match (actual, &self.value) {
+                (Value::String(a), Value::String(b)) => a.contains(b.as_str()),
+                (Value::Array(items), value) => items.contains(value),
+                _ => false,
+            },
+            PredicateOp::Exists => true,
+        }
+    }
+}
+
+/// Reply with a packet when a received packet matches
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ResponderRule {
+    #[serde(default)]
+    pub name: String,
+    /// Packet kind to match, e.g. `Header` or `TargetPacket`; any kind when unset
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// All must hold for the rule to match
+    #[serde(default)]
+    pub when: Vec<FieldPredicate>,
+    /// JSON packet to send, e.g. `{"kind": {"Header": {"id": "{{id+1000}}"}}}`.
+    /// `{{field}}`, `{{field+N}}` and `{{field-N}}` are filled from the received packet,
+    /// `{{seq}}` counts this rule's replies, `{{now_ms}}`/`{{now_s}}` give the time and
+    /// `{{rand A B}}` a random number in that range, an integer when both bounds are.
+    /// Fields left out are copied from the received packet when the kinds match.
+    pub reply: Value,
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Send to the address the packet came from instead of the connection's destination
+    #[serde(default = "default_true")]
+    pub reply_to_source: bool,
+}
+
+impl ResponderRule {
+    pub fn validate(&self) -> Result<(), String> {
+        let reply_kind = self
+            .reply
+            .get("kind")
+            .and_then(Value::as_object)
+            .filter(|kind| kind.len() == 1);
+        if reply_kind.is_none() {
+            return Err(format!(
+                "Rule '{}': reply must look like {{\"kind\": {{\"<Kind>\": {{...}}}}}}",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    fn matches(&self, kind: &str, body: &Value) -> bool {
+        let kind_matches = match &self.kind {
+            Some(wanted) => wanted.eq_ignore_ascii_case(kind),
+            None => true,
+        };
+        kind_matches && self.when.iter().all(|p| p.matches(body))
+    }
+
+    /// The reply to a packet of `kind` with fields `body`, as number `seq`
+    fn render(&self, kind: &str, body: &Value, seq: u64) -> Result<Packet, String> {
+        render_packet(&self.reply, body, seq, Some((kind, body)))
+    }
+}
+
+/// Render a `Packet` JSON template against `body`. Fields the reply leaves out are copied
+/// from `received`, a packet kind and its fields, when the reply has the same kind.
+pub(crate) fn render_packet(
+    template: &Value,
+    body: &Value,
+    seq: u64,
+    received: Option<(&str, &Value)>,
+) -> Result<Packet, String> {
+    let mut reply = render(template, body, seq);
+    if let (Some(Value::Object(reply_kind)), Some((kind, Value::Object(received)))) =
+        (reply.get_mut("kind"), received)
+    {
+        if let Some(Value::Object(fields)) = reply_kind.get_mut(kind) {
+            for (name, value) in received {
+                fields.entry(name.clone()).or_insert_with(|| value.clone());
+            }
+        }
+    }
+    serde_json::from_value(reply).map_err(|e| format!("Invalid reply packet: {}", e))
+}
+
+fn lookup<'a>(body: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(body, |value, key| value.get(key))
+}
+
+/// Random number between the bounds of a `rand A B` expression
+fn random(bounds: &str) -> Option<Value> {
+    let mut bounds = bounds.split_whitespace();
+    let (low, high) = (bounds.next()?, bounds.next()?);
+    if let (Ok(low), Ok(high)) = (low.parse::<i64>(), high.parse::<i64>()) {
+        return (low <= high).then(|| Value::from(rand::thread_rng().gen_range(low..=high)));
+    }
+    let (low, high): (f64, f64) = (low.parse().ok()?, high.parse().ok()?);
+    (low < high).then(|| Value::from(rand::thread_rng().gen_range(low..high)))
+}
+
+/// Value of a `{{...}}` expression
+fn resolve(expr: &str, body: &Value, seq: u64) -> Option<Value> {
+    let expr = expr.trim();
+    if let Some(bounds) = expr.strip_prefix("rand ") {
+        return random(bounds);
+    }
+    let (path, offset) = match expr.rfind(['+', '-']) {
+        Some(at) if at > 0 => {
+            let offset: f64 = expr[at..].replace(' ', "").parse().ok()?;
+            (expr[..at].trim(), offset)
+        }
+        _ => (expr, 0.0),
+    };
+    let base = match path {
+        "seq" => Value::from(seq),
+        "now_ms" => Value::from(now_ms()),
+        "now_s" => Value::from(now_ms() / 1000),
+        _ => lookup(body, path)?.clone(),
+    };
+    if offset == 0.0 {
+        return Some(base);
+    }
+    if let (Some(base), true) = (base.as_i64(), offset.fract() == 0.0) {
+        return Some(Value::from(base + offset as i64));
+    }
+    base.as_f64().map(|base| Value::from(base + offset))
+}
+
+/// Fill the `{{...}}` placeholders of `template` from `body`
+pub(crate) fn render(template: &Value, body: &Value, seq: u64) -> Value {
+    match template {
+        Value::String(text) => {
+            if let Some(expr) = text
+                .strip_prefix("{{")
+                .and_then(|rest| rest.strip_suffix("}}"))
+                .filter(|expr| !expr.contains("{{"))
+            {
+                return resolve(expr, body, seq).unwrap_or(Value::Null);
+            }
+            let mut out = String::new();
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("{{") {
+                let Some(end) = rest[start..].find("}}") else {
+                    break;
+                };
+                out.push_str(&rest[..start]);
+                match resolve(&rest[start + 2..start + end], body, seq) {
+                    Some(Value::String(s)) => out.push_str(&s),
+                    Some(value) => out.push_str(&value.to_string()),
+                    None => {}
+                }
+                rest = &rest[start + end + 2..];
+            }
+            out.push_str(rest);
+            Value::String(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|v| render(v, body, seq)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), render(v, body, seq)))
+                .collect::<Map<_, _>>(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Rules of one connection and the runtime their replies are sent from
+struct ConnectionRules {
+    rules: Vec<ResponderRule>,
+    replies: Vec<Arc<AtomicU64>>,
+    runtime: Handle,
+}
+
+/// Auto-responder rules of every connection. Connections offer each received packet;
+/// the first matching rule replies through the same connection.
+pub struct ResponderRegistry {
+    connections: Connections,
+    rules: RwLock<HashMap<String, ConnectionRules>>,
+}
+
+impl ResponderRegistry {
+    pub fn new(connections: Connections) -> Self {
+        Self {
+            connections,
+            rules: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Replace the rules of a connection; an empty list disables responding. Must be
+    /// called from within the Tokio runtime replies are sent from.
+    pub fn set_rules(&self, connection_id: &str, rules: Vec<ResponderRule>) -> Result<(), String> {
+        for rule in &rules {
+            rule.validate()?;
+        }
+        if rules.is_empty() {
+            self.rules.write().unwrap().remove(connection_id);
+            return Ok(());
+        }
+        let runtime = Handle::try_current().map_err(|e| e.to_string())?;
+        let replies = rules.iter().map(|_| Arc::new(AtomicU64::new(0))).collect();
+        self.rules.write().unwrap().insert(
+            connection_id.to_string(),
+            ConnectionRules {
+                rules,
+                replies,
+                runtime,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn rules(&self, connection_id: &str) -> Vec<ResponderRule> {
+        self.rules
+            .read()
+            .unwrap()
+            .get(connection_id)
+            .map(|c| c.rules.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn remove_connection(&self, connection_id: &str) {
+        self.rules.write().unwrap().remove(connection_id);
+    }
+
+    pub fn clear(&self) {
+        self.rules.write().unwrap().clear();
+    }
+
+    /// Check a received packet against the connection's rules and schedule the reply of
+    /// the first one that matches
+    pub fn offer_packet(&self, connection_id: &str, source: Option<SocketAddr>, packet: &Packet) {
+        let rules = self.rules.read().unwrap();
+        let Some(conn_rules) = rules.get(connection_id) else {
+            return;
+        };
+        let Ok(Value::Object(mut json)) = serde_json::to_value(packet) else {
+            return;
+        };
+        let Some(Value::Object(kind)) = json.remove("kind") else {
+            return;
+        };
+        let Some((kind, body)) = kind.into_iter().next() else {
+            return;
+        };
+        let Some((index, rule)) = conn_rules
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matches(&kind, &body))
+        else {
+            return;
+        };
+        let seq = conn_rules.replies[index].fetch_add(1, Ordering::Relaxed);
+        let reply = match rule.render(&kind, &body, seq) {
+            Ok(reply) => reply.encode_to_vec(),
+            Err(e) => {
+                tracing::warn!("[responder] {} rule '{}': {}", connection_id, rule.name, e);
+                return;
+            }
+        };
+        let connections = self.connections.clone();
+        let connection_id = connection_id.to_string();
+        let delay = Duration::from_millis(rule.delay_ms);
+        let target = source.filter(|_| rule.reply_to_source);
+        let name = rule.name.clone();
+        conn_rules.runtime.spawn(async move {
+            tokio::time::sleep(delay).await;
+            let conn = connections.read().unwrap().get(&connection_id).cloned();
+            let Some(conn) = conn else {
+                return;
+            };
+            let result = match target {
+                Some(addr) => conn.send_to_addr(addr, reply).await,
+                None => conn.send(reply).await,
+            };
+            if let Err(e) = result {
+                tracing::warn!("[responder] {} rule '{}': {}", connection_id, name, e);
+            }
+        });
+    }
+}
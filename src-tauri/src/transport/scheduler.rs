@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::packet::{packet::Kind, Packet, PacketTimestamp};
+
+/// When a scheduled packet is sent
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScheduleTiming {
+    /// Every `interval_ms`, stopping after `count` packets when set
+    Periodic {
+        interval_ms: u64,
+        #[serde(default)]
+        count: Option<u64>,
+    },
+    /// `count` packets, `spacing_ms` apart
+    Burst {
+        count: u64,
+        #[serde(default)]
+        spacing_ms: u64,
+    },
+    /// Once, at a wall-clock time
+    At { at: DateTime<Utc> },
+}
+
+/// A packet to send to a connection on a schedule
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScheduleConfig {
+    pub connection_id: String,
+    pub packet: Packet,
+    pub timing: ScheduleTiming,
+    /// For header packets, add the number of packets already sent to `PacketHeader.id`
+    #[serde(default)]
+    pub increment_header_id: bool,
+    /// For timestamp packets, send the current time instead of the template's
+    #[serde(default)]
+    pub stamp_timestamp: bool,
+}
+
+impl ScheduleConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.timing {
+            ScheduleTiming::Periodic { interval_ms: 0, .. } => {
+                Err("Schedule interval must be greater than 0".to_string())
+            }
+            ScheduleTiming::Periodic { count: Some(0), .. }
+            | ScheduleTiming::Burst { count: 0, .. } => {
+                Err("Schedule count must be greater than 0".to_string())
+            }
+            ScheduleTiming::At { at } if *at < Utc::now() => {
+                Err(format!("Schedule time {} is in the past", at.to_rfc3339()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The packet to send as number `seq`, counting from 0
+    pub fn render(&self, seq: u64) -> Packet {
+        let mut packet = self.packet.clone();
+        match &mut packet.kind {
+            Some(Kind::Header(header)) if self.increment_header_id => {
+                header.id = header.id.wrapping_add(seq as u32);
+            }
+            Some(Kind::Timestamp(timestamp)) if self.stamp_timestamp => {
+                let now = Utc::now();
+                *timestamp = PacketTimestamp {
+                    seconds: now.timestamp() as u32,
+                    nanoseconds: now.timestamp_subsec_nanos(),
+                };
+            }
+            _ => {}
+        }
+        packet
+    }
+}
+
+/// A schedule as returned by `list_schedules`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScheduleInfo {
+    pub id: String,
+    #[serde(flatten)]
+    pub config: ScheduleConfig,
+    pub sent: u64,
+    pub errors: u64,
+    pub last_sent_ms: Option<u64>,
+    /// False once every packet has been sent
+    pub running: bool,
+}
+
+/// Task sending one schedule, keyed by schedule id in `Manager::schedules`
+#[derive(Debug)]
+pub struct Schedule {
+    pub config: ScheduleConfig,
+    pub sent: Arc<AtomicU64>,
+    pub errors: Arc<AtomicU64>,
+    pub last_sent_ms: Arc<AtomicU64>,
+    pub handle: tokio::task::JoinHandle<()>,
+}
+
+impl Schedule {
+    pub fn info(&self, id: &str) -> ScheduleInfo {
+        let last = self.last_sent_ms.load(Ordering::Relaxed);
+        ScheduleInfo {
+            id: id.to_string(),
+            config: self.config.clone(),
+            sent: self.sent.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            last_sent_ms: (last > 0).then_some(last),
+            running: !self.handle.is_finished(),
+        }
+    }
+}
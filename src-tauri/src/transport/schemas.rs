@@ -0,0 +1,164 @@
+use once_cell::sync::Lazy;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor};
+use prost_types::FileDescriptorSet;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Protobuf schemas loaded at runtime, shared by every connection's decoders
+pub static SCHEMAS: Lazy<SchemaRegistry> = Lazy::new(SchemaRegistry::default);
+
+/// A `.proto` file, or any other file holding an encoded `FileDescriptorSet`
+/// (e.g. `protoc --descriptor_set_out`)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SchemaSource {
+    pub path: String,
+    /// Import paths for a `.proto` file; its own directory is always searched
+    #[serde(default)]
+    pub include_dirs: Vec<String>,
+}
+
+impl SchemaSource {
+    fn is_proto(&self) -> bool {
+        Path::new(&self.path)
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("proto"))
+            .unwrap_or(false)
+    }
+
+    fn read(&self) -> Result<FileDescriptorSet, String> {
+        if self.is_proto() {
+            let path = Path::new(&self.path);
+            let mut includes: Vec<PathBuf> =
+                path.parent().map(Path::to_path_buf).into_iter().collect();
+            includes.extend(self.include_dirs.iter().map(PathBuf::from));
+            return protox::compile([path], includes)
+                .map_err(|e| format!("Failed to compile {}: {}", self.path, e));
+        }
+        let bytes = std::fs::read(&self.path)
+            .map_err(|e| format!("Failed to read {}: {}", self.path, e))?;
+        FileDescriptorSet::decode(bytes.as_slice())
+            .map_err(|e| format!("{} is not a FileDescriptorSet: {}", self.path, e))
+    }
+}
+
+/// Descriptor pool built from every loaded source. Sources are re-read whenever the set
+/// changes, so loading a changed file again replaces its old definitions.
+#[derive(Default)]
+pub struct SchemaRegistry {
+    pool: RwLock<DescriptorPool>,
+    sources: RwLock<Vec<SchemaSource>>,
+}
+
+fn build_pool(sources: &[SchemaSource]) -> Result<DescriptorPool, String> {
+    let mut pool = DescriptorPool::new();
+    for source in sources {
+        pool.add_file_descriptor_set(source.read()?)
+            .map_err(|e| format!("Invalid schema {}: {}", source.path, e))?;
+    }
+    Ok(pool)
+}
+
+/// `message` and its nested messages, without the generated map entry types
+fn message_names(message: MessageDescriptor, names: &mut Vec<String>) {
+    if message.is_map_entry() {
+        return;
+    }
+    names.push(message.full_name().to_string());
+    for child in message.child_messages() {
+        message_names(child, names);
+    }
+}
+
+impl SchemaRegistry {
+    /// Load or reload `source`, returning the full names of the messages it defines
+    pub fn load(&self, source: SchemaSource) -> Result<Vec<String>, String> {
+        let files = source.read()?;
+        let mut sources: Vec<SchemaSource> = self
+            .sources
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|s| s.path != source.path)
+            .cloned()
+            .collect();
+        let mut pool = build_pool(&sources)?;
+        pool.add_file_descriptor_set(files.clone())
+            .map_err(|e| format!("Invalid schema {}: {}", source.path, e))?;
+        sources.push(source);
+
+        let mut names = Vec::new();
+        for file in files
+            .file
+            .iter()
+            .filter_map(|f| pool.get_file_by_name(f.name()))
+        {
+            for message in file.messages() {
+                message_names(message, &mut names);
+            }
+        }
+        *self.pool.write().unwrap() = pool;
+        *self.sources.write().unwrap() = sources;
+        Ok(names)
+    }
+
+    pub fn unload(&self, path: &str) -> Result<(), String> {
+        let sources: Vec<SchemaSource> = self
+            .sources
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|s| s.path != path)
+            .cloned()
+            .collect();
+        if sources.len() == self.sources.read().unwrap().len() {
+            return Err(format!("Schema {} is not loaded", path));
+        }
+        *self.pool.write().unwrap() = build_pool(&sources)?;
+        *self.sources.write().unwrap() = sources;
+        Ok(())
+    }
+
+    pub fn sources(&self) -> Vec<SchemaSource> {
+        self.sources.read().unwrap().clone()
+    }
+
+    /// Full names of every loaded message type, sorted
+    pub fn message_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .pool
+            .read()
+            .unwrap()
+            .all_messages()
+            .filter(|m| !m.is_map_entry())
+            .map(|m| m.full_name().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn message(&self, name: &str) -> Option<MessageDescriptor> {
+        self.pool.read().unwrap().get_message_by_name(name)
+    }
+
+    fn descriptor(&self, name: &str) -> Result<MessageDescriptor, String> {
+        self.message(name)
+            .ok_or_else(|| format!("Message type {} is not loaded", name))
+    }
+
+    /// Decode `data` as message `name` into its protobuf JSON mapping
+    pub fn decode_json(&self, name: &str, data: &[u8]) -> Result<Value, String> {
+        let message = DynamicMessage::decode(self.descriptor(name)?, data)
+            .map_err(|e| format!("Failed to decode {}: {}", name, e))?;
+        serde_json::to_value(&message).map_err(|e| e.to_string())
+    }
+
+    /// Encode the protobuf JSON mapping `value` of message `name`
+    pub fn encode_json(&self, name: &str, value: &Value) -> Result<Vec<u8>, String> {
+        let message = DynamicMessage::deserialize(self.descriptor(name)?, value)
+            .map_err(|e| format!("Invalid {}: {}", name, e))?;
+        Ok(message.encode_to_vec())
+    }
+}
@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{broadcast, oneshot};
+
+use crate::transport::tracks::now_ms;
+
+/// One `tid,sensor_id,lat,lon,alt,temp` line from a UDP sensor client
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct SensorReading {
+    /// Target the client is mapped to, 0 when unmapped
+    pub target_id: u32,
+    pub sensor_id: u32,
+    pub lat: f64,
+    pub lon: f64,
+    pub alt: f64,
+    pub temp: f64,
+}
+
+impl SensorReading {
+    /// Parse a datagram of the sensor text protocol; anything else is `None`
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(data).ok()?;
+        let mut fields = text.trim().split(',').map(str::trim);
+        let reading = SensorReading {
+            target_id: fields.next()?.parse().ok()?,
+            sensor_id: fields.next()?.parse().ok()?,
+            lat: fields.next()?.parse().ok()?,
+            lon: fields.next()?.parse().ok()?,
+            alt: fields.next()?.parse().ok()?,
+            temp: fields.next()?.parse().ok()?,
+        };
+        fields.next().is_none().then_some(reading)
+    }
+}
+
+/// A sensor client discovered on a UDP connection, as returned by `get_udp_sensor_clients`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SensorClient {
+    pub connection_id: String,
+    pub sensor_id: u32,
+    /// Address the client last sent from; mapping and commands go there
+    pub addr: String,
+    /// Target the client reported in its last reading, 0 when unmapped
+    pub target_id: u32,
+    /// Target we last asked the client to map to, until a reading confirms it
+    pub requested_target_id: Option<u32>,
+    pub lat: f64,
+    pub lon: f64,
+    pub alt: f64,
+    pub temp: f64,
+    pub readings: u64,
+    pub first_seen_ms: u64,
+    pub last_seen_ms: u64,
+    /// Last text reply to `send_sensor_command`
+    pub last_response: Option<String>,
+}
+
+/// Emitted as `target_sensor_data` for every reading
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SensorReadingEvent {
+    pub connection_id: String,
+    pub source: String,
+    #[serde(flatten)]
+    pub reading: SensorReading,
+    pub received_ms: u64,
+}
+
+/// One sensor forwarded to one connection as `TargetPacket`s
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SensorStreamConfig {
+    pub sensor_id: u32,
+    /// Destination connection, serial or UDP
+    #[serde(alias = "serial_connection_id")]
+    pub connection_id: String,
+    /// UDP connection the sensor reports on; found from the sensor id when unset
+    #[serde(default)]
+    pub udp_connection_id: Option<String>,
+    /// Target id of the forwarded packets. Defaults to the target the sensor is mapped
+    /// to, or the sensor id while it is unmapped.
+    #[serde(default)]
+    pub target_id: Option<u32>,
+    /// Minimum time between forwarded packets; 0 forwards every reading
+    #[serde(default)]
+    pub interval_ms: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SensorStreamRequest {
+    pub stream_configs: Vec<SensorStreamConfig>,
+}
+
+/// A running sensor stream, as returned by `list_sensor_streams`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SensorStreamInfo {
+    #[serde(flatten)]
+    pub config: SensorStreamConfig,
+    pub forwarded: u64,
+    pub last_forward_ms: Option<u64>,
+}
+
+/// Task forwarding one sensor to one connection, keyed by `(sensor_id, connection_id)`
+/// in `Manager::sensor_streams`
+#[derive(Debug)]
+pub struct SensorStream {
+    pub config: SensorStreamConfig,
+    pub forwarded: Arc<AtomicU64>,
+    pub last_forward_ms: Arc<AtomicU64>,
+    pub handle: tokio::task::JoinHandle<()>,
+}
+
+impl SensorStream {
+    pub fn info(&self) -> SensorStreamInfo {
+        let last = self.last_forward_ms.load(Ordering::Relaxed);
+        SensorStreamInfo {
+            config: self.config.clone(),
+            forwarded: self.forwarded.load(Ordering::Relaxed),
+            last_forward_ms: (last > 0).then_some(last),
+        }
+    }
+}
+
+/// Called with the sender address and every parsed reading
+pub type ReadingHook = Arc<dyn Fn(SocketAddr, &SensorReading) + Send + Sync>;
+
+#[derive(Debug, Clone)]
+struct Sensor {
+    addr: SocketAddr,
+    reading: SensorReading,
+    requested_target_id: Option<u32>,
+    readings: u64,
+    first_seen_ms: u64,
+    last_seen_ms: u64,
+    last_response: Option<String>,
+}
+
+/// UDP sensor clients seen on one connection, keyed by sensor id. Sensor datagrams are
+/// text, so the connection hands every datagram here before trying to decode a packet.
+pub struct SensorRegistry {
+    sensors: RwLock<HashMap<u32, Sensor>>,
+    /// Commands waiting for the client's text reply, by client address
+    pending: Mutex<HashMap<SocketAddr, oneshot::Sender<String>>>,
+    on_reading: RwLock<Option<ReadingHook>>,
+    /// Every reading, for sensor streams
+    feed: broadcast::Sender<SensorReading>,
+}
+
+impl Default for SensorRegistry {
+    fn default() -> Self {
+        Self {
+            sensors: RwLock::default(),
+            pending: Mutex::default(),
+            on_reading: RwLock::default(),
+            feed: broadcast::channel(256).0,
+        }
+    }
+}
+
+impl std::fmt::Debug for SensorRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SensorRegistry")
+            .field("sensors", &self.sensors)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SensorRegistry {
+    pub fn set_on_reading(&self, hook: ReadingHook) {
+        *self.on_reading.write().unwrap() = Some(hook);
+    }
+
+    /// Record a reading or a command reply from `addr`. Returns true if the datagram
+    /// belonged to the sensor protocol and should not be decoded as a packet.
+    pub fn observe(&self, addr: SocketAddr, data: &[u8]) -> bool {
+        if let Some(reading) = SensorReading::parse(data) {
+            self.record(addr, reading);
+            if let Some(hook) = self.on_reading.read().unwrap().as_ref() {
+                hook(addr, &reading);
+            }
+            // No receivers just means no stream is running
+            let _ = self.feed.send(reading);
+            return true;
+        }
+        let Some(reply) = self.pending.lock().unwrap().remove(&addr) else {
+            return false;
+        };
+        let text = String::from_utf8_lossy(data).trim().to_string();
+        if let Some(sensor) = self
+            .sensors
+            .write()
+            .unwrap()
+            .values_mut()
+            .find(|s| s.addr == addr)
+        {
+            sensor.last_response = Some(text.clone());
+        }
+        let _ = reply.send(text);
+        true
+    }
+
+    fn record(&self, addr: SocketAddr, reading: SensorReading) {
+        let now = now_ms();
+        let mut sensors = self.sensors.write().unwrap();
+        let sensor = sensors.entry(reading.sensor_id).or_insert_with(|| Sensor {
+            addr,
+            reading,
+            requested_target_id: None,
+            readings: 0,
+            first_seen_ms: now,
+            last_seen_ms: now,
+            last_response: None,
+        });
+        sensor.addr = addr;
+        sensor.reading = reading;
+        sensor.readings += 1;
+        sensor.last_seen_ms = now;
+        if sensor.requested_target_id == Some(reading.target_id) {
+            sensor.requested_target_id = None;
+        }
+    }
+
+    pub fn addr_of(&self, sensor_id: u32) -> Option<SocketAddr> {
+        self.sensors.read().unwrap().get(&sensor_id).map(|s| s.addr)
+    }
+
+    /// Readings received from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<SensorReading> {
+        self.feed.subscribe()
+    }
+
+    /// Remember which target a sensor was asked to map to; 0 for unmap
+    pub fn set_requested_target(&self, sensor_id: u32, target_id: u32) {
+        if let Some(sensor) = self.sensors.write().unwrap().get_mut(&sensor_id) {
+            // An unmapped client stops sending, so no reading will confirm the unmap
+            if target_id == 0 {
+                sensor.reading.target_id = 0;
+                sensor.requested_target_id = None;
+            } else {
+                sensor.requested_target_id = Some(target_id);
+            }
+        }
+    }
+
+    /// Register interest in the next text reply from `addr`
+    pub fn expect_reply(&self, addr: SocketAddr) -> oneshot::Receiver<String> {
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(addr, tx);
+        rx
+    }
+
+    pub fn cancel_reply(&self, addr: SocketAddr) {
+        self.pending.lock().unwrap().remove(&addr);
+    }
+
+    /// Every known sensor, ordered by sensor id
+    pub fn list(&self, connection_id: &str) -> Vec<SensorClient> {
+        let mut list: Vec<SensorClient> = self
+            .sensors
+            .read()
+            .unwrap()
+            .values()
+            .map(|s| SensorClient {
+                connection_id: connection_id.to_string(),
+                sensor_id: s.reading.sensor_id,
+                addr: s.addr.to_string(),
+                target_id: s.reading.target_id,
+                requested_target_id: s.requested_target_id,
+                lat: s.reading.lat,
+                lon: s.reading.lon,
+                alt: s.reading.alt,
+                temp: s.reading.temp,
+                readings: s.readings,
+                first_seen_ms: s.first_seen_ms,
+                last_seen_ms: s.last_seen_ms,
+                last_response: s.last_response.clone(),
+            })
+            .collect();
+        list.sort_by_key(|s| s.sensor_id);
+        list
+    }
+
+    pub fn contains(&self, sensor_id: u32) -> bool {
+        self.sensors.read().unwrap().contains_key(&sensor_id)
+    }
+
+    pub fn remove(&self, sensor_id: u32) -> bool {
+        self.sensors.write().unwrap().remove(&sensor_id).is_some()
+    }
+}
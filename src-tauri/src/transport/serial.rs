@@ -2,7 +2,6 @@ use async_trait::async_trait;
 use prost::bytes::Buf;
 use prost::bytes::BytesMut;
 use prost::Message;
-use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt, WriteHalf};
 use tokio::sync::Mutex;
@@ -11,7 +10,8 @@ use tokio_serial::{SerialPortBuilderExt, SerialStream};
 use tracing::debug;
 use tracing::{error, info as trace_info};
 
-use crate::storage::file_logger::log_sent_data;
+use crate::transport::decoders::DecoderPipeline;
+use crate::transport::stats::ConnectionStats;
 use crate::transport::{StatableTransport, Transport};
 
 #[derive(Clone)]
@@ -22,8 +22,9 @@ pub struct SerialTransport {
     reader_task: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
     pub last_data: Arc<Mutex<Option<Vec<u8>>>>, // Per-connection last data
     pub notify: Arc<Notify>,                    // Notifies when new data is available
-    pub packet_received_count: Arc<AtomicUsize>,
-    pub packet_sent_count: Arc<AtomicUsize>, // Add packet sent counter
+    pub stats: Arc<ConnectionStats>,
+    /// Framing and decoders; the default keeps the packet resync below
+    pub decoders: Arc<DecoderPipeline>,
 }
 
 impl SerialTransport {
@@ -35,8 +36,8 @@ impl SerialTransport {
             reader_task: Arc::new(Mutex::new(None)),
             last_data: Arc::new(Mutex::new(None)),
             notify: Arc::new(Notify::new()),
-            packet_received_count: Arc::new(AtomicUsize::new(0)),
-            packet_sent_count: Arc::new(AtomicUsize::new(0)),
+            stats: Arc::new(ConnectionStats::new()),
+            decoders: Arc::new(DecoderPipeline::default()),
         }
     }
 
@@ -53,9 +54,6 @@ impl Transport for SerialTransport {
         if let Some(writer) = self.writer.lock().await.as_mut() {
             writer.write_all(&data).await.map_err(|e| e.to_string())?;
             writer.flush().await.map_err(|e| e.to_string())?;
-            log_sent_data(self.name().as_str(), &data);
-            // Increment packet sent counter
-            self.packet_sent_count.fetch_add(1, Ordering::Relaxed);
             Ok(())
         } else {
             Err("Writer not initialized.".to_string())
@@ -78,25 +76,24 @@ impl Transport for SerialTransport {
         self
     }
 
-    fn get_packet_received_count(&self) -> usize {
-        self.packet_received_count.load(Ordering::Relaxed)
+    fn reset_packet_counters(&self) {
+        self.stats.reset();
     }
 
-    fn get_packet_sent_count(&self) -> usize {
-        self.packet_sent_count.load(Ordering::Relaxed)
+    fn stats(&self) -> Option<Arc<ConnectionStats>> {
+        Some(self.stats.clone())
     }
 
-    fn reset_packet_counters(&self) {
-        self.packet_received_count.store(0, Ordering::Relaxed);
-        self.packet_sent_count.store(0, Ordering::Relaxed);
+    fn decoders(&self) -> Option<Arc<DecoderPipeline>> {
+        Some(self.decoders.clone())
     }
 }
 
 impl StatableTransport for SerialTransport {
-    async fn start<F: Message + Default + serde::Serialize>(
+    async fn start<F: Message + Default + serde::Serialize + 'static>(
         &mut self,
         id: String,
-        mut on_packet: impl FnMut(String, F) + Send + 'static,
+        mut on_packet: impl FnMut(String, F, Option<std::net::SocketAddr>) + Send + 'static,
     ) -> Result<(), String> {
         let port = tokio_serial::new(&self.port_name, self.baud_rate)
             .open_native_async()
@@ -109,7 +106,8 @@ impl StatableTransport for SerialTransport {
         let last_data = self.last_data.clone();
         let reader_id = id.clone();
         let notify = self.notify.clone();
-        let packet_received_count = self.packet_received_count.clone();
+        let stats = self.stats.clone();
+        let decoders = self.decoders.clone();
 
         let task = tokio::spawn(async move {
             let mut buffer = BytesMut::with_capacity(4096);
@@ -123,6 +121,22 @@ impl StatableTransport for SerialTransport {
                 match reader.lock().await.as_mut().unwrap().read(&mut buf).await {
                     Ok(n) if n > 0 => {
                         buf.truncate(n);
+
+                        // Configured decoders frame the stream themselves
+                        if !decoders.is_default() {
+                            let decoded = decoders.feed(&reader_id, &buf, None);
+                            for _ in 0..decoded.undecoded {
+                                stats.record_decode_error();
+                            }
+                            for frame in decoded.packets {
+                                if let Ok(packet) = F::decode(&frame[..]) {
+                                    on_packet(reader_id.clone(), packet, None);
+                                    *last_data.lock().await = Some(frame);
+                                    notify.notify_waiters();
+                                }
+                            }
+                            continue;
+                        }
                         buffer.extend_from_slice(&buf);
 
                         trace_info!(
@@ -138,6 +152,7 @@ impl StatableTransport for SerialTransport {
                                 "[serialcom] Buffer overflow on {}, clearing buffer",
                                 reader_id
                             );
+                            stats.record_buffer_overflow();
                             buffer.clear();
                             continue;
                         }
@@ -159,10 +174,7 @@ impl StatableTransport for SerialTransport {
                                             packet_size
                                         );
 
-                                        // Increment packet counter only for successful decodes
-                                        packet_received_count.fetch_add(1, Ordering::Relaxed);
-
-                                        on_packet(reader_id.clone(), packet);
+                                        on_packet(reader_id.clone(), packet, None);
 
                                         // Store the latest raw data for sharing
                                         let mut ld = last_data.lock().await;
@@ -194,6 +206,7 @@ impl StatableTransport for SerialTransport {
                                         processed_bytes,
                                         e
                                     );
+                                    stats.record_decode_error();
 
                                     // If we can't decode, try to find a valid packet boundary
                                     // Look for potential packet start by trying different offsets
@@ -214,11 +227,7 @@ impl StatableTransport for SerialTransport {
                                                     packet_size
                                                 );
 
-                                                // Increment packet counter for successful decodes
-                                                packet_received_count
-                                                    .fetch_add(1, Ordering::Relaxed);
-
-                                                on_packet(reader_id.clone(), packet);
+                                                on_packet(reader_id.clone(), packet, None);
 
                                                 // Store the latest raw data for sharing
                                                 let mut ld = last_data.lock().await;
@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Length of the rolling window used for packets/s and bytes/s
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Rolling traffic statistics for one connection
+#[derive(Debug)]
+pub struct ConnectionStats {
+    rx_packets: AtomicU64,
+    rx_bytes: AtomicU64,
+    tx_packets: AtomicU64,
+    tx_bytes: AtomicU64,
+    decode_errors: AtomicU64,
+    buffer_overflows: AtomicU64,
+    windows: Mutex<StatsWindows>,
+}
+
+#[derive(Debug)]
+struct StatsWindows {
+    started: Instant,
+    rx: VecDeque<(Instant, usize)>,
+    tx: VecDeque<(Instant, usize)>,
+    last_rx: Option<Instant>,
+    last_interval_ms: Option<f64>,
+    /// Smoothed inter-arrival jitter as in RFC 3550
+    jitter_ms: f64,
+    latency_min: Option<Duration>,
+    latency_max: Option<Duration>,
+    latency_total: Duration,
+    latency_count: u64,
+}
+
+impl StatsWindows {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            rx: VecDeque::new(),
+            tx: VecDeque::new(),
+            last_rx: None,
+            last_interval_ms: None,
+            jitter_ms: 0.0,
+            latency_min: None,
+            latency_max: None,
+            latency_total: Duration::ZERO,
+            latency_count: 0,
+        }
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while matches!(self.rx.front(), Some((t, _)) if now.duration_since(*t) > RATE_WINDOW) {
+            self.rx.pop_front();
+        }
+        while matches!(self.tx.front(), Some((t, _)) if now.duration_since(*t) > RATE_WINDOW) {
+            self.tx.pop_front();
+        }
+    }
+}
+
+/// Point-in-time view of `ConnectionStats`, as sent in `statistics_tick`
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ConnectionStatsSnapshot {
+    pub rx_packets: u64,
+    pub rx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_bytes: u64,
+    pub rx_packets_per_sec: f64,
+    pub rx_bytes_per_sec: f64,
+    pub tx_packets_per_sec: f64,
+    pub tx_bytes_per_sec: f64,
+    pub decode_errors: u64,
+    pub buffer_overflows: u64,
+    pub jitter_ms: f64,
+    pub send_latency_min_ms: Option<f64>,
+    pub send_latency_avg_ms: Option<f64>,
+    pub send_latency_max_ms: Option<f64>,
+}
+
+impl Default for ConnectionStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConnectionStats {
+    pub fn new() -> Self {
+        Self {
+            rx_packets: AtomicU64::new(0),
+            rx_bytes: AtomicU64::new(0),
+            tx_packets: AtomicU64::new(0),
+            tx_bytes: AtomicU64::new(0),
+            decode_errors: AtomicU64::new(0),
+            buffer_overflows: AtomicU64::new(0),
+            windows: Mutex::new(StatsWindows::new()),
+        }
+    }
+
+    /// Record a successfully decoded packet of `bytes` bytes
+    pub fn record_rx(&self, bytes: usize) {
+        self.rx_packets.fetch_add(1, Ordering::Relaxed);
+        self.rx_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        let now = Instant::now();
+        let mut w = self.windows.lock().unwrap();
+        if let Some(last) = w.last_rx {
+            let interval_ms = now.duration_since(last).as_secs_f64() * 1000.0;
+            if let Some(prev) = w.last_interval_ms {
+                let d = (interval_ms - prev).abs();
+                w.jitter_ms += (d - w.jitter_ms) / 16.0;
+            }
+            w.last_interval_ms = Some(interval_ms);
+        }
+        w.last_rx = Some(now);
+        w.rx.push_back((now, bytes));
+        w.prune(now);
+    }
+
+    /// Record a sent packet and how long the write took
+    pub fn record_tx(&self, bytes: usize, latency: Duration) {
+        self.tx_packets.fetch_add(1, Ordering::Relaxed);
+        self.tx_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        let now = Instant::now();
+        let mut w = self.windows.lock().unwrap();
+        w.latency_min = Some(w.latency_min.map_or(latency, |min| min.min(latency)));
+        w.latency_max = Some(w.latency_max.map_or(latency, |max| max.max(latency)));
+        w.latency_total += latency;
+        w.latency_count += 1;
+        w.tx.push_back((now, bytes));
+        w.prune(now);
+    }
+
+    pub fn record_decode_error(&self) {
+        self.decode_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_buffer_overflow(&self) {
+        self.buffer_overflows.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        for counter in [
+            &self.rx_packets,
+            &self.rx_bytes,
+            &self.tx_packets,
+            &self.tx_bytes,
+            &self.decode_errors,
+            &self.buffer_overflows,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        *self.windows.lock().unwrap() = StatsWindows::new();
+    }
+
+    pub fn snapshot(&self) -> ConnectionStatsSnapshot {
+        let now = Instant::now();
+        let mut w = self.windows.lock().unwrap();
+        w.prune(now);
+        // Connections younger than the window should not report diluted rates
+        let window_secs = now
+            .duration_since(w.started)
+            .min(RATE_WINDOW)
+            .as_secs_f64()
+            .max(0.001);
+        let rate = |samples: &VecDeque<(Instant, usize)>| {
+            let bytes: usize = samples.iter().map(|(_, b)| b).sum();
+            (
+                samples.len() as f64 / window_secs,
+                bytes as f64 / window_secs,
+            )
+        };
+        let (rx_packets_per_sec, rx_bytes_per_sec) = rate(&w.rx);
+        let (tx_packets_per_sec, tx_bytes_per_sec) = rate(&w.tx);
+        let to_ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        ConnectionStatsSnapshot {
+            rx_packets: self.rx_packets.load(Ordering::Relaxed),
+            rx_bytes: self.rx_bytes.load(Ordering::Relaxed),
+            tx_packets: self.tx_packets.load(Ordering::Relaxed),
+            tx_bytes: self.tx_bytes.load(Ordering::Relaxed),
+            rx_packets_per_sec,
+            rx_bytes_per_sec,
+            tx_packets_per_sec,
+            tx_bytes_per_sec,
+            decode_errors: self.decode_errors.load(Ordering::Relaxed),
+            buffer_overflows: self.buffer_overflows.load(Ordering::Relaxed),
+            jitter_ms: w.jitter_ms,
+            send_latency_min_ms: w.latency_min.map(to_ms),
+            send_latency_avg_ms: (w.latency_count > 0)
+                .then(|| to_ms(w.latency_total) / w.latency_count as f64),
+            send_latency_max_ms: w.latency_max.map(to_ms),
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::RwLock;
+
+use crate::packet::TargetPacket;
+
+/// How long tracks are kept and when they count as stale or lost
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TrackConfig {
+    /// Positions kept per target
+    pub history_len: usize,
+    /// A track without updates for this long is stale
+    pub stale_after_ms: u64,
+    /// A track without updates for this long is lost and `track_lost` is emitted
+    pub lost_after_ms: u64,
+    /// Forget lost tracks after this long without updates, keep them when unset
+    pub drop_after_ms: Option<u64>,
+}
+
+impl Default for TrackConfig {
+    fn default() -> Self {
+        Self {
+            history_len: 256,
+            stale_after_ms: 2_000,
+            lost_after_ms: 10_000,
+            drop_after_ms: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackState {
+    Active,
+    Stale,
+    Lost,
+}
+
+/// One received position of a target
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct TrackPoint {
+    pub lat: f64,
+    pub lon: f64,
+    pub alt: f64,
+    /// Target time from the packet
+    pub time: f64,
+    /// Wall-clock receive time, milliseconds since the Unix epoch
+    pub received_ms: u64,
+}
+
+/// A track as returned by `list_udp_targets`. The latest fix is flattened in, so the
+/// entries still read as `TargetPacket`s.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrackInfo {
+    #[serde(flatten)]
+    pub latest: TargetPacket,
+    pub state: TrackState,
+    pub first_seen_ms: u64,
+    pub last_seen_ms: u64,
+    pub updates: u64,
+    /// Updates per second over the kept history
+    pub update_rate_hz: f64,
+    /// Sender of the latest fix
+    pub source: Option<String>,
+    /// Requested history, oldest first; empty unless asked for
+    #[serde(default)]
+    pub history: Vec<TrackPoint>,
+}
+
+/// Emitted as `track_lost` when a track goes `lost_after_ms` without updates
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrackLostEvent {
+    pub connection_id: String,
+    pub target_id: u32,
+    pub last: TargetPacket,
+    pub last_seen_ms: u64,
+    pub updates: u64,
+}
+
+#[derive(Debug)]
+struct Track {
+    latest: TargetPacket,
+    history: VecDeque<TrackPoint>,
+    first_seen_ms: u64,
+    last_seen_ms: u64,
+    updates: u64,
+    source: Option<SocketAddr>,
+    lost_reported: bool,
+}
+
+impl Track {
+    fn state(&self, config: &TrackConfig, now_ms: u64) -> TrackState {
+        let age = now_ms.saturating_sub(self.last_seen_ms);
+        if age >= config.lost_after_ms {
+            TrackState::Lost
+        } else if age >= config.stale_after_ms {
+            TrackState::Stale
+        } else {
+            TrackState::Active
+        }
+    }
+
+    fn update_rate_hz(&self) -> f64 {
+        match (self.history.front(), self.history.back()) {
+            (Some(first), Some(last)) if last.received_ms > first.received_ms => {
+                (self.history.len() - 1) as f64 * 1000.0
+                    / (last.received_ms - first.received_ms) as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn info(&self, config: &TrackConfig, now_ms: u64, history: Vec<TrackPoint>) -> TrackInfo {
+        TrackInfo {
+            latest: self.latest,
+            state: self.state(config, now_ms),
+            first_seen_ms: self.first_seen_ms,
+            last_seen_ms: self.last_seen_ms,
+            updates: self.updates,
+            update_rate_hz: self.update_rate_hz(),
+            source: self.source.map(|addr| addr.to_string()),
+            history,
+        }
+    }
+}
+
+pub fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis().max(0) as u64
+}
+
+/// Tracks of every target a connection has received, with a bounded position history
+#[derive(Debug, Default)]
+pub struct TrackStore {
+    tracks: RwLock<HashMap<u32, Track>>,
+    config: RwLock<TrackConfig>,
+}
+
+impl TrackStore {
+    pub fn config(&self) -> TrackConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    pub fn set_config(&self, config: TrackConfig) {
+        let history_len = config.history_len.max(1);
+        *self.config.write().unwrap() = config;
+        for track in self.tracks.write().unwrap().values_mut() {
+            while track.history.len() > history_len {
+                track.history.pop_front();
+            }
+        }
+    }
+
+    pub fn update(&self, tp: &TargetPacket, source: Option<SocketAddr>) {
+        let now = now_ms();
+        let history_len = self.config.read().unwrap().history_len.max(1);
+        let mut tracks = self.tracks.write().unwrap();
+        let track = tracks.entry(tp.target_id).or_insert_with(|| Track {
+            latest: *tp,
+            history: VecDeque::with_capacity(history_len.min(1024)),
+            first_seen_ms: now,
+            last_seen_ms: now,
+            updates: 0,
+            source,
+            lost_reported: false,
+        });
+        track.latest = *tp;
+        track.last_seen_ms = now;
+        track.updates += 1;
+        track.source = source;
+        track.lost_reported = false;
+        if track.history.len() >= history_len {
+            track.history.pop_front();
+        }
+        track.history.push_back(TrackPoint {
+            lat: tp.lat,
+            lon: tp.lon,
+            alt: tp.alt,
+            time: tp.time,
+            received_ms: now,
+        });
+    }
+
+    /// Latest fix of a target
+    pub fn latest(&self, target_id: u32) -> Option<TargetPacket> {
+        self.tracks
+            .read()
+            .unwrap()
+            .get(&target_id)
+            .map(|t| t.latest)
+    }
+
+    pub fn target_ids(&self) -> Vec<u32> {
+        self.tracks.read().unwrap().keys().copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every track, each with up to `history_limit` of its most recent positions
+    /// received after `since_ms`
+    pub fn list(&self, history_limit: usize, since_ms: Option<u64>) -> Vec<TrackInfo> {
+        let config = self.config();
+        let now = now_ms();
+        let tracks = self.tracks.read().unwrap();
+        let mut list: Vec<TrackInfo> = tracks
+            .values()
+            .map(|t| t.info(&config, now, select_history(t, history_limit, since_ms)))
+            .collect();
+        list.sort_by_key(|t| t.latest.target_id);
+        list
+    }
+
+    pub fn track(
+        &self,
+        target_id: u32,
+        history_limit: usize,
+        since_ms: Option<u64>,
+    ) -> Option<TrackInfo> {
+        let config = self.config();
+        let tracks = self.tracks.read().unwrap();
+        tracks.get(&target_id).map(|t| {
+            t.info(
+                &config,
+                now_ms(),
+                select_history(t, history_limit, since_ms),
+            )
+        })
+    }
+
+    /// Report tracks that just became lost and drop those past `drop_after_ms`
+    pub fn sweep(&self, connection_id: &str) -> Vec<TrackLostEvent> {
+        let config = self.config();
+        let now = now_ms();
+        let mut lost = Vec::new();
+        let mut tracks = self.tracks.write().unwrap();
+        for (target_id, track) in tracks.iter_mut() {
+            if !track.lost_reported && track.state(&config, now) == TrackState::Lost {
+                track.lost_reported = true;
+                lost.push(TrackLostEvent {
+                    connection_id: connection_id.to_string(),
+                    target_id: *target_id,
+                    last: track.latest,
+                    last_seen_ms: track.last_seen_ms,
+                    updates: track.updates,
+                });
+            }
+        }
+        if let Some(drop_after) = config.drop_after_ms {
+            tracks.retain(|_, t| now.saturating_sub(t.last_seen_ms) < drop_after);
+        }
+        lost
+    }
+
+    pub fn clear(&self) {
+        self.tracks.write().unwrap().clear();
+    }
+}
+
+fn select_history(track: &Track, limit: usize, since_ms: Option<u64>) -> Vec<TrackPoint> {
+    let mut points: Vec<TrackPoint> = track
+        .history
+        .iter()
+        .rev()
+        .filter(|p| !matches!(since_ms, Some(since) if p.received_ms <= since))
+        .take(limit)
+        .copied()
+        .collect();
+    points.reverse();
+    points
+}
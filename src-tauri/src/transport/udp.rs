@@ -1,65 +1,251 @@
 use async_trait::async_trait;
 use prost::Message;
-use tracing::{error, info};
-use std::net::SocketAddr;
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+use std::any::Any;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::UdpSocket;
 use tokio::sync::{oneshot, Mutex};
+use tracing::{error, info};
 
 use crate::packet::{packet::Kind, Packet, TargetPacket};
 use prost::bytes::BytesMut;
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::{HashMap, HashSet};
 use tokio::sync::Notify;
 
+use crate::transport::peers::UdpPeers;
+use crate::transport::decoders::DecoderPipeline;
+use crate::transport::sensors::SensorRegistry;
+use crate::transport::stats::ConnectionStats;
+use crate::transport::tracks::{TrackLostEvent, TrackStore};
 use crate::transport::{StatableTransport, Transport};
 
+/// Socket options for a UDP connection, persisted in `ConnectionInfo`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct UdpOptions {
+    /// IPv4 or IPv6 multicast groups to join
+    #[serde(default)]
+    pub multicast_groups: Vec<String>,
+    /// Interface used for group membership: an IPv4 address for IPv4 groups, an interface
+    /// index for IPv6 groups. The system default when unset.
+    #[serde(default)]
+    pub multicast_interface: Option<String>,
+    /// Multicast TTL (IPv4) or hop limit (IPv6) for outgoing datagrams
+    #[serde(default)]
+    pub multicast_ttl: Option<u32>,
+    /// Whether our own multicast datagrams are looped back to local listeners
+    #[serde(default)]
+    pub multicast_loop: Option<bool>,
+    #[serde(default)]
+    pub broadcast: bool,
+}
+
+impl UdpOptions {
+    /// Let several listeners bind the same port, which multicast receivers rely on
+    fn reuse_addr(&self) -> bool {
+        !self.multicast_groups.is_empty()
+    }
+}
+
+fn parse_group(group: &str) -> Result<IpAddr, String> {
+    let addr: IpAddr = group
+        .parse()
+        .map_err(|e| format!("Invalid multicast group '{}': {}", group, e))?;
+    if !addr.is_multicast() {
+        return Err(format!("{} is not a multicast address", addr));
+    }
+    Ok(addr)
+}
+
 #[derive(Clone)]
 pub struct UdpTransport {
     pub local_addr: SocketAddr,
-    pub remote_addr: Option<SocketAddr>,
+    /// Destinations for `send`; shared so peers can change while the connection runs
+    pub peers: Arc<UdpPeers>,
     pub socket: Arc<UdpSocket>,
     pub running: Arc<Mutex<bool>>,
     pub cancel_tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
-    pub target_data: Arc<Mutex<HashMap<u32, TargetPacket>>>, // Per-connection target data
+    /// Per-connection target tracks with position history
+    pub tracks: Arc<TrackStore>,
+    /// Latest target data per sender, so shares can pick which sources they forward
+    pub source_targets: Arc<Mutex<HashMap<SocketAddr, HashMap<u32, TargetPacket>>>>,
     pub notify: Arc<Notify>, // Notifies when new target data is available
-    pub packet_received_count: Arc<AtomicUsize>,
-    pub packet_sent_count: Arc<AtomicUsize>, // Add packet sent counter
+    /// Text-protocol sensor clients discovered on this socket
+    pub sensors: Arc<SensorRegistry>,
+    /// Framing and decoders applied to datagrams that are not sensor text
+    pub decoders: Arc<DecoderPipeline>,
+    pub stats: Arc<ConnectionStats>,
+    /// Options applied to the socket, kept current as groups are joined and left
+    pub options: Arc<std::sync::Mutex<UdpOptions>>,
 }
 
 impl UdpTransport {
     pub async fn new(local_addr: SocketAddr) -> Result<Self, String> {
-        let socket = UdpSocket::bind(local_addr)
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(Self {
+        Self::with_options(local_addr, UdpOptions::default()).await
+    }
+
+    pub async fn with_options(local_addr: SocketAddr, options: UdpOptions) -> Result<Self, String> {
+        let socket = if options.reuse_addr() {
+            let domain = if local_addr.is_ipv4() {
+                Domain::IPV4
+            } else {
+                Domain::IPV6
+            };
+            let socket =
+                Socket::new(domain, Type::DGRAM, Some(Protocol::UDP)).map_err(|e| e.to_string())?;
+            socket.set_reuse_address(true).map_err(|e| e.to_string())?;
+            socket.bind(&local_addr.into()).map_err(|e| e.to_string())?;
+            socket.set_nonblocking(true).map_err(|e| e.to_string())?;
+            UdpSocket::from_std(socket.into()).map_err(|e| e.to_string())?
+        } else {
+            UdpSocket::bind(local_addr)
+                .await
+                .map_err(|e| e.to_string())?
+        };
+        let transport = Self {
             local_addr,
-            remote_addr: None,
+            peers: Arc::new(UdpPeers::default()),
             socket: Arc::new(socket),
             running: Arc::new(Mutex::new(false)),
             cancel_tx: Arc::new(Mutex::new(None)),
-            target_data: Arc::new(Mutex::new(HashMap::new())),
+            tracks: Arc::new(TrackStore::default()),
+            source_targets: Arc::new(Mutex::new(HashMap::new())),
             notify: Arc::new(Notify::new()),
-            packet_received_count: Arc::new(AtomicUsize::new(0)),
-            packet_sent_count: Arc::new(AtomicUsize::new(0)),
-        })
+            sensors: Arc::new(SensorRegistry::default()),
+            decoders: Arc::new(DecoderPipeline::default()),
+            stats: Arc::new(ConnectionStats::new()),
+            options: Arc::new(std::sync::Mutex::new(UdpOptions {
+                multicast_groups: Vec::new(),
+                ..options.clone()
+            })),
+        };
+        transport.apply_options(&options)?;
+        for group in &options.multicast_groups {
+            transport.join_multicast(group)?;
+        }
+        Ok(transport)
+    }
+
+    fn apply_options(&self, options: &UdpOptions) -> Result<(), String> {
+        let socket = SockRef::from(self.socket.as_ref());
+        let v4 = self.local_addr.is_ipv4();
+        if options.broadcast {
+            self.socket
+                .set_broadcast(true)
+                .map_err(|e| format!("Failed to enable broadcast: {}", e))?;
+        }
+        if let Some(ttl) = options.multicast_ttl {
+            let res = if v4 {
+                self.socket.set_multicast_ttl_v4(ttl)
+            } else {
+                socket.set_multicast_hops_v6(ttl)
+            };
+            res.map_err(|e| format!("Failed to set multicast TTL: {}", e))?;
+        }
+        if let Some(enabled) = options.multicast_loop {
+            let res = if v4 {
+                self.socket.set_multicast_loop_v4(enabled)
+            } else {
+                self.socket.set_multicast_loop_v6(enabled)
+            };
+            res.map_err(|e| format!("Failed to set multicast loopback: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn multicast_interface_v4(&self) -> Result<Ipv4Addr, String> {
+        match &self.options.lock().unwrap().multicast_interface {
+            Some(interface) => interface
+                .parse()
+                .map_err(|e| format!("Invalid IPv4 interface '{}': {}", interface, e)),
+            None => Ok(Ipv4Addr::UNSPECIFIED),
+        }
+    }
+
+    fn multicast_interface_v6(&self) -> Result<u32, String> {
+        match &self.options.lock().unwrap().multicast_interface {
+            Some(interface) => interface
+                .parse()
+                .map_err(|e| format!("Invalid IPv6 interface index '{}': {}", interface, e)),
+            None => Ok(0),
+        }
+    }
+
+    /// First configured peer
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.peers.first()
+    }
+
+    /// Join an IPv4 or IPv6 multicast group on the configured interface
+    pub fn join_multicast(&self, group: &str) -> Result<(), String> {
+        let addr = parse_group(group)?;
+        let res = match addr {
+            IpAddr::V4(addr) => self
+                .socket
+                .join_multicast_v4(addr, self.multicast_interface_v4()?),
+            IpAddr::V6(addr) => self
+                .socket
+                .join_multicast_v6(&addr, self.multicast_interface_v6()?),
+        };
+        res.map_err(|e| format!("Failed to join multicast group {}: {}", addr, e))?;
+        let mut options = self.options.lock().unwrap();
+        if !options.multicast_groups.contains(&addr.to_string()) {
+            options.multicast_groups.push(addr.to_string());
+        }
+        info!("[udp] {} joined multicast group {}", self.local_addr, addr);
+        Ok(())
+    }
+
+    pub fn leave_multicast(&self, group: &str) -> Result<(), String> {
+        let addr = parse_group(group)?;
+        let res = match addr {
+            IpAddr::V4(addr) => self
+                .socket
+                .leave_multicast_v4(addr, self.multicast_interface_v4()?),
+            IpAddr::V6(addr) => self
+                .socket
+                .leave_multicast_v6(&addr, self.multicast_interface_v6()?),
+        };
+        res.map_err(|e| format!("Failed to leave multicast group {}: {}", addr, e))?;
+        self.options
+            .lock()
+            .unwrap()
+            .multicast_groups
+            .retain(|g| g != &addr.to_string());
+        info!("[udp] {} left multicast group {}", self.local_addr, addr);
+        Ok(())
+    }
+
+    /// Sweep the tracks, then forget senders idle past `drop_after_ms` and per-source
+    /// targets whose track or sender is gone
+    pub async fn sweep(&self, connection_id: &str) -> Vec<TrackLostEvent> {
+        let lost = self.tracks.sweep(connection_id);
+        if let Some(drop_after) = self.tracks.config().drop_after_ms {
+            self.peers.prune_senders(Duration::from_millis(drop_after));
+        }
+        let live: HashSet<u32> = self.tracks.target_ids().into_iter().collect();
+        self.source_targets.lock().await.retain(|addr, targets| {
+            targets.retain(|target_id, _| live.contains(target_id));
+            !targets.is_empty() && self.peers.is_known_sender(*addr)
+        });
+        lost
     }
 }
 
 #[async_trait]
 impl Transport for UdpTransport {
     async fn send(&self, data: Vec<u8>) -> Result<(), String> {
-        if let Some(addr) = self.remote_addr {
-            self.socket
-                .send_to(&data, addr)
-                .await
-                .map_err(|e| e.to_string())?;
-            // Increment packet sent counter
-            self.packet_sent_count.fetch_add(1, Ordering::Relaxed);
-            Ok(())
-        } else {
-            Err("Remote address not set".to_string())
-        }
+        self.peers
+            .fan_out(&data, |addr| self.socket.send_to(&data, addr))
+            .await
+    }
+
+    async fn send_to_addr(&self, addr: SocketAddr, data: Vec<u8>) -> Result<(), String> {
+        self.peers
+            .send_one(addr, &data, self.socket.send_to(&data, addr))
+            .await
     }
 
     async fn stop(&self) {
@@ -83,31 +269,62 @@ impl Transport for UdpTransport {
         self
     }
 
-    fn get_packet_received_count(&self) -> usize {
-        self.packet_received_count.load(Ordering::Relaxed)
+    fn reset_packet_counters(&self) {
+        self.stats.reset();
     }
 
-    fn get_packet_sent_count(&self) -> usize {
-        self.packet_sent_count.load(Ordering::Relaxed)
+    fn stats(&self) -> Option<Arc<ConnectionStats>> {
+        Some(self.stats.clone())
     }
 
-    fn reset_packet_counters(&self) {
-        self.packet_received_count.store(0, Ordering::Relaxed);
-        self.packet_sent_count.store(0, Ordering::Relaxed);
+    fn decoders(&self) -> Option<Arc<DecoderPipeline>> {
+        Some(self.decoders.clone())
     }
 }
 
+/// If `packet` is a TargetPacket or TargetPacketList, update the connection's tracks
+/// and the table of targets received from `source`
+async fn update_target_data(
+    tracks: &TrackStore,
+    source_targets: &Mutex<HashMap<SocketAddr, HashMap<u32, TargetPacket>>>,
+    notify: &Notify,
+    source: SocketAddr,
+    packet: &Packet,
+) {
+    let targets: &[TargetPacket] = match &packet.kind {
+        Some(Kind::TargetPacket(tp)) => std::slice::from_ref(tp),
+        Some(Kind::TargetPacketList(tpl)) => &tpl.packets,
+        _ => return,
+    };
+    if targets.is_empty() {
+        return;
+    }
+    {
+        let mut by_source = source_targets.lock().await;
+        let from_source = by_source.entry(source).or_default();
+        for tp in targets {
+            tracks.update(tp, Some(source));
+            from_source.insert(tp.target_id, *tp);
+        }
+    }
+    notify.notify_waiters();
+}
+
 impl StatableTransport for UdpTransport {
-    async fn start<F: Message + Default + serde::Serialize>(
+    async fn start<F: Message + Default + serde::Serialize + 'static>(
         &mut self,
         id: String,
-        mut on_packet: impl FnMut(String, F) + Send + 'static,
+        mut on_packet: impl FnMut(String, F, Option<SocketAddr>) + Send + 'static,
     ) -> Result<(), String> {
         let socket = self.socket.clone();
         let running = self.running.clone();
-        let target_data = self.target_data.clone();
+        let tracks = self.tracks.clone();
+        let source_targets = self.source_targets.clone();
         let notify = self.notify.clone();
-        let packet_received_count = self.packet_received_count.clone();
+        let stats = self.stats.clone();
+        let peers = self.peers.clone();
+        let sensors = self.sensors.clone();
+        let decoders = self.decoders.clone();
         *running.lock().await = true;
         let local_addr = self.local_addr;
         let id_clone = id.clone();
@@ -128,34 +345,40 @@ impl StatableTransport for UdpTransport {
                             Ok((n, addr)) => {
                                 buf.truncate(n);
                                 info!("[udp] Received {} bytes from {}", n, addr);
-                                // Try to decode as Packet (for TargetPacket/TargetPacketList)
-                                if let Ok(packet) = Packet::decode(&buf[..]) {
-                                    // If it's a TargetPacket or TargetPacketList, update per-connection target_data
-                                    let mut td = target_data.lock().await;
-                                    match &packet.kind {
-                                        Some(Kind::TargetPacket(tp)) => {
-                                            td.insert(tp.target_id, tp.clone());
-                                            notify.notify_waiters();
-                                        },
-                                        Some(Kind::TargetPacketList(tpl)) => {
-                                            for tp in &tpl.packets {
-                                                td.insert(tp.target_id, tp.clone());
-                                                notify.notify_waiters();
+                                peers.record_rx(addr, n);
+                                // Sensor client text is not a packet
+                                if sensors.observe(addr, &buf[..]) {
+                                    buf.clear();
+                                    buf.resize(65535, 0);
+                                    continue;
+                                }
+                                // Configured decoders report their own payloads and hand
+                                // back the frames that are packets
+                                let frames = if decoders.is_default() {
+                                    vec![buf[..].to_vec()]
+                                } else {
+                                    let decoded = decoders.feed(&id_clone, &buf[..], Some(addr));
+                                    for _ in 0..decoded.undecoded {
+                                        stats.record_decode_error();
+                                    }
+                                    decoded.packets
+                                };
+                                for frame in frames {
+                                    // Decode each frame once; when F is Packet the same value
+                                    // feeds the per-connection target data
+                                    match F::decode(&frame[..]) {
+                                        Ok(packet) => {
+                                            let any_packet: &dyn Any = &packet;
+                                            if let Some(tracked) = any_packet.downcast_ref::<Packet>() {
+                                                update_target_data(&tracks, &source_targets, &notify, addr, tracked).await;
+                                            } else if let Ok(tracked) = Packet::decode(&frame[..]) {
+                                                update_target_data(&tracks, &source_targets, &notify, addr, &tracked).await;
                                             }
-                                        },
-                                        _ => {}
+                                            on_packet(id_clone.clone(), packet, Some(addr));
+                                        }
+                                        Err(_) => stats.record_decode_error(),
                                     }
                                 }
-                                // Also call the original on_packet for generic F
-                                if let Ok(packet) = F::decode(&buf[..]) {
-                                    on_packet(id_clone.clone(), packet);
-                                }
-
-                                // Increment packet counter for successful receives
-                                // Note: We increment for any successful decode, whether Packet or F
-                                if Packet::decode(&buf[..]).is_ok() || F::decode(&buf[..]).is_ok() {
-                                    packet_received_count.fetch_add(1, Ordering::Relaxed);
-                                }
                                 buf.clear();
                                 buf.resize(65535, 0); // Ensure buffer is always the right size
                             }
@@ -0,0 +1,427 @@
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{error, info, warn};
+
+use crate::transport::decoders::DecoderPipeline;
+use crate::transport::stats::ConnectionStats;
+use crate::transport::{StatableTransport, Transport};
+
+/// Endpoint `add_sub` connects to when none is given, set with `init_zmq`
+pub static DEFAULT_SUB_ENDPOINT: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("tcp://127.0.0.1:5556".to_string()));
+
+/// How long the socket thread waits for traffic before checking for commands again
+const POLL_INTERVAL_MS: i64 = 20;
+
+/// Shared by every socket so `inproc://` endpoints of different connections can reach each other
+static CONTEXT: Lazy<::zmq::Context> = Lazy::new(::zmq::Context::new);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ZmqPattern {
+    #[default]
+    Sub,
+    Pub,
+    Req,
+    Rep,
+    Push,
+    Pull,
+}
+
+impl ZmqPattern {
+    fn socket_type(self) -> ::zmq::SocketType {
+        match self {
+            ZmqPattern::Sub => ::zmq::SUB,
+            ZmqPattern::Pub => ::zmq::PUB,
+            ZmqPattern::Req => ::zmq::REQ,
+            ZmqPattern::Rep => ::zmq::REP,
+            ZmqPattern::Push => ::zmq::PUSH,
+            ZmqPattern::Pull => ::zmq::PULL,
+        }
+    }
+
+    /// Servers of the pattern bind unless told otherwise
+    fn binds_by_default(self) -> bool {
+        matches!(self, ZmqPattern::Pub | ZmqPattern::Rep | ZmqPattern::Pull)
+    }
+
+    fn can_send(self) -> bool {
+        !matches!(self, ZmqPattern::Sub | ZmqPattern::Pull)
+    }
+
+    fn can_receive(self) -> bool {
+        !matches!(self, ZmqPattern::Pub | ZmqPattern::Push)
+    }
+}
+
+/// Socket settings of a ZeroMQ connection, persisted in `ConnectionInfo`
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ZmqOptions {
+    #[serde(default)]
+    pub pattern: ZmqPattern,
+    /// e.g. `tcp://127.0.0.1:5556`
+    pub endpoint: String,
+    /// Bind instead of connect; PUB, REP and PULL bind by default
+    #[serde(default)]
+    pub bind: Option<bool>,
+    /// SUB topic prefixes; an empty string subscribes to everything
+    #[serde(default)]
+    pub topics: Vec<String>,
+    /// PUB only: sent as the first frame of every message
+    #[serde(default)]
+    pub publish_topic: Option<String>,
+    /// REP only: reply with an empty frame if nothing was sent within this many ms of a request
+    #[serde(default)]
+    pub reply_timeout_ms: Option<u64>,
+}
+
+impl ZmqOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        let scheme_ok = ["tcp://", "ipc://", "inproc://", "pgm://", "epgm://"]
+            .iter()
+            .any(|scheme| self.endpoint.starts_with(scheme));
+        if !scheme_ok {
+            return Err(format!("Invalid ZeroMQ endpoint: {}", self.endpoint));
+        }
+        Ok(())
+    }
+
+    fn binds(&self) -> bool {
+        self.bind.unwrap_or(self.pattern.binds_by_default())
+    }
+}
+
+/// Called with the topic (empty for single-frame messages) and payload of every message
+pub type MessageHook = Arc<dyn Fn(&str, &[u8]) + Send + Sync>;
+
+enum Command {
+    Send(Vec<u8>, oneshot::Sender<Result<(), String>>),
+    Subscribe(String),
+    Unsubscribe(String),
+}
+
+/// ZeroMQ socket owned by a dedicated thread, as `zmq::Socket` is not `Sync`
+pub struct ZmqTransport {
+    options: Arc<Mutex<ZmqOptions>>,
+    commands: Mutex<Option<mpsc::Sender<Command>>>,
+    running: Arc<AtomicBool>,
+    on_message: Option<MessageHook>,
+    pub stats: Arc<ConnectionStats>,
+    /// Framing and decoders applied to message payloads
+    pub decoders: Arc<DecoderPipeline>,
+}
+
+impl ZmqTransport {
+    pub fn new(options: ZmqOptions) -> Result<Self, String> {
+        options.validate()?;
+        Ok(Self {
+            options: Arc::new(Mutex::new(options)),
+            commands: Mutex::new(None),
+            running: Arc::new(AtomicBool::new(false)),
+            on_message: None,
+            stats: Arc::new(ConnectionStats::new()),
+            decoders: Arc::new(DecoderPipeline::default()),
+        })
+    }
+
+    pub fn on_message(mut self, hook: MessageHook) -> Self {
+        self.on_message = Some(hook);
+        self
+    }
+
+    pub fn options(&self) -> ZmqOptions {
+        self.options.lock().unwrap().clone()
+    }
+
+    pub fn pattern(&self) -> ZmqPattern {
+        self.options.lock().unwrap().pattern
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    fn command(&self, command: Command) -> Result<(), String> {
+        self.commands
+            .lock()
+            .unwrap()
+            .as_ref()
+            .ok_or("ZeroMQ socket is not running")?
+            .send(command)
+            .map_err(|_| "ZeroMQ socket thread has stopped".to_string())
+    }
+
+    /// Add a topic filter to a running SUB socket
+    pub fn subscribe(&self, topic: &str) -> Result<(), String> {
+        if self.pattern() != ZmqPattern::Sub {
+            return Err("Only SUB sockets have topics".to_string());
+        }
+        {
+            let mut options = self.options.lock().unwrap();
+            if options.topics.iter().any(|t| t == topic) {
+                return Ok(());
+            }
+            options.topics.push(topic.to_string());
+        }
+        self.command(Command::Subscribe(topic.to_string()))
+    }
+
+    pub fn unsubscribe(&self, topic: &str) -> Result<(), String> {
+        if self.pattern() != ZmqPattern::Sub {
+            return Err("Only SUB sockets have topics".to_string());
+        }
+        self.options.lock().unwrap().topics.retain(|t| t != topic);
+        self.command(Command::Unsubscribe(topic.to_string()))
+    }
+
+    fn open_socket(options: &ZmqOptions) -> Result<::zmq::Socket, String> {
+        let socket = CONTEXT
+            .socket(options.pattern.socket_type())
+            .map_err(|e| e.to_string())?;
+        socket.set_linger(0).map_err(|e| e.to_string())?;
+        match options.pattern {
+            ZmqPattern::Sub => {
+                for topic in &options.topics {
+                    socket
+                        .set_subscribe(topic.as_bytes())
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+            ZmqPattern::Req => {
+                // Allow a new request when the previous reply never came
+                socket.set_req_relaxed(true).map_err(|e| e.to_string())?;
+                socket.set_req_correlate(true).map_err(|e| e.to_string())?;
+            }
+            _ => {}
+        }
+        if options.binds() {
+            socket
+                .bind(&options.endpoint)
+                .map_err(|e| format!("Failed to bind {}: {}", options.endpoint, e))?;
+        } else {
+            socket
+                .connect(&options.endpoint)
+                .map_err(|e| format!("Failed to connect {}: {}", options.endpoint, e))?;
+        }
+        Ok(socket)
+    }
+}
+
+/// Send without blocking, so a socket with no peer ready cannot wedge its thread
+fn send_now(socket: &::zmq::Socket, topic: Option<&str>, data: &[u8]) -> Result<(), String> {
+    let sent = match topic {
+        Some(topic) => socket.send_multipart([topic.as_bytes(), data], ::zmq::DONTWAIT),
+        None => socket.send(data, ::zmq::DONTWAIT),
+    };
+    sent.map_err(|e| match e {
+        ::zmq::Error::EAGAIN => "No ZeroMQ peer is ready to take the message".to_string(),
+        e => e.to_string(),
+    })
+}
+
+/// Split a multipart message into topic and payload
+fn topic_and_payload(mut frames: Vec<Vec<u8>>) -> (String, Vec<u8>) {
+    match frames.len() {
+        0 => (String::new(), Vec::new()),
+        1 => (String::new(), frames.remove(0)),
+        _ => {
+            let payload = frames.pop().unwrap_or_default();
+            (String::from_utf8_lossy(&frames[0]).to_string(), payload)
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for ZmqTransport {
+    async fn send(&self, data: Vec<u8>) -> Result<(), String> {
+        let pattern = self.pattern();
+        if !pattern.can_send() {
+            return Err(format!("Cannot send on a {:?} socket", pattern));
+        }
+        let (tx, rx) = oneshot::channel();
+        self.command(Command::Send(data, tx))?;
+        rx.await
+            .map_err(|_| "ZeroMQ socket thread has stopped".to_string())?
+    }
+
+    async fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+        // Dropping the sender wakes a thread waiting for commands
+        self.commands.lock().unwrap().take();
+    }
+
+    fn name(&self) -> String {
+        let options = self.options.lock().unwrap();
+        format!("ZMQ({:?} {})", options.pattern, options.endpoint)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn reset_packet_counters(&self) {
+        self.stats.reset();
+    }
+
+    fn stats(&self) -> Option<Arc<ConnectionStats>> {
+        Some(self.stats.clone())
+    }
+
+    fn decoders(&self) -> Option<Arc<DecoderPipeline>> {
+        Some(self.decoders.clone())
+    }
+}
+
+impl StatableTransport for ZmqTransport {
+    async fn start<F: Message + Default + serde::Serialize + 'static>(
+        &mut self,
+        id: String,
+        mut on_packet: impl FnMut(String, F, Option<std::net::SocketAddr>) + Send + 'static,
+    ) -> Result<(), String> {
+        let options = self.options();
+        let socket = Self::open_socket(&options)?;
+        info!("[zmq] {} {:?} on {}", id, options.pattern, options.endpoint);
+
+        let (tx, rx) = mpsc::channel::<Command>();
+        *self.commands.lock().unwrap() = Some(tx);
+        self.running.store(true, Ordering::Relaxed);
+        let running = self.running.clone();
+        let stats = self.stats.clone();
+        let decoders = self.decoders.clone();
+        let on_message = self.on_message.clone();
+        let pattern = options.pattern;
+        let reply_timeout = options.reply_timeout_ms.map(Duration::from_millis);
+
+        std::thread::Builder::new()
+            .name(format!("zmq-{}", id))
+            .spawn(move || {
+                // REP: when the pending request arrived
+                let mut pending_request: Option<Instant> = None;
+                while running.load(Ordering::Relaxed) {
+                    loop {
+                        let command = if pattern.can_receive() {
+                            match rx.try_recv() {
+                                Ok(command) => command,
+                                Err(mpsc::TryRecvError::Empty) => break,
+                                Err(mpsc::TryRecvError::Disconnected) => return,
+                            }
+                        } else {
+                            match rx.recv_timeout(Duration::from_millis(100)) {
+                                Ok(command) => command,
+                                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                            }
+                        };
+                        match command {
+                            Command::Send(data, done) => {
+                                let result =
+                                    if pattern == ZmqPattern::Rep && pending_request.is_none() {
+                                        Err("No request to reply to".to_string())
+                                    } else {
+                                        let topic = match pattern {
+                                            ZmqPattern::Pub => options.publish_topic.as_deref(),
+                                            _ => None,
+                                        };
+                                        let sent = send_now(&socket, topic, &data);
+                                        if sent.is_ok() {
+                                            pending_request = None;
+                                        }
+                                        sent
+                                    };
+                                let _ = done.send(result);
+                            }
+                            Command::Subscribe(topic) => {
+                                if let Err(e) = socket.set_subscribe(topic.as_bytes()) {
+                                    error!("[zmq] Subscribe to '{}' failed: {}", topic, e);
+                                }
+                            }
+                            Command::Unsubscribe(topic) => {
+                                if let Err(e) = socket.set_unsubscribe(topic.as_bytes()) {
+                                    error!("[zmq] Unsubscribe from '{}' failed: {}", topic, e);
+                                }
+                            }
+                        }
+                    }
+
+                    if let (Some(since), Some(timeout)) = (pending_request, reply_timeout) {
+                        if since.elapsed() >= timeout {
+                            warn!(
+                                "[zmq] No reply sent within {:?}, sending an empty one",
+                                timeout
+                            );
+                            if let Err(e) = send_now(&socket, None, &[]) {
+                                error!("[zmq] Empty reply failed: {}", e);
+                            }
+                            pending_request = None;
+                        }
+                    }
+                    // A REP socket cannot take another request before replying
+                    if !pattern.can_receive() || pending_request.is_some() {
+                        if pending_request.is_some() {
+                            std::thread::sleep(Duration::from_millis(POLL_INTERVAL_MS as u64));
+                        }
+                        continue;
+                    }
+
+                    match socket.poll(::zmq::POLLIN, POLL_INTERVAL_MS) {
+                        Ok(0) => continue,
+                        Ok(_) => {}
+                        Err(e) => {
+                            error!("[zmq] Poll error: {}", e);
+                            continue;
+                        }
+                    }
+                    let frames = match socket.recv_multipart(::zmq::DONTWAIT) {
+                        Ok(frames) => frames,
+                        Err(::zmq::Error::EAGAIN) => continue,
+                        Err(e) => {
+                            error!("[zmq] Receive error: {}", e);
+                            continue;
+                        }
+                    };
+                    if pattern == ZmqPattern::Rep {
+                        pending_request = Some(Instant::now());
+                    }
+                    let (topic, payload) = topic_and_payload(frames);
+                    if let Some(hook) = &on_message {
+                        hook(&topic, &payload);
+                    }
+                    let frames = if decoders.is_default() {
+                        vec![payload]
+                    } else {
+                        let decoded = decoders.feed(&id, &payload, None);
+                        for _ in 0..decoded.undecoded {
+                            stats.record_decode_error();
+                        }
+                        decoded.packets
+                    };
+                    for frame in frames {
+                        match F::decode(frame.as_slice()) {
+                            Ok(packet) => on_packet(id.clone(), packet, None),
+                            Err(_) => stats.record_decode_error(),
+                        }
+                    }
+                }
+                // Fail sends queued after the socket stopped instead of leaving them waiting
+                while let Ok(command) = rx.try_recv() {
+                    if let Command::Send(_, done) = command {
+                        let _ = done.send(Err("ZeroMQ socket is not running".to_string()));
+                    }
+                }
+                info!("[zmq] Socket thread for {} stopped", id);
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
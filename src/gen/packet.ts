@@ -132,7 +132,11 @@ export interface PacketPing {
 
 export interface SerialPacketEvent {
   id: string;
-  packet: Packet | undefined;
+  packet:
+    | Packet
+    | undefined;
+  /** Sender address for UDP connections, empty otherwise */
+  source: string;
 }
 
 export interface TargetPacket {
@@ -2120,7 +2124,7 @@ export const PacketPing: MessageFns<PacketPing> = {
 };
 
 function createBaseSerialPacketEvent(): SerialPacketEvent {
-  return { id: "", packet: undefined };
+  return { id: "", packet: undefined, source: "" };
 }
 
 export const SerialPacketEvent: MessageFns<SerialPacketEvent> = {
@@ -2131,6 +2135,9 @@ export const SerialPacketEvent: MessageFns<SerialPacketEvent> = {
     if (message.packet !== undefined) {
       Packet.encode(message.packet, writer.uint32(18).fork()).join();
     }
+    if (message.source !== "") {
+      writer.uint32(26).string(message.source);
+    }
     return writer;
   },
 
@@ -2157,6 +2164,14 @@ export const SerialPacketEvent: MessageFns<SerialPacketEvent> = {
           message.packet = Packet.decode(reader, reader.uint32());
           continue;
         }
+        case 3: {
+          if (tag !== 26) {
+            break;
+          }
+
+          message.source = reader.string();
+          continue;
+        }
       }
       if ((tag & 7) === 4 || tag === 0) {
         break;
@@ -2170,6 +2185,7 @@ export const SerialPacketEvent: MessageFns<SerialPacketEvent> = {
     return {
       id: isSet(object.id) ? globalThis.String(object.id) : "",
       packet: isSet(object.packet) ? Packet.fromJSON(object.packet) : undefined,
+      source: isSet(object.source) ? globalThis.String(object.source) : "",
     };
   },
 
@@ -2181,6 +2197,9 @@ export const SerialPacketEvent: MessageFns<SerialPacketEvent> = {
     if (message.packet !== undefined) {
       obj.packet = Packet.toJSON(message.packet);
     }
+    if (message.source !== "") {
+      obj.source = message.source;
+    }
     return obj;
   },
 
@@ -2193,6 +2212,7 @@ export const SerialPacketEvent: MessageFns<SerialPacketEvent> = {
     message.packet = (object.packet !== undefined && object.packet !== null)
       ? Packet.fromPartial(object.packet)
       : undefined;
+    message.source = object.source ?? "";
     return message;
   },
 };
@@ -172,6 +172,6 @@ export interface PacketDisplayProps extends BaseComponentProps {
 // Utility Types
 export type PacketType = "header" | "payload" | "command" | "state" | "TargetPacket" | "TargetPacketList" | "other"
 
-export type ConnectionType = "serial" | "udp" | "simulation"
+export type ConnectionType = "serial" | "udp" | "zmq" | "simulation"
 
 export type ShareStatus = "active" | "stopped" | "error"
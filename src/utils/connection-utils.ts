@@ -14,6 +14,10 @@ import type { Connection, ConnectionType } from "@/types"
 export function getConnectionType(connection: Connection | string): ConnectionType {
   const name = typeof connection === "string" ? connection : connection.name
 
+  if (name.startsWith("ZMQ(")) {
+    return "zmq"
+  }
+
   if (name.toLowerCase().includes("udp") || name.includes("127.0.0.1") || name.includes("0.0.0.0")) {
     return "udp"
   }
@@ -155,13 +159,14 @@ export function formatConnectionName(connection: Connection, maxLength = 30): st
  * @example
  * ```typescript
  * const grouped = groupConnectionsByType(connections)
- * // Returns: { serial: [...], udp: [...], simulation: [...] }
+ * // Returns: { serial: [...], udp: [...], zmq: [...], simulation: [...] }
  * ```
  */
 export function groupConnectionsByType(connections: Connection[]): Record<ConnectionType, Connection[]> {
   const grouped: Record<ConnectionType, Connection[]> = {
     serial: [],
     udp: [],
+    zmq: [],
     simulation: [],
   }
 